@@ -0,0 +1,19 @@
+use near_contract_standards::sim::{FtConformance, SetupCall};
+use near_sdk::serde_json::json;
+
+near_sdk_sim::lazy_static_include::lazy_static_include_bytes! {
+    FT_WASM_BYTES => "res/fungible_token.wasm",
+    DEFI_WASM_BYTES => "res/defi.wasm",
+}
+
+#[test]
+fn ft_conformance() {
+    FtConformance::new(&FT_WASM_BYTES, |owner_id| {
+        SetupCall::new(
+            "new_default_meta",
+            json!({ "owner_id": owner_id, "total_supply": "1000000000000000" }),
+        )
+    })
+    .with_receiver(&DEFI_WASM_BYTES)
+    .run();
+}
@@ -1,3 +1,4 @@
+mod conformance;
 mod no_macros;
 mod utils;
 mod with_macros;
@@ -0,0 +1,61 @@
+/*!
+A stub non-fungible token whose owners pay for their storage with NEP-145 storage deposits, for
+simulation testing the storage management of `NonFungibleToken`.
+*/
+use near_contract_standards::non_fungible_token::{NonFungibleToken, Token, TokenId};
+use near_contract_standards::storage_management::StorageBalances;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::{
+    env, near_bindgen, require, AccountId, BorshStorageKey, PanicOnDefault, Promise, PromiseOrValue,
+};
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    tokens: NonFungibleToken,
+    storage_balances: StorageBalances,
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey {
+    NonFungibleToken,
+    Enumeration,
+    Approval,
+    StorageBalances,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId) -> Self {
+        require!(!env::state_exists(), "Already initialized");
+        Self {
+            tokens: NonFungibleToken::new(
+                StorageKey::NonFungibleToken,
+                owner_id,
+                None::<StorageKey>,
+                Some(StorageKey::Enumeration),
+                Some(StorageKey::Approval),
+            ),
+            storage_balances: StorageBalances::new(StorageKey::StorageBalances, None),
+        }
+    }
+
+    /// Mints a token for `token_owner_id`, which pays for its entry in the tokens per owner with
+    /// its storage balance. The attached deposit pays for the token itself.
+    #[payable]
+    pub fn nft_mint(&mut self, token_id: TokenId, token_owner_id: AccountId) -> Token {
+        assert_eq!(env::predecessor_account_id(), self.tokens.owner_id, "Unauthorized");
+        self.tokens.internal_mint_with_storage(
+            token_id,
+            token_owner_id,
+            None,
+            Some(&mut self.storage_balances),
+        )
+    }
+}
+
+near_contract_standards::impl_non_fungible_token_core!(Contract, tokens, storage_balances);
+near_contract_standards::impl_non_fungible_token_approval!(Contract, tokens, storage_balances);
+near_contract_standards::impl_non_fungible_token_enumeration!(Contract, tokens);
+near_contract_standards::impl_non_fungible_token_storage!(Contract, tokens, storage_balances);
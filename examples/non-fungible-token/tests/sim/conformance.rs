@@ -0,0 +1,29 @@
+use near_contract_standards::sim::{NftConformance, SetupCall};
+use near_sdk::serde_json::json;
+use near_sdk_sim::to_yocto;
+
+near_sdk_sim::lazy_static_include::lazy_static_include_bytes! {
+    NFT_WASM_BYTES => "res/non_fungible_token.wasm",
+    TOKEN_RECEIVER_WASM_BYTES => "res/token_receiver.wasm",
+}
+
+#[test]
+fn nft_conformance() {
+    NftConformance::new(
+        &NFT_WASM_BYTES,
+        |owner_id| SetupCall::new("new_default_meta", json!({ "owner_id": owner_id })),
+        |token_id, receiver_id| {
+            SetupCall::new(
+                "nft_mint",
+                json!({
+                    "token_id": token_id,
+                    "token_owner_id": receiver_id,
+                    "token_metadata": { "title": format!("Token {}", token_id) },
+                }),
+            )
+            .with_deposit(to_yocto("0.1"))
+        },
+    )
+    .with_receiver(&TOKEN_RECEIVER_WASM_BYTES)
+    .run();
+}
@@ -1,4 +1,6 @@
+mod conformance;
 mod test_approval;
 mod test_core;
 mod test_enumeration;
+mod test_storage;
 mod utils;
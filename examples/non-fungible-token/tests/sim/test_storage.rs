@@ -0,0 +1,154 @@
+use near_contract_standards::non_fungible_token::Token;
+use near_contract_standards::sim::{NftConformance, SetupCall};
+use near_sdk::serde_json::{json, Value};
+use near_sdk_sim::{
+    compile_project, init_simulator, to_yocto, ExecutionResult, UserAccount, DEFAULT_GAS,
+    STORAGE_AMOUNT,
+};
+
+const TOKEN_ID: &str = "0";
+
+/// Deploys the NFT with storage balances and the token receiver, registers alice and the
+/// receiver and mints a token for alice. Returns the root, the NFT, alice and the receiver.
+fn init() -> (UserAccount, UserAccount, UserAccount, UserAccount) {
+    let root = init_simulator(None);
+    let nft = root.deploy_and_init(
+        &compile_project("test-storage-nft"),
+        "nft".parse().unwrap(),
+        "new",
+        json!({ "owner_id": root.account_id() }).to_string().as_bytes(),
+        STORAGE_AMOUNT,
+        DEFAULT_GAS,
+    );
+    let receiver = root.deploy_and_init(
+        &compile_project("test-token-receiver"),
+        "token-receiver".parse().unwrap(),
+        "new",
+        json!({ "non_fungible_token_account_id": nft.account_id() }).to_string().as_bytes(),
+        STORAGE_AMOUNT,
+        DEFAULT_GAS,
+    );
+
+    let alice = root.create_user("alice".parse().unwrap(), to_yocto("100"));
+    for account_id in [alice.account_id(), receiver.account_id()] {
+        root.call(
+            nft.account_id(),
+            "storage_deposit",
+            json!({ "account_id": account_id }).to_string().as_bytes(),
+            DEFAULT_GAS,
+            to_yocto("0.1"),
+        )
+        .assert_success();
+    }
+    root.call(
+        nft.account_id(),
+        "nft_mint",
+        json!({ "token_id": TOKEN_ID, "token_owner_id": alice.account_id() })
+            .to_string()
+            .as_bytes(),
+        DEFAULT_GAS,
+        to_yocto("0.1"),
+    )
+    .assert_success();
+    (root, nft, alice, receiver)
+}
+
+fn owner_of(nft: &UserAccount) -> String {
+    let args = json!({ "token_id": TOKEN_ID }).to_string();
+    let token: Token = nft.view(nft.account_id(), "nft_token", args.as_bytes()).unwrap_json();
+    token.owner_id.to_string()
+}
+
+fn storage_balance_of(nft: &UserAccount, account: &UserAccount) -> Value {
+    let args = json!({ "account_id": account.account_id() }).to_string();
+    nft.view(nft.account_id(), "storage_balance_of", args.as_bytes()).unwrap_json()
+}
+
+/// Alice sends the token to the receiver, which returns it, and in the same transaction
+/// calls `then` on the NFT before the transfer is resolved.
+fn transfer_call_then(
+    nft: &UserAccount,
+    alice: &UserAccount,
+    receiver: &UserAccount,
+    method: &str,
+) -> ExecutionResult {
+    let args = json!({
+        "receiver_id": receiver.account_id(),
+        "token_id": TOKEN_ID,
+        "msg": "return-it-now",
+    });
+    alice
+        .create_transaction(nft.account_id())
+        .function_call(
+            "nft_transfer_call".to_string(),
+            args.to_string().into_bytes(),
+            DEFAULT_GAS / 2,
+            1,
+        )
+        .function_call(method.to_string(), b"{}".to_vec(), DEFAULT_GAS / 4, 1)
+        .submit()
+}
+
+#[test]
+fn simulate_return_to_owner_after_storage_withdraw() {
+    let (_, nft, alice, receiver) = init();
+
+    let outcome = transfer_call_then(&nft, &alice, &receiver, "storage_withdraw");
+    outcome.assert_success();
+    assert!(outcome.promise_errors().is_empty(), "{}", outcome.receipt_tree());
+
+    assert_eq!(owner_of(&nft), alice.account_id().to_string());
+    // The contract pays for the returned token, the withdrawn balance isn't charged again.
+    assert_eq!(storage_balance_of(&nft, &alice)["available"], "0");
+}
+
+#[test]
+fn simulate_return_to_owner_after_storage_unregister() {
+    let (_, nft, alice, receiver) = init();
+
+    let outcome = transfer_call_then(&nft, &alice, &receiver, "storage_unregister");
+    outcome.assert_success();
+    assert!(outcome.promise_errors().is_empty(), "{}", outcome.receipt_tree());
+
+    assert_eq!(owner_of(&nft), alice.account_id().to_string());
+    assert!(storage_balance_of(&nft, &alice).is_null());
+}
+
+#[test]
+fn simulate_forced_storage_unregister_burns_tokens() {
+    let (_, nft, alice, _) = init();
+
+    alice
+        .call(
+            nft.account_id(),
+            "storage_unregister",
+            json!({ "force": true }).to_string().as_bytes(),
+            DEFAULT_GAS,
+            1,
+        )
+        .assert_success();
+    assert!(storage_balance_of(&nft, &alice).is_null());
+    let args = json!({ "token_id": TOKEN_ID }).to_string();
+    let token: Option<Token> =
+        nft.view(nft.account_id(), "nft_token", args.as_bytes()).unwrap_json();
+    assert!(token.is_none());
+}
+
+#[test]
+fn storage_nft_conformance() {
+    let conformance = NftConformance::new(
+        &compile_project("test-storage-nft"),
+        |owner_id| SetupCall::new("new", json!({ "owner_id": owner_id })),
+        |token_id, receiver_id| {
+            SetupCall::new(
+                "nft_mint",
+                json!({ "token_id": token_id, "token_owner_id": receiver_id }),
+            )
+            .with_deposit(to_yocto("0.1"))
+        },
+    )
+    .with_storage_management();
+    conformance.check_storage_deposit();
+    conformance.check_storage_mint();
+    conformance.check_storage_transfer();
+}
@@ -0,0 +1,340 @@
+use crate::factory::{FactoryCore, FactoryResolver};
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::json_types::{Base58CryptoHash, Base64VecU8, U128};
+use near_sdk::{
+    env, ext_contract, is_promise_success, log, require, AccountId, Balance, CryptoHash, Gas,
+    IntoStorageKey, Promise, PublicKey, StorageUsage,
+};
+
+const GAS_FOR_RESOLVE_CREATE: Gas = Gas(5_000_000_000_000);
+const GAS_FOR_CREATE_ACCOUNT: Gas = Gas(25_000_000_000_000 + GAS_FOR_RESOLVE_CREATE.0);
+
+const NO_DEPOSIT: Balance = 0;
+
+/// The storage in bytes used by a new account record with a single full access key,
+/// excluding the deployed code and the contract state.
+pub const NEW_ACCOUNT_STORAGE_USAGE: StorageUsage = 182;
+
+#[ext_contract(ext_self)]
+trait FactoryResolver {
+    fn factory_resolve_create(
+        &mut self,
+        account_id: AccountId,
+        predecessor_id: AccountId,
+        amount: U128,
+    ) -> bool;
+}
+
+/// Returns the sub-account `{name}.{current_account_id}`.
+/// Panics if `name` contains a `.` or the resulting account ID is invalid.
+pub fn sub_account_id(name: &str) -> AccountId {
+    env::current_account_id()
+        .sub_account(name)
+        .unwrap_or_else(|_| env::panic_str("Invalid sub-account name"))
+}
+
+/// Returns the minimum balance a new account needs to cover the storage of `code_len` bytes
+/// of code and its account record.
+pub fn required_deposit_for_code(code_len: usize) -> Balance {
+    Balance::from(NEW_ACCOUNT_STORAGE_USAGE + code_len as StorageUsage) * env::storage_byte_cost()
+}
+
+/// Returns the sha256 hash of `code`, which is the key the code is stored under.
+pub fn hash_code(code: &[u8]) -> CryptoHash {
+    let mut hash = CryptoHash::default();
+    hash.copy_from_slice(&env::sha256(code));
+    hash
+}
+
+fn refund_deposit(storage_used: StorageUsage) {
+    let required_cost = env::storage_byte_cost() * Balance::from(storage_used);
+    let attached_deposit = env::attached_deposit();
+
+    require!(
+        required_cost <= attached_deposit,
+        format!("Must attach {} yoctoNEAR to cover storage", required_cost)
+    );
+
+    let refund = attached_deposit - required_cost;
+    if refund > 1 {
+        Promise::new(env::predecessor_account_id()).transfer(refund);
+    }
+}
+
+/// Implementation of a sub-account factory.
+/// Allows any contract to deploy stored wasm blobs on its sub-accounts.
+/// There are next traits that any contract may implement:
+///     - FactoryCore -- interface for storing code and creating sub-accounts. ContractFactory provides methods for it.
+///     - FactoryResolver -- callback refunding the caller when creation fails. ContractFactory provides methods for it.
+///
+/// Restricting who is allowed to store code or create accounts is up to the contract.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ContractFactory {
+    /// Code hash -> wasm blob.
+    pub codes: LookupMap<CryptoHash, Vec<u8>>,
+}
+
+impl ContractFactory {
+    pub fn new<S>(prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        Self { codes: LookupMap::new(prefix) }
+    }
+
+    /// Stores the code without any deposit checks. Returns the hash of the code.
+    pub fn internal_store_code(&mut self, code: &[u8]) -> CryptoHash {
+        let code_hash = hash_code(code);
+        if !self.codes.contains_key(&code_hash) {
+            self.codes.insert(&code_hash, &code.to_vec());
+        }
+        code_hash
+    }
+
+    /// Removes the code from the factory. Returns `true` if the code was stored.
+    /// The released storage is not refunded, this is left to the contract.
+    pub fn internal_remove_code(&mut self, code_hash: &CryptoHash) -> bool {
+        self.codes.remove(code_hash).is_some()
+    }
+
+    pub fn internal_unwrap_code(&self, code_hash: &CryptoHash) -> Vec<u8> {
+        self.codes.get(code_hash).unwrap_or_else(|| env::panic_str("Code not found"))
+    }
+
+    /// Creates `account_id` with `amount` attached and deploys `code` without checking the
+    /// attached deposit. `refund_id` is refunded with `amount` if the creation fails.
+    /// The init call gets all the prepaid gas left, minus the gas reserved for the resolver.
+    #[allow(clippy::too_many_arguments)]
+    pub fn internal_create_account(
+        &self,
+        account_id: AccountId,
+        code: Vec<u8>,
+        init_method: String,
+        init_args: Vec<u8>,
+        public_key: Option<PublicKey>,
+        amount: Balance,
+        refund_id: AccountId,
+    ) -> Promise {
+        let remaining_gas = env::prepaid_gas() - env::used_gas();
+        require!(
+            remaining_gas > GAS_FOR_CREATE_ACCOUNT,
+            format!("Must attach more than {} gas to create an account", GAS_FOR_CREATE_ACCOUNT.0)
+        );
+        let mut promise = Promise::new(account_id.clone()).create_account().transfer(amount);
+        if let Some(public_key) = public_key {
+            promise = promise.add_full_access_key(public_key);
+        }
+        promise
+            .deploy_contract(code)
+            .function_call(
+                init_method,
+                init_args,
+                NO_DEPOSIT,
+                remaining_gas - GAS_FOR_CREATE_ACCOUNT,
+            )
+            .then(ext_self::factory_resolve_create(
+                account_id,
+                refund_id,
+                amount.into(),
+                env::current_account_id(),
+                NO_DEPOSIT,
+                GAS_FOR_RESOLVE_CREATE,
+            ))
+    }
+}
+
+impl FactoryCore for ContractFactory {
+    fn factory_store_code(&mut self, code: Base64VecU8) -> Base58CryptoHash {
+        let initial_storage_usage = env::storage_usage();
+        let code_hash = self.internal_store_code(&code.0);
+        refund_deposit(env::storage_usage() - initial_storage_usage);
+        code_hash.into()
+    }
+
+    fn factory_has_code(&self, code_hash: Base58CryptoHash) -> bool {
+        self.codes.contains_key(&code_hash.into())
+    }
+
+    fn factory_required_deposit(&self, code_hash: Base58CryptoHash) -> U128 {
+        required_deposit_for_code(self.internal_unwrap_code(&code_hash.into()).len()).into()
+    }
+
+    fn factory_create_account(
+        &mut self,
+        name: String,
+        code_hash: Base58CryptoHash,
+        init_method: String,
+        init_args: Base64VecU8,
+        public_key: Option<PublicKey>,
+    ) -> Promise {
+        let account_id = sub_account_id(&name);
+        let code = self.internal_unwrap_code(&code_hash.into());
+        let amount = env::attached_deposit();
+        let required_deposit = required_deposit_for_code(code.len());
+        require!(
+            amount >= required_deposit,
+            format!("Must attach at least {} yoctoNEAR to cover storage", required_deposit)
+        );
+        self.internal_create_account(
+            account_id,
+            code,
+            init_method,
+            init_args.into(),
+            public_key,
+            amount,
+            env::predecessor_account_id(),
+        )
+    }
+}
+
+impl FactoryResolver for ContractFactory {
+    fn factory_resolve_create(
+        &mut self,
+        account_id: AccountId,
+        predecessor_id: AccountId,
+        amount: U128,
+    ) -> bool {
+        if is_promise_success() {
+            return true;
+        }
+        log!("Failed to create {}, refunding {} to {}", account_id, amount.0, predecessor_id);
+        if amount.0 > 0 {
+            Promise::new(predecessor_id).transfer(amount.0);
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::{testing_env, PromiseResult};
+
+    const CODE: &[u8] = b"\0asm fake contract";
+
+    fn context(attached_deposit: Balance) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(attached_deposit);
+        builder
+    }
+
+    fn factory_with_code() -> (ContractFactory, Base58CryptoHash) {
+        testing_env!(context(10u128.pow(24)).build());
+        let mut factory = ContractFactory::new(b"f".to_vec());
+        let code_hash = factory.factory_store_code(CODE.to_vec().into());
+        (factory, code_hash)
+    }
+
+    #[test]
+    fn store_code_refunds_excess_deposit() {
+        let (factory, code_hash) = factory_with_code();
+        assert!(factory.factory_has_code(code_hash));
+        assert_eq!(CryptoHash::from(code_hash), hash_code(CODE));
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, accounts(1));
+        assert!(matches!(receipts[0].actions[0], VmAction::Transfer { .. }));
+    }
+
+    #[test]
+    fn create_account_deploys_code_and_calls_init() {
+        let (mut factory, code_hash) = factory_with_code();
+        let required_deposit = factory.factory_required_deposit(code_hash).0;
+        assert_eq!(required_deposit, required_deposit_for_code(CODE.len()));
+
+        testing_env!(context(required_deposit).build());
+        drop(factory.factory_create_account(
+            "sub".to_string(),
+            code_hash,
+            "new".to_string(),
+            b"{}".to_vec().into(),
+            None,
+        ));
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 2);
+        assert_eq!(receipts[0].receiver_id.as_str(), "sub.alice");
+        assert_eq!(receipts[0].actions[0], VmAction::CreateAccount);
+        assert_eq!(receipts[0].actions[1], VmAction::Transfer { deposit: required_deposit });
+        assert_eq!(receipts[0].actions[2], VmAction::DeployContract { code: CODE.to_vec() });
+        match &receipts[0].actions[3] {
+            VmAction::FunctionCall { method_name, args, gas, deposit } => {
+                assert_eq!(method_name, "new");
+                assert_eq!(args, b"{}");
+                assert_eq!(*deposit, 0);
+                assert!(gas.0 > 0);
+                assert!(gas.0 < env::prepaid_gas().0 - GAS_FOR_CREATE_ACCOUNT.0);
+            }
+            action => panic!("Unexpected action {:?}", action),
+        }
+        assert_eq!(receipts[1].receiver_id, accounts(0));
+        assert_eq!(receipts[1].receipt_indices, vec![0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Must attach at least")]
+    fn create_account_requires_deposit() {
+        let (mut factory, code_hash) = factory_with_code();
+        testing_env!(context(1).build());
+        factory.factory_create_account(
+            "sub".to_string(),
+            code_hash,
+            "new".to_string(),
+            vec![].into(),
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "gas to create an account")]
+    fn create_account_requires_gas() {
+        let (mut factory, code_hash) = factory_with_code();
+        let required_deposit = factory.factory_required_deposit(code_hash).0;
+        testing_env!(context(required_deposit).prepaid_gas(GAS_FOR_CREATE_ACCOUNT).build());
+        factory.factory_create_account(
+            "sub".to_string(),
+            code_hash,
+            "new".to_string(),
+            vec![].into(),
+            None,
+        );
+    }
+
+    #[test]
+    fn resolve_create_refunds_on_failed_init() {
+        let mut factory = ContractFactory::new(b"f".to_vec());
+        testing_env!(
+            context(0).predecessor_account_id(accounts(0)).build(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        let sub_account_id = sub_account_id("sub");
+        assert!(!factory.factory_resolve_create(sub_account_id, accounts(1), 1000.into()));
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, accounts(1));
+        assert_eq!(receipts[0].actions, vec![VmAction::Transfer { deposit: 1000 }]);
+    }
+
+    #[test]
+    fn resolve_create_keeps_deposit_on_success() {
+        let mut factory = ContractFactory::new(b"f".to_vec());
+        testing_env!(
+            context(0).predecessor_account_id(accounts(0)).build(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        let sub_account_id = sub_account_id("sub");
+        assert!(factory.factory_resolve_create(sub_account_id, accounts(1), 1000.into()));
+        assert!(get_created_receipts().is_empty());
+    }
+}
@@ -0,0 +1,61 @@
+/// The core methods for a sub-account factory. Takes name of the Contract struct and the inner
+/// field for the factory.
+#[macro_export]
+macro_rules! impl_factory {
+    ($contract: ident, $factory: ident) => {
+        use $crate::factory::{FactoryCore, FactoryResolver};
+
+        #[near_bindgen]
+        impl FactoryCore for $contract {
+            #[payable]
+            fn factory_store_code(
+                &mut self,
+                code: near_sdk::json_types::Base64VecU8,
+            ) -> near_sdk::json_types::Base58CryptoHash {
+                self.$factory.factory_store_code(code)
+            }
+
+            fn factory_has_code(&self, code_hash: near_sdk::json_types::Base58CryptoHash) -> bool {
+                self.$factory.factory_has_code(code_hash)
+            }
+
+            fn factory_required_deposit(
+                &self,
+                code_hash: near_sdk::json_types::Base58CryptoHash,
+            ) -> near_sdk::json_types::U128 {
+                self.$factory.factory_required_deposit(code_hash)
+            }
+
+            #[payable]
+            fn factory_create_account(
+                &mut self,
+                name: String,
+                code_hash: near_sdk::json_types::Base58CryptoHash,
+                init_method: String,
+                init_args: near_sdk::json_types::Base64VecU8,
+                public_key: Option<near_sdk::PublicKey>,
+            ) -> near_sdk::Promise {
+                self.$factory.factory_create_account(
+                    name,
+                    code_hash,
+                    init_method,
+                    init_args,
+                    public_key,
+                )
+            }
+        }
+
+        #[near_bindgen]
+        impl FactoryResolver for $contract {
+            #[private]
+            fn factory_resolve_create(
+                &mut self,
+                account_id: AccountId,
+                predecessor_id: AccountId,
+                amount: near_sdk::json_types::U128,
+            ) -> bool {
+                self.$factory.factory_resolve_create(account_id, predecessor_id, amount)
+            }
+        }
+    };
+}
@@ -0,0 +1,76 @@
+mod core_impl;
+mod macros;
+
+pub use self::core_impl::*;
+
+use near_sdk::json_types::{Base58CryptoHash, Base64VecU8, U128};
+use near_sdk::{AccountId, Promise, PublicKey};
+
+/// Used for contracts that deploy child contracts on their own sub-accounts. Wasm blobs are
+/// stored once on the factory and referenced by their sha256 hash, so every sub-account created
+/// from the same code only pays for the code once on the factory side.
+pub trait FactoryCore {
+    /// Stores a wasm blob on the factory.
+    ///
+    /// Requirements:
+    /// * Caller of the method must attach a deposit covering the storage of the blob. If the
+    ///   blob is already stored, the full deposit is refunded.
+    /// * Any attached deposit above the storage cost is refunded.
+    ///
+    /// Returns the sha256 hash of the code which is used to reference it.
+    fn factory_store_code(&mut self, code: Base64VecU8) -> Base58CryptoHash;
+
+    /// Returns `true` if the code with the given hash is stored on the factory.
+    fn factory_has_code(&self, code_hash: Base58CryptoHash) -> bool;
+
+    /// Returns the minimum deposit that has to be attached to `factory_create_account` to
+    /// cover the storage of the new account with the given code deployed.
+    fn factory_required_deposit(&self, code_hash: Base58CryptoHash) -> U128;
+
+    /// Creates the sub-account `{name}.{factory}`, transfers the attached deposit to it,
+    /// optionally adds a full access key, deploys the stored code and calls `init_method` with
+    /// `init_args`.
+    ///
+    /// Requirements:
+    /// * `name` must not contain a `.` and `{name}.{factory}` must be a valid account ID.
+    /// * Caller of the method must attach at least `factory_required_deposit(code_hash)`.
+    /// * If the creation fails, the attached deposit MUST be refunded to the caller.
+    ///
+    /// Arguments:
+    /// * `name`: the prefix of the sub-account.
+    /// * `code_hash`: the hash of the code previously stored with `factory_store_code`.
+    /// * `init_method`: the method called on the new contract right after deployment.
+    /// * `init_args`: raw arguments of the `init_method` call.
+    /// * `public_key`: optional full access key added to the new account.
+    fn factory_create_account(
+        &mut self,
+        name: String,
+        code_hash: Base58CryptoHash,
+        init_method: String,
+        init_args: Base64VecU8,
+        public_key: Option<PublicKey>,
+    ) -> Promise;
+}
+
+/// Resolves `factory_create_account` once the sub-account creation has finished.
+/// This trait is implemented on the factory contract.
+pub trait FactoryResolver {
+    /// Finalize a `factory_create_account` call.
+    ///
+    /// Requirements:
+    /// * Contract MUST forbid calls to this function by any account except self
+    /// * If the creation failed, contract MUST refund `amount` to `predecessor_id`
+    ///
+    /// Arguments:
+    /// * `account_id`: the sub-account that was created
+    /// * `predecessor_id`: the account which called `factory_create_account`
+    /// * `amount`: the deposit attached to `factory_create_account`
+    ///
+    /// Returns true if the sub-account was successfully created.
+    fn factory_resolve_create(
+        &mut self,
+        account_id: AccountId,
+        predecessor_id: AccountId,
+        amount: U128,
+    ) -> bool;
+}
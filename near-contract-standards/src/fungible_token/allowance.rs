@@ -0,0 +1,78 @@
+use near_sdk::json_types::U128;
+use near_sdk::{AccountId, Promise, PromiseOrValue};
+
+/// Optional extension of the fungible token standard, which lets an owner allow another account,
+/// the spender, to transfer up to some amount of its tokens. Every transfer made by the spender
+/// decreases the allowance.
+pub trait FungibleTokenAllowance {
+    /// Sets the amount of tokens `spender_id` can transfer on behalf of the
+    /// `env::predecessor_account_id`, replacing the previous allowance. An allowance of `0`
+    /// removes it.
+    /// At least 1 yoctoNEAR must be attached. Storing a new allowance requires the attached
+    /// deposit to cover its storage, and removing it refunds its storage to the owner.
+    ///
+    /// Arguments:
+    /// - `spender_id` - the account ID allowed to transfer the tokens.
+    /// - `amount` - the amount of tokens in decimal string representation.
+    /// - `msg` - if given, `ft_on_approve` is called on `spender_id` with it.
+    ///
+    /// Returns the promise calling `ft_on_approve` if `msg` is given.
+    fn ft_approve(
+        &mut self,
+        spender_id: AccountId,
+        amount: U128,
+        msg: Option<String>,
+    ) -> Option<Promise>;
+
+    /// Returns the amount of tokens `spender_id` can transfer on behalf of `owner_id`, `"0"` if
+    /// there is no allowance.
+    fn ft_allowance(&self, owner_id: AccountId, spender_id: AccountId) -> U128;
+
+    /// Transfers positive `amount` of tokens from `owner_id` to `receiver_id`, using the
+    /// allowance of the `env::predecessor_account_id`. Exactly 1 yoctoNEAR must be attached.
+    ///
+    /// Arguments:
+    /// - `owner_id` - the account ID whose tokens are transferred.
+    /// - `receiver_id` - the account ID of the receiver.
+    /// - `amount` - the amount of tokens to transfer. Must be a positive number in decimal string representation.
+    /// - `memo` - an optional string field in a free form to associate a memo with this transfer.
+    fn ft_transfer_from(
+        &mut self,
+        owner_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    );
+
+    /// Transfers tokens like `ft_transfer_from` and calls `ft_on_transfer` on `receiver_id`
+    /// like `ft_transfer_call`. The receiver sees `owner_id` as the sender, and the unused
+    /// tokens are refunded to `owner_id`. The allowance isn't restored by the refund.
+    ///
+    /// Returns a promise which will result in the amount of tokens withdrawn from owner's account.
+    fn ft_transfer_call_from(
+        &mut self,
+        owner_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<U128>;
+}
+
+/// Used when an allowance is set with `ft_approve` and a `msg`. This trait is implemented on
+/// the spender contract, not on the FT contract.
+pub trait FungibleTokenAllowanceReceiver {
+    /// Called by fungible token contract after `owner_id` allowed this contract to transfer
+    /// `amount` of its tokens.
+    ///
+    /// Arguments:
+    /// - `owner_id` - the account ID that set the allowance.
+    /// - `amount` - the allowance in a decimal string representation.
+    /// - `msg` - the message given to `ft_approve`.
+    fn ft_on_approve(
+        &mut self,
+        owner_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<String>;
+}
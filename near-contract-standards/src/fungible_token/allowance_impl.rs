@@ -0,0 +1,298 @@
+use crate::fungible_token::core_impl::ext_fungible_token_receiver;
+use crate::fungible_token::FungibleToken;
+use crate::non_fungible_token::{assert_at_least_one_yocto, refund_deposit};
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::json_types::U128;
+use near_sdk::{
+    assert_one_yocto, env, ext_contract, log, require, AccountId, Balance, Gas, IntoStorageKey,
+    Promise, PromiseOrValue, StorageUsage,
+};
+
+const GAS_FOR_FT_APPROVE: Gas = Gas(10_000_000_000_000);
+const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(5_000_000_000_000);
+const GAS_FOR_FT_TRANSFER_CALL: Gas = Gas(25_000_000_000_000 + GAS_FOR_RESOLVE_TRANSFER.0);
+
+const NO_DEPOSIT: Balance = 0;
+
+#[ext_contract(ext_self)]
+trait FungibleTokenResolver {
+    fn ft_resolve_transfer(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> U128;
+}
+
+#[ext_contract(ext_allowance_receiver)]
+pub trait FungibleTokenAllowanceReceiver {
+    fn ft_on_approve(
+        &mut self,
+        owner_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<String>;
+}
+
+/// Refunds the storage released since `initial_storage_usage` to `account_id`.
+fn refund_released_storage(account_id: AccountId, initial_storage_usage: StorageUsage) {
+    let storage_released = initial_storage_usage.saturating_sub(env::storage_usage());
+    if storage_released > 0 {
+        Promise::new(account_id)
+            .transfer(Balance::from(storage_released) * env::storage_byte_cost());
+    }
+}
+
+/// Allowances of a fungible token, which together with a [`FungibleToken`] implement the
+/// [allowance extension](crate::fungible_token::allowance::FungibleTokenAllowance).
+///
+/// The storage of an allowance is paid by its owner when setting it, and refunded when it's
+/// removed, either by setting it to zero or by spending all of it. Allowances are not removed
+/// when the owner unregisters.
+///
+/// For example usage, see `impl_fungible_token_allowance!`.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Allowances {
+    /// (Owner AccountID, Spender AccountID) -> Allowance.
+    pub allowances: LookupMap<(AccountId, AccountId), Balance>,
+}
+
+impl Allowances {
+    pub fn new<S>(prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        Self { allowances: LookupMap::new(prefix) }
+    }
+
+    pub fn get(&self, owner_id: &AccountId, spender_id: &AccountId) -> Balance {
+        self.allowances.get(&(owner_id.clone(), spender_id.clone())).unwrap_or(0)
+    }
+
+    /// Sets the allowance, removing it if `amount` is zero. The storage is not paid by anyone.
+    pub fn internal_set(&mut self, owner_id: &AccountId, spender_id: &AccountId, amount: Balance) {
+        let key = (owner_id.clone(), spender_id.clone());
+        if amount > 0 {
+            self.allowances.insert(&key, &amount);
+        } else {
+            self.allowances.remove(&key);
+        }
+    }
+
+    /// Spends `amount` of the allowance of `spender_id`, and refunds the storage of the
+    /// allowance to the owner if all of it was spent. The owner doesn't need an allowance.
+    pub fn internal_spend(
+        &mut self,
+        owner_id: &AccountId,
+        spender_id: &AccountId,
+        amount: Balance,
+    ) {
+        if owner_id == spender_id {
+            return;
+        }
+        let allowance = self.get(owner_id, spender_id);
+        let new_allowance = allowance
+            .checked_sub(amount)
+            .unwrap_or_else(|| env::panic_str("The allowance is not enough"));
+        let initial_storage_usage = env::storage_usage();
+        self.internal_set(owner_id, spender_id, new_allowance);
+        refund_released_storage(owner_id.clone(), initial_storage_usage);
+    }
+
+    pub fn ft_approve(
+        &mut self,
+        tokens: &FungibleToken,
+        spender_id: AccountId,
+        amount: U128,
+        msg: Option<String>,
+    ) -> Option<Promise> {
+        assert_at_least_one_yocto();
+        let owner_id = env::predecessor_account_id();
+        tokens.internal_unwrap_balance_of(&owner_id);
+        require!(owner_id != spender_id, "Can't set allowance for yourself");
+
+        let initial_storage_usage = env::storage_usage();
+        self.internal_set(&owner_id, &spender_id, amount.0);
+        let storage_usage = env::storage_usage();
+        if storage_usage >= initial_storage_usage {
+            // Require that enough deposit was attached to pay for storage, and refund excess.
+            refund_deposit(storage_usage - initial_storage_usage);
+        } else {
+            refund_deposit(0);
+            refund_released_storage(owner_id.clone(), initial_storage_usage);
+        }
+        log!("Allowance of {} for {} set to {}", owner_id, spender_id, amount.0);
+
+        // if given `msg`, schedule call to `ft_on_approve` and return it. Else, return None.
+        msg.map(|msg| {
+            ext_allowance_receiver::ft_on_approve(
+                owner_id,
+                amount,
+                msg,
+                spender_id,
+                NO_DEPOSIT,
+                env::prepaid_gas() - GAS_FOR_FT_APPROVE,
+            )
+        })
+    }
+
+    pub fn ft_allowance(&self, owner_id: AccountId, spender_id: AccountId) -> U128 {
+        self.get(&owner_id, &spender_id).into()
+    }
+
+    pub fn ft_transfer_from(
+        &mut self,
+        tokens: &mut FungibleToken,
+        owner_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    ) {
+        assert_one_yocto();
+        let spender_id = env::predecessor_account_id();
+        let amount: Balance = amount.into();
+        self.internal_spend(&owner_id, &spender_id, amount);
+        tokens.internal_transfer(&owner_id, &receiver_id, amount, memo);
+    }
+
+    /// Transfers the tokens like `ft_transfer_from`, and resolves the transfer with the
+    /// `ft_resolve_transfer` of the contract, which refunds the unused tokens to `owner_id`.
+    pub fn ft_transfer_call_from(
+        &mut self,
+        tokens: &mut FungibleToken,
+        owner_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        assert_one_yocto();
+        let spender_id = env::predecessor_account_id();
+        let amount: Balance = amount.into();
+        self.internal_spend(&owner_id, &spender_id, amount);
+        tokens.internal_transfer(&owner_id, &receiver_id, amount, memo);
+        // Initiating receiver's call and the callback
+        ext_fungible_token_receiver::ft_on_transfer(
+            owner_id.clone(),
+            amount.into(),
+            msg,
+            receiver_id.clone(),
+            NO_DEPOSIT,
+            env::prepaid_gas() - GAS_FOR_FT_TRANSFER_CALL,
+        )
+        .then(ext_self::ft_resolve_transfer(
+            owner_id,
+            receiver_id,
+            amount.into(),
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_RESOLVE_TRANSFER,
+        ))
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fungible_token::resolver::FungibleTokenResolver;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::{testing_env, PromiseResult};
+
+    const APPROVE_DEPOSIT: Balance = 10u128.pow(22);
+
+    fn context(predecessor_account_id: AccountId, attached_deposit: Balance) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .predecessor_account_id(predecessor_account_id)
+            .attached_deposit(attached_deposit);
+        builder
+    }
+
+    /// A token with 100 tokens for bob, who allows charlie to transfer 60 of them to dave.
+    /// Returns the cost of the storage of the allowance too.
+    fn setup() -> (FungibleToken, Allowances, Balance) {
+        testing_env!(context(accounts(0), 0).build());
+        let mut tokens = FungibleToken::new(b"t".to_vec());
+        for account_id in [accounts(1), accounts(2), accounts(3)] {
+            tokens.internal_register_account(&account_id);
+        }
+        tokens.internal_deposit(&accounts(1), 100);
+        let mut allowances = Allowances::new(b"a".to_vec());
+
+        testing_env!(context(accounts(1), APPROVE_DEPOSIT).build());
+        let initial_storage_usage = env::storage_usage();
+        allowances.ft_approve(&tokens, accounts(2), U128(60), None);
+        let storage_cost =
+            Balance::from(env::storage_usage() - initial_storage_usage) * env::storage_byte_cost();
+        (tokens, allowances, storage_cost)
+    }
+
+    /// The transfers created by the last call, by receiver.
+    fn refunds() -> Vec<(AccountId, Balance)> {
+        get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| {
+                let receiver_id = receipt.receiver_id;
+                receipt.actions.into_iter().filter_map(move |action| match action {
+                    VmAction::Transfer { deposit } => Some((receiver_id.clone(), deposit)),
+                    _ => None,
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn transfer_from_spends_allowance() {
+        let (mut tokens, mut allowances, _) = setup();
+        testing_env!(context(accounts(2), 1).build());
+        allowances.ft_transfer_from(&mut tokens, accounts(1), accounts(3), U128(20), None);
+
+        assert_eq!(allowances.ft_allowance(accounts(1), accounts(2)), U128(40));
+        assert_eq!(tokens.internal_unwrap_balance_of(&accounts(1)), 80);
+        assert_eq!(tokens.internal_unwrap_balance_of(&accounts(3)), 20);
+        assert!(refunds().is_empty());
+    }
+
+    #[test]
+    fn spending_whole_allowance_refunds_storage() {
+        let (mut tokens, mut allowances, storage_cost) = setup();
+        testing_env!(context(accounts(2), 1).build());
+        allowances.ft_transfer_from(&mut tokens, accounts(1), accounts(3), U128(60), None);
+
+        assert_eq!(allowances.ft_allowance(accounts(1), accounts(2)), U128(0));
+        assert!(allowances.allowances.get(&(accounts(1), accounts(2))).is_none());
+        assert_eq!(refunds(), vec![(accounts(1), storage_cost)]);
+    }
+
+    #[test]
+    fn transfer_call_from_refunds_owner() {
+        let (mut tokens, mut allowances, _) = setup();
+        testing_env!(context(accounts(2), 1).build());
+        allowances.ft_transfer_call_from(
+            &mut tokens,
+            accounts(1),
+            accounts(3),
+            U128(50),
+            None,
+            "".to_string(),
+        );
+
+        testing_env!(
+            context(accounts(0), 0).build(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            vec![PromiseResult::Successful(near_sdk::serde_json::to_vec(&U128(20)).unwrap())],
+        );
+        let used = tokens.ft_resolve_transfer(accounts(1), accounts(3), U128(50));
+        assert_eq!(used, U128(30));
+        assert_eq!(tokens.internal_unwrap_balance_of(&accounts(1)), 70);
+        assert_eq!(tokens.internal_unwrap_balance_of(&accounts(3)), 30);
+        // The refund goes to the owner, the allowance isn't restored.
+        assert_eq!(allowances.ft_allowance(accounts(1), accounts(2)), U128(10));
+    }
+}
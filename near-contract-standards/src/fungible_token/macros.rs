@@ -107,3 +107,70 @@ macro_rules! impl_fungible_token_storage {
         }
     };
 }
+
+/// Fungible token allowances let owners allow other accounts to transfer some of their tokens.
+/// The allowances are kept in an
+/// [`Allowances`](crate::fungible_token::allowance_impl::Allowances) next to the token.
+#[macro_export]
+macro_rules! impl_fungible_token_allowance {
+    ($contract: ident, $token: ident, $allowances: ident) => {
+        use $crate::fungible_token::allowance::FungibleTokenAllowance;
+
+        #[near_bindgen]
+        impl FungibleTokenAllowance for $contract {
+            #[payable]
+            fn ft_approve(
+                &mut self,
+                spender_id: AccountId,
+                amount: near_sdk::json_types::U128,
+                msg: Option<String>,
+            ) -> Option<near_sdk::Promise> {
+                self.$allowances.ft_approve(&self.$token, spender_id, amount, msg)
+            }
+
+            fn ft_allowance(
+                &self,
+                owner_id: AccountId,
+                spender_id: AccountId,
+            ) -> near_sdk::json_types::U128 {
+                self.$allowances.ft_allowance(owner_id, spender_id)
+            }
+
+            #[payable]
+            fn ft_transfer_from(
+                &mut self,
+                owner_id: AccountId,
+                receiver_id: AccountId,
+                amount: near_sdk::json_types::U128,
+                memo: Option<String>,
+            ) {
+                self.$allowances.ft_transfer_from(
+                    &mut self.$token,
+                    owner_id,
+                    receiver_id,
+                    amount,
+                    memo,
+                )
+            }
+
+            #[payable]
+            fn ft_transfer_call_from(
+                &mut self,
+                owner_id: AccountId,
+                receiver_id: AccountId,
+                amount: near_sdk::json_types::U128,
+                memo: Option<String>,
+                msg: String,
+            ) -> PromiseOrValue<near_sdk::json_types::U128> {
+                self.$allowances.ft_transfer_call_from(
+                    &mut self.$token,
+                    owner_id,
+                    receiver_id,
+                    amount,
+                    memo,
+                    msg,
+                )
+            }
+        }
+    };
+}
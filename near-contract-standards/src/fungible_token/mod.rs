@@ -1,3 +1,5 @@
+pub mod allowance;
+pub mod allowance_impl;
 pub mod core;
 pub mod core_impl;
 pub mod macros;
@@ -6,5 +8,6 @@ pub mod receiver;
 pub mod resolver;
 pub mod storage_impl;
 
+pub use allowance_impl::Allowances;
 pub use core_impl::FungibleToken;
 pub use macros::*;
@@ -1,7 +1,16 @@
+/// Factory for deploying stored contracts on sub-accounts of the current account.
+pub mod factory;
 /// Fungible tokens as described in [by the spec](https://nomicon.io/Standards/FungibleToken/README.html).
 pub mod fungible_token;
+/// Multi-tokens, which hold any amount of several kinds of tokens in a single contract, as
+/// described in [by the spec](https://nomicon.io/Standards/MultiToken/README.html).
+pub mod multi_token;
 /// Non-fungible tokens as described in [by the spec](https://nomicon.io/Standards/NonFungibleToken/README.html).
 pub mod non_fungible_token;
+/// Conformance test suites of the token standards which run against any compiled contract in
+/// the simulator. Only available with the `sim` feature.
+#[cfg(feature = "sim")]
+pub mod sim;
 /// Storage management deals with handling [state storage](https://docs.near.org/docs/concepts/storage-staking) on NEAR. This follows the [storage management standard](https://nomicon.io/Standards/StorageManagement.html).
 pub mod storage_management;
 /// This upgrade standard is a use case where a staging area exists for a WASM
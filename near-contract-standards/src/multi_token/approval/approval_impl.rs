@@ -0,0 +1,180 @@
+use crate::multi_token::approval::MultiTokenApproval;
+use crate::multi_token::token::{Approval, TokenId};
+use crate::multi_token::MultiToken;
+use crate::non_fungible_token::{assert_at_least_one_yocto, refund_deposit};
+use near_sdk::json_types::U128;
+use near_sdk::{
+    assert_one_yocto, env, ext_contract, require, AccountId, Balance, Gas, Promise, StorageUsage,
+};
+
+const GAS_FOR_MT_APPROVE: Gas = Gas(10_000_000_000_000);
+const NO_DEPOSIT: Balance = 0;
+
+fn expect_approval<T>(option: Option<T>) -> T {
+    option.unwrap_or_else(|| env::panic_str("next_approval_id_by_id must be set for approval ext"))
+}
+
+#[allow(clippy::too_many_arguments)]
+#[ext_contract(ext_approval_receiver)]
+pub trait MultiTokenReceiver {
+    fn mt_on_approve(
+        &mut self,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        owner_id: AccountId,
+        approval_ids: Vec<u64>,
+        msg: String,
+    );
+}
+
+/// Refunds the storage released since `initial_storage_usage` to `account_id`.
+fn refund_released_storage(account_id: AccountId, initial_storage_usage: StorageUsage) {
+    let storage_released = initial_storage_usage.saturating_sub(env::storage_usage());
+    if storage_released > 0 {
+        Promise::new(account_id)
+            .transfer(Balance::from(storage_released) * env::storage_byte_cost());
+    }
+}
+
+impl MultiTokenApproval for MultiToken {
+    fn mt_approve(
+        &mut self,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        account_id: AccountId,
+        msg: Option<String>,
+    ) -> Option<Promise> {
+        assert_at_least_one_yocto();
+        require!(
+            token_ids.len() == amounts.len(),
+            "The number of token_ids and amounts must match"
+        );
+        let initial_storage_usage = env::storage_usage();
+        let approvals_by_id = self
+            .approvals_by_id
+            .as_mut()
+            .unwrap_or_else(|| env::panic_str("MT does not support Approval Management"));
+        let next_approval_id_by_id = expect_approval(self.next_approval_id_by_id.as_mut());
+
+        let owner_id = env::predecessor_account_id();
+        let mut approval_ids = Vec::with_capacity(token_ids.len());
+        for (token_id, amount) in token_ids.iter().zip(&amounts) {
+            let balance = self.balances.get(&(token_id.clone(), owner_id.clone())).unwrap_or(0);
+            require!(balance >= amount.0, "The owner doesn't have enough balance");
+
+            // update HashMap of approvals the owner gave for this token
+            let key = (token_id.clone(), owner_id.clone());
+            let mut approvals = approvals_by_id.get(&key).unwrap_or_default();
+            let approval_id: u64 = next_approval_id_by_id.get(token_id).unwrap_or(1u64);
+            approvals.insert(account_id.clone(), Approval { approval_id, amount: *amount });
+            approvals_by_id.insert(&key, &approvals);
+
+            // increment next_approval_id for this token
+            next_approval_id_by_id.insert(token_id, &(approval_id + 1));
+            approval_ids.push(approval_id);
+        }
+
+        // Require that enough deposit was attached to pay for the storage of new approvals, and
+        // refund excess.
+        refund_deposit(env::storage_usage().saturating_sub(initial_storage_usage));
+
+        // if given `msg`, schedule call to `mt_on_approve` and return it. Else, return None.
+        msg.map(|msg| {
+            ext_approval_receiver::mt_on_approve(
+                token_ids,
+                amounts,
+                owner_id,
+                approval_ids,
+                msg,
+                account_id,
+                NO_DEPOSIT,
+                env::prepaid_gas() - GAS_FOR_MT_APPROVE,
+            )
+        })
+    }
+
+    fn mt_revoke(&mut self, token_ids: Vec<TokenId>, account_id: AccountId) {
+        assert_one_yocto();
+        let initial_storage_usage = env::storage_usage();
+        let approvals_by_id = self
+            .approvals_by_id
+            .as_mut()
+            .unwrap_or_else(|| env::panic_str("MT does not support Approval Management"));
+
+        let owner_id = env::predecessor_account_id();
+        for token_id in token_ids {
+            let key = (token_id, owner_id.clone());
+            // if the owner gave no approvals for this token, do nothing
+            if let Some(mut approvals) = approvals_by_id.get(&key) {
+                // if account_id was already not approved, do nothing
+                if approvals.remove(&account_id).is_some() {
+                    // if this was the last approval, remove the whole HashMap to save space.
+                    if approvals.is_empty() {
+                        approvals_by_id.remove(&key);
+                    } else {
+                        approvals_by_id.insert(&key, &approvals);
+                    }
+                }
+            }
+        }
+        refund_released_storage(owner_id, initial_storage_usage);
+    }
+
+    fn mt_revoke_all(&mut self, token_ids: Vec<TokenId>) {
+        assert_one_yocto();
+        let initial_storage_usage = env::storage_usage();
+        let approvals_by_id = self
+            .approvals_by_id
+            .as_mut()
+            .unwrap_or_else(|| env::panic_str("MT does not support Approval Management"));
+
+        let owner_id = env::predecessor_account_id();
+        for token_id in token_ids {
+            approvals_by_id.remove(&(token_id, owner_id.clone()));
+        }
+        refund_released_storage(owner_id, initial_storage_usage);
+    }
+
+    fn mt_is_approved(
+        &self,
+        token_ids: Vec<TokenId>,
+        owner_id: AccountId,
+        approved_account_id: AccountId,
+        amounts: Vec<U128>,
+        approval_ids: Option<Vec<u64>>,
+    ) -> bool {
+        require!(
+            token_ids.len() == amounts.len(),
+            "The number of token_ids and amounts must match"
+        );
+        if let Some(approval_ids) = &approval_ids {
+            require!(
+                token_ids.len() == approval_ids.len(),
+                "The number of token_ids and approval_ids must match"
+            );
+        }
+
+        let approvals_by_id = if let Some(a) = self.approvals_by_id.as_ref() {
+            a
+        } else {
+            // contract does not support approval management
+            return false;
+        };
+
+        token_ids.into_iter().zip(amounts).enumerate().all(|(i, (token_id, amount))| {
+            let approval = approvals_by_id
+                .get(&(token_id, owner_id.clone()))
+                .and_then(|approvals| approvals.get(&approved_account_id).copied());
+            match approval {
+                Some(approval) => {
+                    let approval_id_matches = match &approval_ids {
+                        Some(approval_ids) => approval_ids[i] == approval.approval_id,
+                        None => true,
+                    };
+                    approval.amount.0 >= amount.0 && approval_id_matches
+                }
+                None => false,
+            }
+        })
+    }
+}
@@ -0,0 +1,29 @@
+use crate::multi_token::token::TokenId;
+use near_sdk::json_types::U128;
+use near_sdk::AccountId;
+
+/// Approval receiver is the trait for the method called (or attempted to be called) when an MT contract adds an approval for an account.
+pub trait MultiTokenApprovalReceiver {
+    /// Respond to notification that contract has been granted approval for tokens.
+    ///
+    /// Notes
+    /// * Contract knows the token contract ID from `predecessor_account_id`
+    ///
+    /// Arguments:
+    /// * `token_ids`: the tokens to which this contract has been granted approval
+    /// * `amounts`: the number of each of the tokens this contract may transfer
+    /// * `owner_id`: the owner of the tokens
+    /// * `approval_ids`: the approval IDs stored by MT contract for each of the approvals.
+    ///   Expected to be numbers within the 2^53 limit representable by JSON.
+    /// * `msg`: specifies information needed by the approved contract in order to
+    ///   handle the approval. Can indicate both a function to call and the
+    ///   parameters to pass to that function.
+    fn mt_on_approve(
+        &mut self,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        owner_id: AccountId,
+        approval_ids: Vec<u64>,
+        msg: String,
+    ) -> near_sdk::PromiseOrValue<String>;
+}
@@ -0,0 +1,91 @@
+mod approval_impl;
+mod approval_receiver;
+
+pub use approval_impl::*;
+pub use approval_receiver::*;
+
+use crate::multi_token::token::TokenId;
+use near_sdk::json_types::U128;
+use near_sdk::AccountId;
+use near_sdk::Promise;
+
+/// Trait used when it's desired to have multi-tokens with a traditional escrow or approval
+/// system, as described in the [approval management standard]. This allows Alice to allow Bob to take up to 10 of her tokens "gold" but not more,
+/// and none of her other tokens. Every owner of a token gives its own approvals, and an
+/// approved amount decreases with every transfer made with it.
+///
+/// [approval management standard]: https://nomicon.io/Standards/MultiToken/ApprovalManagement.html
+pub trait MultiTokenApproval {
+    /// Approve an account to transfer the tokens of the caller.
+    ///
+    /// Requirements
+    /// * Caller of the method must attach a deposit of at least 1 yoctoⓃ for
+    ///   security purposes
+    /// * Contract MAY require caller to attach larger deposit, to cover cost of
+    ///   storing approver data
+    /// * Contract MUST panic if the caller doesn't hold the given amounts of the tokens
+    /// * Contract MUST increment approval ID even if re-approving an account
+    /// * If successfully approved or if had already been approved, and if `msg` is
+    ///   present, contract MUST call `mt_on_approve` on `account_id`.
+    ///
+    /// Arguments:
+    /// * `token_ids`: the tokens for which to add an approval
+    /// * `amounts`: the number of each of the tokens `account_id` may transfer
+    /// * `account_id`: the account to add to the approvals
+    /// * `msg`: optional string to be passed to `mt_on_approve`
+    ///
+    /// Returns void, if no `msg` given. Otherwise, returns promise call to
+    /// `mt_on_approve`, which can resolve with whatever it wants.
+    fn mt_approve(
+        &mut self,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        account_id: AccountId,
+        msg: Option<String>,
+    ) -> Option<Promise>;
+
+    /// Revoke the approvals of an account for the tokens of the caller.
+    ///
+    /// Requirements
+    /// * Caller of the method must attach a deposit of 1 yoctoⓃ for security
+    ///   purposes
+    /// * If contract requires >1yN deposit on `mt_approve`, contract
+    ///   MUST refund associated storage deposit when owner revokes approval
+    ///
+    /// Arguments:
+    /// * `token_ids`: the tokens for which to revoke an approval
+    /// * `account_id`: the account to remove from the approvals
+    fn mt_revoke(&mut self, token_ids: Vec<TokenId>, account_id: AccountId);
+
+    /// Revoke all the approvals for the tokens of the caller.
+    ///
+    /// Requirements
+    /// * Caller of the method must attach a deposit of 1 yoctoⓃ for security
+    ///   purposes
+    /// * If contract requires >1yN deposit on `mt_approve`, contract
+    ///   MUST refund all associated storage deposit when owner revokes approvals
+    ///
+    /// Arguments:
+    /// * `token_ids`: the tokens with approvals to revoke
+    fn mt_revoke_all(&mut self, token_ids: Vec<TokenId>);
+
+    /// Check if an account is approved to transfer the given amounts of the tokens of an owner,
+    /// optionally checking the approval IDs.
+    ///
+    /// Arguments:
+    /// * `token_ids`: the tokens to check the approvals for
+    /// * `owner_id`: the owner who gave the approvals
+    /// * `approved_account_id`: the account to check the approvals of
+    /// * `amounts`: the number of each of the tokens the account must be approved for
+    /// * `approval_ids`: optional approval IDs to check against the current approval IDs
+    ///
+    /// Returns `true` if `approved_account_id` is approved for all of the tokens
+    fn mt_is_approved(
+        &self,
+        token_ids: Vec<TokenId>,
+        owner_id: AccountId,
+        approved_account_id: AccountId,
+        amounts: Vec<U128>,
+        approval_ids: Option<Vec<u64>>,
+    ) -> bool;
+}
@@ -0,0 +1,855 @@
+use super::resolver::MultiTokenResolver;
+use crate::multi_token::core::MultiTokenCore;
+use crate::multi_token::events::{MtBurn, MtMint, MtTransfer};
+use crate::multi_token::metadata::TokenMetadata;
+use crate::multi_token::token::{Approval, Token, TokenId};
+use crate::non_fungible_token::refund_deposit;
+use crate::storage_management::StorageBalances;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, TreeMap, UnorderedSet};
+use near_sdk::json_types::U128;
+use near_sdk::{
+    assert_one_yocto, env, ext_contract, require, AccountId, Balance, Gas, IntoStorageKey,
+    PromiseOrValue, PromiseResult,
+};
+use std::collections::HashMap;
+
+const GAS_FOR_RESOLVE_TRANSFER_PER_TOKEN: Gas = Gas(5_000_000_000_000);
+const GAS_FOR_MT_TRANSFER_CALL: Gas = Gas(25_000_000_000_000);
+
+const NO_DEPOSIT: Balance = 0;
+
+#[allow(clippy::too_many_arguments)]
+#[ext_contract(ext_self)]
+trait MultiTokenResolver {
+    fn mt_resolve_transfer(
+        &mut self,
+        previous_owner_ids: Vec<AccountId>,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+    ) -> Vec<U128>;
+}
+
+#[allow(clippy::too_many_arguments)]
+#[ext_contract(ext_receiver)]
+pub trait MultiTokenReceiver {
+    /// Returns the number of each of the tokens that should be returned to their previous owners
+    fn mt_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_ids: Vec<AccountId>,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>>;
+}
+
+/// Implementation of the multi-token standard.
+/// Allows to include NEP-245 compatible tokens to any contract.
+/// There are next traits that any contract may implement:
+///     - MultiTokenCore -- interface with mt_transfer methods. MultiToken provides methods for it.
+///     - MultiTokenApproval -- interface with mt_approve methods. MultiToken provides methods for it.
+///     - MultiTokenEnumeration -- interface for getting lists of tokens. MultiToken provides methods for it.
+///     - MultiTokenMetadataProvider -- return metadata for the contract, up to contract to implement.
+///     - StorageManagement -- interface for NEP-145 for registering accounts. MultiToken provides methods for it.
+///
+/// Accounts have to be registered to receive tokens. They prepay with `storage_deposit` for
+/// the balances of the tokens they hold, which are charged to the storage balance of the
+/// receiver of the tokens and released when the balance drops to zero.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct MultiToken {
+    // prefix of all the collections, including the tokens of each owner
+    pub prefix: Vec<u8>,
+
+    // the storage deposits of the registered accounts, the registration includes the set of
+    // tokens of the account
+    pub storage_balances: StorageBalances,
+
+    // always required
+    pub supply_by_id: TreeMap<TokenId, Balance>,
+    pub balances: LookupMap<(TokenId, AccountId), Balance>,
+
+    // the tokens each registered account holds
+    pub tokens_per_owner: LookupMap<AccountId, UnorderedSet<TokenId>>,
+
+    // required by metadata extension
+    pub token_metadata_by_id: Option<LookupMap<TokenId, TokenMetadata>>,
+
+    // required by approval extension, the approvals given by each owner of a token
+    pub approvals_by_id: Option<LookupMap<(TokenId, AccountId), HashMap<AccountId, Approval>>>,
+    pub next_approval_id_by_id: Option<LookupMap<TokenId, u64>>,
+}
+
+impl MultiToken {
+    pub fn new<Q, R, S>(
+        prefix: Q,
+        token_metadata_prefix: Option<R>,
+        approval_prefix: Option<S>,
+    ) -> Self
+    where
+        Q: IntoStorageKey,
+        R: IntoStorageKey,
+        S: IntoStorageKey,
+    {
+        let prefix: Vec<u8> = prefix.into_storage_key();
+        let (approvals_by_id, next_approval_id_by_id) = if let Some(prefix) = approval_prefix {
+            let prefix: Vec<u8> = prefix.into_storage_key();
+            (
+                Some(LookupMap::new(prefix.clone())),
+                Some(LookupMap::new([prefix, "n".into()].concat())),
+            )
+        } else {
+            (None, None)
+        };
+
+        let mut this = Self {
+            supply_by_id: TreeMap::new([prefix.clone(), "s".into()].concat()),
+            balances: LookupMap::new([prefix.clone(), "b".into()].concat()),
+            tokens_per_owner: LookupMap::new([prefix.clone(), "o".into()].concat()),
+            storage_balances: StorageBalances::new([prefix.clone(), "r".into()].concat(), None),
+            prefix,
+            token_metadata_by_id: token_metadata_prefix.map(LookupMap::new),
+            approvals_by_id,
+            next_approval_id_by_id,
+        };
+        this.measure_account_storage_usage();
+        this
+    }
+
+    fn measure_account_storage_usage(&mut self) {
+        let initial_storage_usage = env::storage_usage();
+        let tmp_account_id = AccountId::new_unchecked("a".repeat(64));
+        self.internal_register_account(&tmp_account_id);
+        self.storage_balances.account_storage_usage += env::storage_usage() - initial_storage_usage;
+        self.tokens_per_owner.remove(&tmp_account_id);
+    }
+
+    fn tokens_of_owner_prefix(&self, account_id: &AccountId) -> Vec<u8> {
+        [self.prefix.clone(), "t".into(), env::sha256(account_id.as_bytes())].concat()
+    }
+
+    pub fn internal_register_account(&mut self, account_id: &AccountId) {
+        let tokens = UnorderedSet::new(self.tokens_of_owner_prefix(account_id));
+        if self.tokens_per_owner.insert(account_id, &tokens).is_some() {
+            env::panic_str("The account is already registered");
+        }
+    }
+
+    pub fn internal_unwrap_balance_of(
+        &self,
+        #[allow(clippy::ptr_arg)] token_id: &TokenId,
+        account_id: &AccountId,
+    ) -> Balance {
+        if !self.tokens_per_owner.contains_key(account_id) {
+            env::panic_str(format!("The account {} is not registered", &account_id).as_str())
+        }
+        self.balances.get(&(token_id.clone(), account_id.clone())).unwrap_or(0)
+    }
+
+    /// Adds `amount` to the balance of `account_id`, charging the account for a new balance.
+    pub fn internal_deposit(
+        &mut self,
+        #[allow(clippy::ptr_arg)] token_id: &TokenId,
+        account_id: &AccountId,
+        amount: Balance,
+    ) {
+        let initial_storage_usage = env::storage_usage();
+        self.internal_add_balance(token_id, account_id, amount);
+        self.storage_balances.settle(account_id, initial_storage_usage);
+    }
+
+    fn internal_add_balance(
+        &mut self,
+        #[allow(clippy::ptr_arg)] token_id: &TokenId,
+        account_id: &AccountId,
+        amount: Balance,
+    ) {
+        let mut tokens = self.tokens_per_owner.get(account_id).unwrap_or_else(|| {
+            env::panic_str(format!("The account {} is not registered", &account_id).as_str())
+        });
+        let key = (token_id.clone(), account_id.clone());
+        let balance = self.balances.get(&key).unwrap_or(0);
+        let new_balance =
+            balance.checked_add(amount).unwrap_or_else(|| env::panic_str("Balance overflow"));
+        self.balances.insert(&key, &new_balance);
+        if balance == 0 {
+            tokens.insert(token_id);
+            self.tokens_per_owner.insert(account_id, &tokens);
+        }
+    }
+
+    /// Subtracts `amount` from the balance of `account_id`, releasing the storage of the balance
+    /// when it drops to zero.
+    pub fn internal_withdraw(
+        &mut self,
+        #[allow(clippy::ptr_arg)] token_id: &TokenId,
+        account_id: &AccountId,
+        amount: Balance,
+    ) {
+        let initial_storage_usage = env::storage_usage();
+        let balance = self.internal_unwrap_balance_of(token_id, account_id);
+        let new_balance = balance
+            .checked_sub(amount)
+            .unwrap_or_else(|| env::panic_str("The account doesn't have enough balance"));
+        let key = (token_id.clone(), account_id.clone());
+        if new_balance > 0 {
+            self.balances.insert(&key, &new_balance);
+        } else if self.balances.remove(&key).is_some() {
+            // the account was registered, checked above
+            let mut tokens = self.tokens_per_owner.get(account_id).unwrap();
+            tokens.remove(token_id);
+            self.tokens_per_owner.insert(account_id, &tokens);
+        }
+        self.storage_balances.settle(account_id, initial_storage_usage);
+    }
+
+    /// Uses `amount` of the tokens `account_id` is approved to transfer on behalf of `owner_id`.
+    fn internal_use_approval(
+        &mut self,
+        #[allow(clippy::ptr_arg)] token_id: &TokenId,
+        owner_id: &AccountId,
+        account_id: &AccountId,
+        approval_id: u64,
+        amount: Balance,
+    ) {
+        let approvals_by_id = self
+            .approvals_by_id
+            .as_mut()
+            .unwrap_or_else(|| env::panic_str("MT does not support Approval Management"));
+        let key = (token_id.clone(), owner_id.clone());
+        let mut approvals = approvals_by_id.get(&key).unwrap_or_default();
+        let approval =
+            approvals.get_mut(account_id).unwrap_or_else(|| env::panic_str("Sender not approved"));
+        require!(
+            approval.approval_id == approval_id,
+            format!(
+                "The actual approval_id {} is different from the given approval_id {}",
+                approval.approval_id, approval_id
+            )
+        );
+        require!(amount <= approval.amount.0, "The approved amount is not enough");
+        approval.amount.0 -= amount;
+        approvals_by_id.insert(&key, &approvals);
+    }
+
+    /// Gives back `amount` to the approval of `account_id`, if it wasn't changed in the meantime.
+    fn internal_restore_approval(
+        &mut self,
+        #[allow(clippy::ptr_arg)] token_id: &TokenId,
+        owner_id: &AccountId,
+        account_id: &AccountId,
+        approval_id: u64,
+        amount: Balance,
+    ) {
+        if let Some(approvals_by_id) = &mut self.approvals_by_id {
+            let key = (token_id.clone(), owner_id.clone());
+            if let Some(mut approvals) = approvals_by_id.get(&key) {
+                if let Some(approval) = approvals.get_mut(account_id) {
+                    if approval.approval_id == approval_id {
+                        approval.amount.0 += amount;
+                        approvals_by_id.insert(&key, &approvals);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Transfer `amount` of the token from its owner to `receiver_id`, checking that sender is
+    /// allowed to transfer. The owner is the sender, unless an approval is given.
+    /// Return the owner and the approval used, without logging an event.
+    pub fn internal_transfer(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        #[allow(clippy::ptr_arg)] token_id: &TokenId,
+        amount: Balance,
+        approval: Option<(AccountId, u64)>,
+    ) -> (AccountId, Option<(AccountId, u64)>) {
+        let (owner_id, used_approval) = match approval {
+            Some((owner_id, approval_id)) if &owner_id != sender_id => {
+                self.internal_use_approval(token_id, &owner_id, sender_id, approval_id, amount);
+                (owner_id, Some((sender_id.clone(), approval_id)))
+            }
+            _ => (sender_id.clone(), None),
+        };
+
+        require!(&owner_id != receiver_id, "Sender and receiver should be different");
+        require!(amount > 0, "The amount should be a positive number");
+
+        self.internal_withdraw(token_id, &owner_id, amount);
+        self.internal_deposit(token_id, receiver_id, amount);
+
+        (owner_id, used_approval)
+    }
+
+    /// Transfers each of the tokens with [`MultiToken::internal_transfer`] and logs a single
+    /// `mt_transfer` event for all of them.
+    /// Return the previous owners and the approvals used.
+    pub fn internal_batch_transfer(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        token_ids: &[TokenId],
+        amounts: &[U128],
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+    ) -> (Vec<AccountId>, Vec<Option<(AccountId, u64)>>) {
+        require!(!token_ids.is_empty(), "At least one token must be transferred");
+        require!(
+            token_ids.len() == amounts.len(),
+            "The number of token_ids and amounts must match"
+        );
+        let approvals = approvals.unwrap_or_else(|| vec![None; token_ids.len()]);
+        require!(
+            token_ids.len() == approvals.len(),
+            "The number of token_ids and approvals must match"
+        );
+
+        let mut previous_owner_ids = Vec::with_capacity(token_ids.len());
+        let mut used_approvals = Vec::with_capacity(token_ids.len());
+        let mut events = Vec::with_capacity(token_ids.len());
+        for ((token_id, amount), approval) in token_ids.iter().zip(amounts).zip(approvals) {
+            let (owner_id, used_approval) =
+                self.internal_transfer(sender_id, receiver_id, token_id, amount.0, approval);
+            events.push(MtTransfer {
+                authorized_id: used_approval.as_ref().map(|_| sender_id.clone()),
+                old_owner_id: owner_id.clone(),
+                new_owner_id: receiver_id.clone(),
+                token_ids: vec![token_id.clone()],
+                amounts: vec![*amount],
+                memo: memo.clone(),
+            });
+            previous_owner_ids.push(owner_id);
+            used_approvals.push(used_approval);
+        }
+        MtTransfer::emit_many(events);
+
+        (previous_owner_ids, used_approvals)
+    }
+
+    fn internal_batch_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>> {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        let (previous_owner_ids, used_approvals) = self.internal_batch_transfer(
+            &sender_id,
+            &receiver_id,
+            &token_ids,
+            &amounts,
+            approvals,
+            memo,
+        );
+        let used_approvals =
+            if used_approvals.iter().any(Option::is_some) { Some(used_approvals) } else { None };
+        let gas_for_resolve_transfer = GAS_FOR_RESOLVE_TRANSFER_PER_TOKEN * token_ids.len() as u64;
+        // Initiating receiver's call and the callback
+        ext_receiver::mt_on_transfer(
+            sender_id,
+            previous_owner_ids.clone(),
+            token_ids.clone(),
+            amounts.clone(),
+            msg,
+            receiver_id.clone(),
+            NO_DEPOSIT,
+            env::prepaid_gas() - GAS_FOR_MT_TRANSFER_CALL - gas_for_resolve_transfer,
+        )
+        .then(ext_self::mt_resolve_transfer(
+            previous_owner_ids,
+            receiver_id,
+            token_ids,
+            amounts,
+            used_approvals,
+            env::current_account_id(),
+            NO_DEPOSIT,
+            gas_for_resolve_transfer,
+        ))
+        .into()
+    }
+
+    /// Mint `amount` of a token for `owner_id`, creating the token if it doesn't exist yet.
+    /// Not part of official standard, but needed in most situations. Consuming contract
+    /// expected to wrap this with an `mt_mint` function.
+    ///
+    /// Requirements:
+    /// * `owner_id` must be registered, and pays for its new balance with its storage balance.
+    /// * If contract is using Metadata extension (by having provided `token_metadata_prefix`
+    ///   during contract initialization), `token_metadata` must be given when the token is
+    ///   created, and only then.
+    /// * Caller of the method must attach a deposit to cover the storage used by the token.
+    ///
+    /// Returns the token with its new supply
+    pub fn internal_mint(
+        &mut self,
+        token_id: TokenId,
+        owner_id: AccountId,
+        amount: Balance,
+        token_metadata: Option<TokenMetadata>,
+    ) -> Token {
+        let initial_storage_usage = env::storage_usage();
+        require!(amount > 0, "The amount should be a positive number");
+
+        let supply = match self.supply_by_id.get(&token_id) {
+            Some(supply) => {
+                if token_metadata.is_some() {
+                    env::panic_str("Metadata can only be given when the token is created");
+                }
+                supply
+                    .checked_add(amount)
+                    .unwrap_or_else(|| env::panic_str("Total supply overflow"))
+            }
+            None => {
+                if let Some(token_metadata_by_id) = &mut self.token_metadata_by_id {
+                    let token_metadata = token_metadata
+                        .as_ref()
+                        .unwrap_or_else(|| env::panic_str("Must provide metadata"));
+                    token_metadata_by_id.insert(&token_id, token_metadata);
+                }
+                amount
+            }
+        };
+        self.supply_by_id.insert(&token_id, &supply);
+        let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+        self.internal_deposit(&token_id, &owner_id, amount);
+
+        MtMint {
+            owner_id,
+            token_ids: vec![token_id.clone()],
+            amounts: vec![amount.into()],
+            memo: None,
+        }
+        .emit();
+
+        // Return any extra attached deposit not used for storage
+        refund_deposit(storage_used);
+
+        let metadata = self.token_metadata_by_id.as_ref().and_then(|by_id| by_id.get(&token_id));
+        Token { token_id, supply: supply.into(), metadata }
+    }
+
+    /// Burn `amount` of the token held by `owner_id`. The token keeps existing even if its
+    /// supply drops to zero.
+    pub fn internal_burn(
+        &mut self,
+        token_id: TokenId,
+        owner_id: AccountId,
+        amount: Balance,
+        memo: Option<String>,
+    ) {
+        require!(amount > 0, "The amount should be a positive number");
+        self.internal_withdraw(&token_id, &owner_id, amount);
+        // the owner had enough balance, so the supply is at least as large
+        let supply = self.supply_by_id.get(&token_id).unwrap_or_default();
+        self.supply_by_id.insert(&token_id, &(supply - amount));
+
+        MtBurn {
+            owner_id,
+            authorized_id: None,
+            token_ids: vec![token_id],
+            amounts: vec![amount.into()],
+            memo,
+        }
+        .emit();
+    }
+
+    fn internal_token(&self, token_id: TokenId) -> Option<Token> {
+        let supply = self.supply_by_id.get(&token_id)?;
+        let metadata = self.token_metadata_by_id.as_ref().and_then(|by_id| by_id.get(&token_id));
+        Some(Token { token_id, supply: supply.into(), metadata })
+    }
+}
+
+impl MultiTokenCore for MultiToken {
+    fn mt_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        approval: Option<(AccountId, u64)>,
+        memo: Option<String>,
+    ) {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        self.internal_batch_transfer(
+            &sender_id,
+            &receiver_id,
+            &[token_id],
+            &[amount],
+            Some(vec![approval]),
+            memo,
+        );
+    }
+
+    fn mt_batch_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+    ) {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        self.internal_batch_transfer(
+            &sender_id,
+            &receiver_id,
+            &token_ids,
+            &amounts,
+            approvals,
+            memo,
+        );
+    }
+
+    fn mt_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        approval: Option<(AccountId, u64)>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>> {
+        self.internal_batch_transfer_call(
+            receiver_id,
+            vec![token_id],
+            vec![amount],
+            Some(vec![approval]),
+            memo,
+            msg,
+        )
+    }
+
+    fn mt_batch_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>> {
+        self.internal_batch_transfer_call(receiver_id, token_ids, amounts, approvals, memo, msg)
+    }
+
+    fn mt_token(&self, token_ids: Vec<TokenId>) -> Vec<Option<Token>> {
+        token_ids.into_iter().map(|token_id| self.internal_token(token_id)).collect()
+    }
+
+    fn mt_balance_of(&self, account_id: AccountId, token_id: TokenId) -> U128 {
+        self.balances.get(&(token_id, account_id)).unwrap_or(0).into()
+    }
+
+    fn mt_batch_balance_of(&self, account_id: AccountId, token_ids: Vec<TokenId>) -> Vec<U128> {
+        token_ids
+            .into_iter()
+            .map(|token_id| self.balances.get(&(token_id, account_id.clone())).unwrap_or(0).into())
+            .collect()
+    }
+
+    fn mt_supply(&self, token_id: TokenId) -> Option<U128> {
+        self.supply_by_id.get(&token_id).map(U128::from)
+    }
+
+    fn mt_batch_supply(&self, token_ids: Vec<TokenId>) -> Vec<Option<U128>> {
+        token_ids.into_iter().map(|token_id| self.mt_supply(token_id)).collect()
+    }
+}
+
+impl MultiTokenResolver for MultiToken {
+    /// Returns the number of each of the tokens that were used by the receiver. Tokens returned
+    /// to a previous owner that unregistered in the meantime are burned. The contract pays for
+    /// the balances restored for previous owners that can't pay for them anymore.
+    fn mt_resolve_transfer(
+        &mut self,
+        previous_owner_ids: Vec<AccountId>,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+    ) -> Vec<U128> {
+        // Get the unused amounts from the `mt_on_transfer` call result.
+        let unused_amounts: Vec<Balance> = match env::promise_result(0) {
+            PromiseResult::NotReady => env::abort(),
+            PromiseResult::Successful(value) => {
+                match near_sdk::serde_json::from_slice::<Vec<U128>>(&value) {
+                    Ok(unused_amounts) if unused_amounts.len() == amounts.len() => unused_amounts
+                        .iter()
+                        .zip(&amounts)
+                        .map(|(unused_amount, amount)| std::cmp::min(amount.0, unused_amount.0))
+                        .collect(),
+                    _ => amounts.iter().map(|amount| amount.0).collect(),
+                }
+            }
+            PromiseResult::Failed => amounts.iter().map(|amount| amount.0).collect(),
+        };
+
+        let mut used_amounts = Vec::with_capacity(token_ids.len());
+        let mut refunds = Vec::new();
+        let mut burns = Vec::new();
+        for (i, token_id) in token_ids.iter().enumerate() {
+            let amount = amounts[i].0;
+            let previous_owner_id = &previous_owner_ids[i];
+            let receiver_balance =
+                self.balances.get(&(token_id.clone(), receiver_id.clone())).unwrap_or(0);
+            let refund_amount = std::cmp::min(receiver_balance, unused_amounts[i]);
+            if refund_amount == 0 {
+                used_amounts.push(amount.into());
+                continue;
+            }
+
+            self.internal_withdraw(token_id, &receiver_id, refund_amount);
+            if self.tokens_per_owner.contains_key(previous_owner_id) {
+                let initial_storage_usage = env::storage_usage();
+                self.internal_add_balance(token_id, previous_owner_id, refund_amount);
+                self.storage_balances.try_settle(previous_owner_id, initial_storage_usage);
+                if let Some((account_id, approval_id)) =
+                    approvals.as_ref().and_then(|approvals| approvals.get(i).cloned().flatten())
+                {
+                    self.internal_restore_approval(
+                        token_id,
+                        previous_owner_id,
+                        &account_id,
+                        approval_id,
+                        refund_amount,
+                    );
+                }
+                refunds.push(MtTransfer {
+                    authorized_id: None,
+                    old_owner_id: receiver_id.clone(),
+                    new_owner_id: previous_owner_id.clone(),
+                    token_ids: vec![token_id.clone()],
+                    amounts: vec![refund_amount.into()],
+                    memo: None,
+                });
+                used_amounts.push((amount - refund_amount).into());
+            } else {
+                // The previous owner's account was deleted, so we need to burn tokens.
+                let supply = self.supply_by_id.get(token_id).unwrap_or_default();
+                self.supply_by_id.insert(token_id, &(supply - refund_amount));
+                burns.push(MtBurn {
+                    owner_id: receiver_id.clone(),
+                    authorized_id: None,
+                    token_ids: vec![token_id.clone()],
+                    amounts: vec![refund_amount.into()],
+                    memo: Some(format!("The account {} was deleted", previous_owner_id)),
+                });
+                used_amounts.push(amount.into());
+            }
+        }
+        if !refunds.is_empty() {
+            MtTransfer::emit_many(refunds);
+        }
+        if !burns.is_empty() {
+            MtBurn::emit_many(burns);
+        }
+        used_amounts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::multi_token::approval::MultiTokenApproval;
+    use crate::storage_management::StorageManagement;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    const STORAGE_DEPOSIT: Balance = 10u128.pow(23);
+
+    fn context(predecessor_account_id: AccountId, attached_deposit: Balance) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .predecessor_account_id(predecessor_account_id)
+            .attached_deposit(attached_deposit);
+        builder
+    }
+
+    /// A token with the approval extension, `amount` of "gold" minted for bob and charlie
+    /// registered to receive it.
+    fn setup(amount: Balance) -> MultiToken {
+        testing_env!(context(accounts(0), 0).build());
+        let mut mt = MultiToken::new(b"m".to_vec(), None::<Vec<u8>>, Some(b"a".to_vec()));
+        for account_id in [accounts(1), accounts(2)] {
+            testing_env!(context(account_id, STORAGE_DEPOSIT).build());
+            mt.storage_deposit(None, None);
+        }
+        testing_env!(context(accounts(0), STORAGE_DEPOSIT).build());
+        mt.internal_mint("gold".to_string(), accounts(1), amount, None);
+        mt
+    }
+
+    /// Resolves the transfer of `amount` gold from bob to charlie with `result` as the
+    /// result of `mt_on_transfer`.
+    fn resolve(
+        mt: &mut MultiToken,
+        amount: Balance,
+        approval: Option<(AccountId, u64)>,
+        result: PromiseResult,
+    ) -> Vec<U128> {
+        testing_env!(
+            context(accounts(0), 0).build(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            vec![result],
+        );
+        mt.mt_resolve_transfer(
+            vec![accounts(1)],
+            accounts(2),
+            vec!["gold".to_string()],
+            vec![U128(amount)],
+            approval.map(|approval| vec![Some(approval)]),
+        )
+    }
+
+    fn unused(amount: Balance) -> PromiseResult {
+        PromiseResult::Successful(near_sdk::serde_json::to_vec(&vec![U128(amount)]).unwrap())
+    }
+
+    fn balance_of(mt: &MultiToken, account_id: AccountId) -> Balance {
+        mt.mt_balance_of(account_id, "gold".to_string()).0
+    }
+
+    fn available_storage_balance(mt: &MultiToken, account_id: AccountId) -> Balance {
+        mt.storage_balance_of(account_id).unwrap().available.0
+    }
+
+    #[test]
+    fn receiver_pays_for_new_balances() {
+        let mut mt = setup(100);
+        let available = available_storage_balance(&mt, accounts(2));
+        testing_env!(context(accounts(1), 1).build());
+        mt.mt_transfer(accounts(2), "gold".to_string(), U128(100), None, None);
+        assert!(available_storage_balance(&mt, accounts(2)) < available);
+
+        // The sender's balance dropped to zero, so its storage is released.
+        let registration_cost = mt.storage_balance_bounds().min.0;
+        assert_eq!(
+            available_storage_balance(&mt, accounts(1)),
+            STORAGE_DEPOSIT - registration_cost
+        );
+    }
+
+    #[test]
+    fn resolve_transfer_refunds_unused_amount() {
+        let mut mt = setup(100);
+        testing_env!(context(accounts(1), 1).build());
+        mt.internal_batch_transfer(
+            &accounts(1),
+            &accounts(2),
+            &["gold".to_string()],
+            &[U128(100)],
+            None,
+            None,
+        );
+
+        assert_eq!(resolve(&mut mt, 100, None, unused(30)), vec![U128(70)]);
+        assert_eq!(balance_of(&mt, accounts(1)), 30);
+        assert_eq!(balance_of(&mt, accounts(2)), 70);
+        assert_eq!(mt.mt_supply("gold".to_string()), Some(U128(100)));
+    }
+
+    #[test]
+    fn resolve_transfer_refunds_at_most_the_receiver_balance() {
+        let mut mt = setup(100);
+        testing_env!(context(accounts(1), 1).build());
+        mt.internal_batch_transfer(
+            &accounts(1),
+            &accounts(2),
+            &["gold".to_string()],
+            &[U128(100)],
+            None,
+            None,
+        );
+        // The receiver spent some of the tokens before the resolution.
+        testing_env!(context(accounts(2), 1).build());
+        mt.internal_burn("gold".to_string(), accounts(2), 80, None);
+
+        assert_eq!(resolve(&mut mt, 100, None, PromiseResult::Failed), vec![U128(80)]);
+        assert_eq!(balance_of(&mt, accounts(1)), 20);
+        assert_eq!(balance_of(&mt, accounts(2)), 0);
+    }
+
+    #[test]
+    fn resolve_transfer_burns_refund_of_unregistered_owner() {
+        let mut mt = setup(100);
+        testing_env!(context(accounts(1), 1).build());
+        mt.internal_batch_transfer(
+            &accounts(1),
+            &accounts(2),
+            &["gold".to_string()],
+            &[U128(100)],
+            None,
+            None,
+        );
+        assert!(mt.storage_unregister(None));
+
+        assert_eq!(resolve(&mut mt, 100, None, PromiseResult::Failed), vec![U128(100)]);
+        assert_eq!(balance_of(&mt, accounts(2)), 0);
+        assert_eq!(mt.mt_supply("gold".to_string()), Some(U128(0)));
+        assert!(mt.storage_balance_of(accounts(1)).is_none());
+    }
+
+    #[test]
+    fn resolve_transfer_refunds_owner_without_storage_balance() {
+        let mut mt = setup(100);
+        testing_env!(context(accounts(1), 1).build());
+        mt.internal_batch_transfer(
+            &accounts(1),
+            &accounts(2),
+            &["gold".to_string()],
+            &[U128(100)],
+            None,
+            None,
+        );
+        mt.storage_withdraw(None);
+        assert_eq!(available_storage_balance(&mt, accounts(1)), 0);
+
+        // The contract pays for the restored balance instead of failing the callback.
+        assert_eq!(resolve(&mut mt, 100, None, PromiseResult::Failed), vec![U128(0)]);
+        assert_eq!(balance_of(&mt, accounts(1)), 100);
+        assert_eq!(balance_of(&mt, accounts(2)), 0);
+    }
+
+    #[test]
+    fn resolve_transfer_restores_approval() {
+        let mut mt = setup(100);
+        testing_env!(context(accounts(1), STORAGE_DEPOSIT).build());
+        mt.mt_approve(vec!["gold".to_string()], vec![U128(100)], accounts(3), None);
+        testing_env!(context(accounts(3), 1).build());
+        let (_, used_approvals) = mt.internal_batch_transfer(
+            &accounts(3),
+            &accounts(2),
+            &["gold".to_string()],
+            &[U128(60)],
+            Some(vec![Some((accounts(1), 1))]),
+            None,
+        );
+        assert_eq!(used_approvals, vec![Some((accounts(3), 1))]);
+        let is_approved = |mt: &MultiToken, amount: Balance| {
+            mt.mt_is_approved(
+                vec!["gold".to_string()],
+                accounts(1),
+                accounts(3),
+                vec![U128(amount)],
+                Some(vec![1]),
+            )
+        };
+        assert!(is_approved(&mt, 40));
+        assert!(!is_approved(&mt, 41));
+
+        assert_eq!(resolve(&mut mt, 60, Some((accounts(3), 1)), unused(20)), vec![U128(40)]);
+        assert_eq!(balance_of(&mt, accounts(1)), 60);
+        assert!(is_approved(&mt, 60));
+        assert!(!is_approved(&mt, 61));
+    }
+}
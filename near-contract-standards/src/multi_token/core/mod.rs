@@ -0,0 +1,115 @@
+mod core_impl;
+
+mod receiver;
+mod resolver;
+
+pub use self::core_impl::*;
+
+pub use self::receiver::*;
+pub use self::resolver::*;
+
+use crate::multi_token::token::{Token, TokenId};
+use near_sdk::json_types::U128;
+use near_sdk::AccountId;
+use near_sdk::PromiseOrValue;
+
+/// Used for all multi-tokens. The specification for the [core multi-token standard] lays out
+/// the reasoning for each method. It's important to check out
+/// [MultiTokenReceiver](crate::multi_token::core::MultiTokenReceiver) and
+/// [MultiTokenResolver](crate::multi_token::core::MultiTokenResolver) to understand how the
+/// cross-contract calls work.
+///
+/// [core multi-token standard]: https://nomicon.io/Standards/MultiToken/Core.html
+pub trait MultiTokenCore {
+    /// Simple transfer. Transfer `amount` of the `token_id` token to `receiver_id`.
+    ///
+    /// Requirements
+    /// * Caller of the method must attach a deposit of 1 yoctoⓃ for security purposes
+    /// * Contract MUST panic if called by someone other than the owner of the tokens or,
+    ///   if using Approval Management, an account approved for at least `amount`
+    /// * Both accounts must be registered with the contract for transfer to
+    ///   succeed, see the [storage management standard](https://nomicon.io/Standards/StorageManagement.html)
+    ///
+    /// Arguments:
+    /// * `receiver_id`: the valid NEAR account receiving the tokens
+    /// * `token_id`: the token to transfer
+    /// * `amount`: the number of tokens to transfer
+    /// * `approval`: the owner of the tokens and the expected approval ID, for use with
+    ///   Approval Management
+    /// * `memo` (optional): for use cases that may benefit from indexing or
+    ///   providing information for a transfer
+    fn mt_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        approval: Option<(AccountId, u64)>,
+        memo: Option<String>,
+    );
+
+    /// Transfers several tokens at once, with the same requirements as `mt_transfer` for
+    /// each of them. `token_ids`, `amounts` and `approvals` MUST have the same length.
+    fn mt_batch_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+    );
+
+    /// Transfer tokens and call a method on a receiver contract. A successful
+    /// workflow will end in a success execution outcome to the callback on the MT
+    /// contract at the method `mt_resolve_transfer`.
+    ///
+    /// Requirements:
+    /// * Same as `mt_transfer`
+    /// * The receiving contract must implement `mt_on_transfer` according to the
+    ///   standard. If it does not, MT contract's `mt_resolve_transfer` MUST deal
+    ///   with the resulting failed cross-contract call and roll back the transfer.
+    /// * Contract MUST implement the behavior described in `mt_resolve_transfer`
+    ///
+    /// Arguments:
+    /// * Same as `mt_transfer`
+    /// * `msg`: specifies information needed by the receiving contract in
+    ///   order to properly handle the transfer. Can indicate both a function to
+    ///   call and the parameters to pass to that function.
+    ///
+    /// Returns the amount of tokens that were used by the receiver.
+    fn mt_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        approval: Option<(AccountId, u64)>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>>;
+
+    /// Transfers several tokens at once like `mt_batch_transfer` and calls `mt_on_transfer` on
+    /// the receiver once for all of them, like `mt_transfer_call`.
+    fn mt_batch_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>>;
+
+    /// Returns the tokens with the given `token_ids`, `null` for the ones that don't exist.
+    fn mt_token(&self, token_ids: Vec<TokenId>) -> Vec<Option<Token>>;
+
+    /// Returns the balance of the account for the token, `"0"` if it doesn't hold any.
+    fn mt_balance_of(&self, account_id: AccountId, token_id: TokenId) -> U128;
+
+    /// Returns the balances of the account for each of the tokens.
+    fn mt_batch_balance_of(&self, account_id: AccountId, token_ids: Vec<TokenId>) -> Vec<U128>;
+
+    /// Returns the total supply of the token, `null` if it doesn't exist.
+    fn mt_supply(&self, token_id: TokenId) -> Option<U128>;
+
+    /// Returns the total supplies of each of the tokens.
+    fn mt_batch_supply(&self, token_ids: Vec<TokenId>) -> Vec<Option<U128>>;
+}
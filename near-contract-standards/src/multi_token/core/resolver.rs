@@ -0,0 +1,42 @@
+use crate::multi_token::token::TokenId;
+use near_sdk::json_types::U128;
+use near_sdk::AccountId;
+
+/// Used when tokens are transferred using `mt_transfer_call`. This is the method that's called
+/// after `mt_on_transfer`. This trait is implemented on the MT contract.
+pub trait MultiTokenResolver {
+    /// Finalize an `mt_transfer_call` chain of cross-contract calls.
+    ///
+    /// The `mt_transfer_call` process:
+    ///
+    /// 1. Sender calls `mt_transfer_call` on MT contract
+    /// 2. MT contract transfers tokens from sender to receiver
+    /// 3. MT contract calls `mt_on_transfer` on receiver contract
+    /// 4. [receiver contract may make other cross-contract calls]
+    /// 5. MT contract resolves promise chain with `mt_resolve_transfer`, and may
+    ///    return the unused tokens to their previous owners
+    ///
+    /// Requirements:
+    /// * Contract MUST forbid calls to this function by any account except self
+    /// * If promise chain failed, contract MUST revert the transfers
+    /// * If promise chain resolves with unused amounts, contract MUST return them to the
+    ///   previous owners, as far as the receiver still holds the tokens
+    ///
+    /// Arguments:
+    /// * `previous_owner_ids`: the owners of the tokens prior to the call to `mt_transfer_call`
+    /// * `receiver_id`: the `receiver_id` argument given to `mt_transfer_call`
+    /// * `token_ids`: the tokens given to `mt_transfer_call`
+    /// * `amounts`: the number of each of the tokens given to `mt_transfer_call`
+    /// * `approvals`: if using Approval Management, the approved account and approval ID used
+    ///   for each of the transfers, so the approved amounts can be restored on revert.
+    ///
+    /// Returns the number of each of the tokens that were used by the receiver.
+    fn mt_resolve_transfer(
+        &mut self,
+        previous_owner_ids: Vec<AccountId>,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+    ) -> Vec<U128>;
+}
@@ -0,0 +1,109 @@
+//! Events emitted by the multi-token contract, following the [events standard]. Each event is
+//! logged as a JSON string prefixed with `EVENT_JSON:`, so indexers can follow the changes of
+//! the balances without tracking the calls to the contract.
+//!
+//! The [`MultiToken`](crate::multi_token::MultiToken) methods emit these events already.
+//! Contracts changing the balances through other means should emit them as well.
+//!
+//! [events standard]: https://nomicon.io/Standards/EventsFormat.html
+use crate::multi_token::token::TokenId;
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{env, AccountId};
+
+/// Tokens were minted for `owner_id`.
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MtMint {
+    pub owner_id: AccountId,
+    pub token_ids: Vec<TokenId>,
+    pub amounts: Vec<U128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+impl MtMint {
+    pub fn emit(self) {
+        Self::emit_many(vec![self])
+    }
+
+    pub fn emit_many(data: Vec<MtMint>) {
+        MtEvent::Mint(data).emit()
+    }
+}
+
+/// Tokens were transferred from `old_owner_id` to `new_owner_id`. `authorized_id` is set when
+/// the transfer was made by an approved account.
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MtTransfer {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_id: Option<AccountId>,
+    pub old_owner_id: AccountId,
+    pub new_owner_id: AccountId,
+    pub token_ids: Vec<TokenId>,
+    pub amounts: Vec<U128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+impl MtTransfer {
+    pub fn emit(self) {
+        Self::emit_many(vec![self])
+    }
+
+    pub fn emit_many(data: Vec<MtTransfer>) {
+        MtEvent::Transfer(data).emit()
+    }
+}
+
+/// Tokens of `owner_id` were burned.
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MtBurn {
+    pub owner_id: AccountId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_id: Option<AccountId>,
+    pub token_ids: Vec<TokenId>,
+    pub amounts: Vec<U128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+impl MtBurn {
+    pub fn emit(self) {
+        Self::emit_many(vec![self])
+    }
+
+    pub fn emit_many(data: Vec<MtBurn>) {
+        MtEvent::Burn(data).emit()
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data")]
+enum MtEvent {
+    #[serde(rename = "mt_mint")]
+    Mint(Vec<MtMint>),
+    #[serde(rename = "mt_transfer")]
+    Transfer(Vec<MtTransfer>),
+    #[serde(rename = "mt_burn")]
+    Burn(Vec<MtBurn>),
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+struct EventLog {
+    standard: &'static str,
+    version: &'static str,
+    #[serde(flatten)]
+    event: MtEvent,
+}
+
+impl MtEvent {
+    fn emit(self) {
+        let log = EventLog { standard: "nep245", version: "1.0.0", event: self };
+        env::log_str(&format!("EVENT_JSON:{}", near_sdk::serde_json::to_string(&log).unwrap()));
+    }
+}
@@ -0,0 +1,262 @@
+/// The core methods for a basic multi-token. Extension standards may be
+/// added in addition to this macro.
+#[macro_export]
+macro_rules! impl_multi_token_core {
+    ($contract: ident, $token: ident) => {
+        use $crate::multi_token::core::MultiTokenCore;
+        use $crate::multi_token::core::MultiTokenResolver;
+
+        #[near_bindgen]
+        impl MultiTokenCore for $contract {
+            #[payable]
+            fn mt_transfer(
+                &mut self,
+                receiver_id: AccountId,
+                token_id: $crate::multi_token::TokenId,
+                amount: near_sdk::json_types::U128,
+                approval: Option<(AccountId, u64)>,
+                memo: Option<String>,
+            ) {
+                self.$token.mt_transfer(receiver_id, token_id, amount, approval, memo)
+            }
+
+            #[payable]
+            fn mt_batch_transfer(
+                &mut self,
+                receiver_id: AccountId,
+                token_ids: Vec<$crate::multi_token::TokenId>,
+                amounts: Vec<near_sdk::json_types::U128>,
+                approvals: Option<Vec<Option<(AccountId, u64)>>>,
+                memo: Option<String>,
+            ) {
+                self.$token.mt_batch_transfer(receiver_id, token_ids, amounts, approvals, memo)
+            }
+
+            #[payable]
+            fn mt_transfer_call(
+                &mut self,
+                receiver_id: AccountId,
+                token_id: $crate::multi_token::TokenId,
+                amount: near_sdk::json_types::U128,
+                approval: Option<(AccountId, u64)>,
+                memo: Option<String>,
+                msg: String,
+            ) -> PromiseOrValue<Vec<near_sdk::json_types::U128>> {
+                self.$token.mt_transfer_call(receiver_id, token_id, amount, approval, memo, msg)
+            }
+
+            #[payable]
+            fn mt_batch_transfer_call(
+                &mut self,
+                receiver_id: AccountId,
+                token_ids: Vec<$crate::multi_token::TokenId>,
+                amounts: Vec<near_sdk::json_types::U128>,
+                approvals: Option<Vec<Option<(AccountId, u64)>>>,
+                memo: Option<String>,
+                msg: String,
+            ) -> PromiseOrValue<Vec<near_sdk::json_types::U128>> {
+                self.$token.mt_batch_transfer_call(
+                    receiver_id,
+                    token_ids,
+                    amounts,
+                    approvals,
+                    memo,
+                    msg,
+                )
+            }
+
+            fn mt_token(
+                &self,
+                token_ids: Vec<$crate::multi_token::TokenId>,
+            ) -> Vec<Option<$crate::multi_token::Token>> {
+                self.$token.mt_token(token_ids)
+            }
+
+            fn mt_balance_of(
+                &self,
+                account_id: AccountId,
+                token_id: $crate::multi_token::TokenId,
+            ) -> near_sdk::json_types::U128 {
+                self.$token.mt_balance_of(account_id, token_id)
+            }
+
+            fn mt_batch_balance_of(
+                &self,
+                account_id: AccountId,
+                token_ids: Vec<$crate::multi_token::TokenId>,
+            ) -> Vec<near_sdk::json_types::U128> {
+                self.$token.mt_batch_balance_of(account_id, token_ids)
+            }
+
+            fn mt_supply(
+                &self,
+                token_id: $crate::multi_token::TokenId,
+            ) -> Option<near_sdk::json_types::U128> {
+                self.$token.mt_supply(token_id)
+            }
+
+            fn mt_batch_supply(
+                &self,
+                token_ids: Vec<$crate::multi_token::TokenId>,
+            ) -> Vec<Option<near_sdk::json_types::U128>> {
+                self.$token.mt_batch_supply(token_ids)
+            }
+        }
+
+        #[near_bindgen]
+        impl MultiTokenResolver for $contract {
+            #[private]
+            fn mt_resolve_transfer(
+                &mut self,
+                previous_owner_ids: Vec<AccountId>,
+                receiver_id: AccountId,
+                token_ids: Vec<$crate::multi_token::TokenId>,
+                amounts: Vec<near_sdk::json_types::U128>,
+                approvals: Option<Vec<Option<(AccountId, u64)>>>,
+            ) -> Vec<near_sdk::json_types::U128> {
+                self.$token.mt_resolve_transfer(
+                    previous_owner_ids,
+                    receiver_id,
+                    token_ids,
+                    amounts,
+                    approvals,
+                )
+            }
+        }
+    };
+}
+
+/// Multi-token approval management allows for an escrow system where every owner
+/// approves accounts to transfer up to some amount of its tokens.
+#[macro_export]
+macro_rules! impl_multi_token_approval {
+    ($contract: ident, $token: ident) => {
+        use $crate::multi_token::approval::MultiTokenApproval;
+
+        #[near_bindgen]
+        impl MultiTokenApproval for $contract {
+            #[payable]
+            fn mt_approve(
+                &mut self,
+                token_ids: Vec<$crate::multi_token::TokenId>,
+                amounts: Vec<near_sdk::json_types::U128>,
+                account_id: AccountId,
+                msg: Option<String>,
+            ) -> Option<Promise> {
+                self.$token.mt_approve(token_ids, amounts, account_id, msg)
+            }
+
+            #[payable]
+            fn mt_revoke(
+                &mut self,
+                token_ids: Vec<$crate::multi_token::TokenId>,
+                account_id: AccountId,
+            ) {
+                self.$token.mt_revoke(token_ids, account_id)
+            }
+
+            #[payable]
+            fn mt_revoke_all(&mut self, token_ids: Vec<$crate::multi_token::TokenId>) {
+                self.$token.mt_revoke_all(token_ids)
+            }
+
+            fn mt_is_approved(
+                &self,
+                token_ids: Vec<$crate::multi_token::TokenId>,
+                owner_id: AccountId,
+                approved_account_id: AccountId,
+                amounts: Vec<near_sdk::json_types::U128>,
+                approval_ids: Option<Vec<u64>>,
+            ) -> bool {
+                self.$token.mt_is_approved(
+                    token_ids,
+                    owner_id,
+                    approved_account_id,
+                    amounts,
+                    approval_ids,
+                )
+            }
+        }
+    };
+}
+
+/// Multi-token enumeration adds the extension standard offering
+/// view-only methods to page through all the tokens and the tokens of an owner.
+#[macro_export]
+macro_rules! impl_multi_token_enumeration {
+    ($contract: ident, $token: ident) => {
+        use $crate::multi_token::enumeration::MultiTokenEnumeration;
+
+        #[near_bindgen]
+        impl MultiTokenEnumeration for $contract {
+            fn mt_tokens(
+                &self,
+                from_index: Option<near_sdk::json_types::U128>,
+                limit: Option<u64>,
+            ) -> Vec<$crate::multi_token::Token> {
+                self.$token.mt_tokens(from_index, limit)
+            }
+
+            fn mt_tokens_for_owner(
+                &self,
+                account_id: AccountId,
+                from_index: Option<near_sdk::json_types::U128>,
+                limit: Option<u64>,
+            ) -> Vec<$crate::multi_token::Token> {
+                self.$token.mt_tokens_for_owner(account_id, from_index, limit)
+            }
+        }
+    };
+}
+
+/// Ensures that accounts pay for their registration and the balances of their tokens with
+/// the storage management standard.
+/// Takes name of the Contract struct, the inner field for the token and optional method name to
+/// call when the account was closed, with the balances of the tokens that were burned.
+#[macro_export]
+macro_rules! impl_multi_token_storage {
+    ($contract: ident, $token: ident $(, $on_account_closed_fn:ident)?) => {
+        use $crate::storage_management::{
+            StorageManagement, StorageBalance, StorageBalanceBounds
+        };
+
+        #[near_bindgen]
+        impl StorageManagement for $contract {
+            #[payable]
+            fn storage_deposit(
+                &mut self,
+                account_id: Option<AccountId>,
+                registration_only: Option<bool>,
+            ) -> StorageBalance {
+                self.$token.storage_deposit(account_id, registration_only)
+            }
+
+            #[payable]
+            fn storage_withdraw(
+                &mut self,
+                amount: Option<near_sdk::json_types::U128>,
+            ) -> StorageBalance {
+                self.$token.storage_withdraw(amount)
+            }
+
+            #[payable]
+            fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+                #[allow(unused_variables)]
+                if let Some((account_id, burned)) = self.$token.internal_storage_unregister(force) {
+                    $(self.$on_account_closed_fn(account_id, burned);)?
+                    true
+                } else {
+                    false
+                }
+            }
+
+            fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+                self.$token.storage_balance_bounds()
+            }
+
+            fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+                self.$token.storage_balance_of(account_id)
+            }
+        }
+    };
+}
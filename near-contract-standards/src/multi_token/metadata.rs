@@ -0,0 +1,41 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::require;
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// Metadata of each kind of token, which has the same fields as the metadata of a
+/// non-fungible token.
+pub use crate::non_fungible_token::metadata::TokenMetadata;
+
+/// This spec can be treated like a version of the standard.
+pub const MT_METADATA_SPEC: &str = "mt-1.0.0";
+
+/// Metadata for the multi-token contract itself.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MTContractMetadata {
+    pub spec: String,              // required, essentially a version like "mt-1.0.0"
+    pub name: String,              // required, ex. "Zoo Items"
+    pub icon: Option<String>,      // Data URL
+    pub base_uri: Option<String>, // Centralized gateway known to have reliable access to decentralized storage assets referenced by `reference` or `media` URLs
+    pub reference: Option<String>, // URL to a JSON file with more info
+    pub reference_hash: Option<Base64VecU8>, // Base64-encoded sha256 hash of JSON from reference field. Required if `reference` is included.
+}
+
+/// Offers details on the contract-level metadata.
+pub trait MultiTokenMetadataProvider {
+    fn mt_metadata_contract(&self) -> MTContractMetadata;
+}
+
+impl MTContractMetadata {
+    pub fn assert_valid(&self) {
+        require!(self.spec == MT_METADATA_SPEC, "Spec is not MT metadata");
+        require!(
+            self.reference.is_some() == self.reference_hash.is_some(),
+            "Reference and reference hash must be present"
+        );
+        if let Some(reference_hash) = &self.reference_hash {
+            require!(reference_hash.0.len() == 32, "Hash has to be 32 bytes");
+        }
+    }
+}
@@ -0,0 +1,23 @@
+/// The [approval management standard](https://nomicon.io/Standards/MultiToken/ApprovalManagement.html) for multi-tokens.
+pub mod approval;
+/// The [core multi-token standard](https://nomicon.io/Standards/MultiToken/Core.html). This can be thought of as the base standard, with the others being extension standards.
+pub mod core;
+/// Trait for the [MT enumeration standard](https://nomicon.io/Standards/MultiToken/Enumeration.html).
+/// This provides view-only methods to page through all the tokens and the tokens of an owner.
+pub mod enumeration;
+/// The `mt_mint`, `mt_transfer` and `mt_burn` events of the multi-token standard.
+pub mod events;
+/// Macros typically used by a contract wanting to take advantage of the multi-token
+/// NEAR contract standard approach.
+mod macros;
+/// Metadata traits and implementation according to the [MT metadata standard](https://nomicon.io/Standards/MultiToken/Metadata.html).
+/// This covers both the contract metadata and the individual token metadata.
+pub mod metadata;
+/// Registration of the accounts holding tokens with the storage management standard.
+mod storage_impl;
+/// The Token struct for the multi-token.
+mod token;
+pub use self::token::{Approval, Token, TokenId};
+
+pub use self::core::MultiToken;
+pub use macros::*;
@@ -0,0 +1,91 @@
+use crate::multi_token::events::MtBurn;
+use crate::multi_token::token::TokenId;
+use crate::multi_token::MultiToken;
+use crate::storage_management::{StorageBalance, StorageBalanceBounds, StorageManagement};
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, log, AccountId, Balance};
+
+impl MultiToken {
+    /// Internal method that returns the Account ID and the burned balances of its tokens in case
+    /// the account was unregistered.
+    pub fn internal_storage_unregister(
+        &mut self,
+        force: Option<bool>,
+    ) -> Option<(AccountId, Vec<(TokenId, Balance)>)> {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let force = force.unwrap_or(false);
+        if let Some(mut tokens) = self.tokens_per_owner.get(&account_id) {
+            if tokens.is_empty() || force {
+                let mut burned = Vec::with_capacity(tokens.len() as usize);
+                for token_id in tokens.iter() {
+                    let key = (token_id.clone(), account_id.clone());
+                    let balance = self.balances.remove(&key).unwrap_or(0);
+                    let supply = self.supply_by_id.get(&token_id).unwrap_or_default();
+                    self.supply_by_id.insert(&token_id, &(supply - balance));
+                    if let Some(approvals_by_id) = &mut self.approvals_by_id {
+                        approvals_by_id.remove(&key);
+                    }
+                    burned.push((token_id, balance));
+                }
+                tokens.clear();
+                self.tokens_per_owner.remove(&account_id);
+                if !burned.is_empty() {
+                    MtBurn {
+                        owner_id: account_id.clone(),
+                        authorized_id: None,
+                        token_ids: burned.iter().map(|(token_id, _)| token_id.clone()).collect(),
+                        amounts: burned.iter().map(|(_, balance)| U128(*balance)).collect(),
+                        memo: None,
+                    }
+                    .emit();
+                }
+                // All the data of the account is removed, so the whole deposit is refunded.
+                self.storage_balances.internal_storage_unregister(Some(true));
+                Some((account_id, burned))
+            } else {
+                env::panic_str(
+                    "Can't unregister the account with the positive balance without force",
+                )
+            }
+        } else {
+            log!("The account {} is not registered", &account_id);
+            None
+        }
+    }
+}
+
+impl StorageManagement for MultiToken {
+    /// Registers the account and creates its set of tokens, the deposit above the registration
+    /// pays for the balances of the tokens it receives.
+    fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance {
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        let registered = self.storage_balances.is_registered(&account_id);
+        let storage_balance =
+            self.storage_balances.storage_deposit(Some(account_id.clone()), registration_only);
+        if !registered {
+            self.internal_register_account(&account_id);
+        }
+        storage_balance
+    }
+
+    fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        self.storage_balances.storage_withdraw(amount)
+    }
+
+    fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+        self.internal_storage_unregister(force).is_some()
+    }
+
+    fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        self.storage_balances.storage_balance_bounds()
+    }
+
+    fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        self.storage_balances.storage_balance_of(account_id)
+    }
+}
@@ -0,0 +1,27 @@
+use crate::multi_token::metadata::TokenMetadata;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// Token IDs are strings, like for non-fungible tokens. Every token ID identifies a kind of
+/// token, of which accounts can hold any amount up to the supply of the token.
+pub type TokenId = String;
+
+/// A kind of token with its total supply and, if the metadata extension is used, its metadata.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Token {
+    pub token_id: TokenId,
+    pub supply: U128,
+    pub metadata: Option<TokenMetadata>,
+}
+
+/// An account's approval to transfer up to `amount` of a token on behalf of its owner.
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, PartialEq, BorshDeserialize, BorshSerialize,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Approval {
+    pub approval_id: u64,
+    pub amount: U128,
+}
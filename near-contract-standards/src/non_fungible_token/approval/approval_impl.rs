@@ -3,10 +3,11 @@
 use crate::non_fungible_token::approval::NonFungibleTokenApproval;
 use crate::non_fungible_token::token::TokenId;
 use crate::non_fungible_token::utils::{
-    assert_at_least_one_yocto, bytes_for_approved_account_id, refund_approved_account_ids,
-    refund_approved_account_ids_iter, refund_deposit,
+    assert_at_least_one_yocto, bytes_for_approved_account_id, refund_deposit,
+    release_approved_account_ids_iter,
 };
 use crate::non_fungible_token::NonFungibleToken;
+use crate::storage_management::StorageBalances;
 use near_sdk::{assert_one_yocto, env, ext_contract, require, AccountId, Balance, Gas, Promise};
 
 const GAS_FOR_NFT_APPROVE: Gas = Gas(10_000_000_000_000);
@@ -31,12 +32,15 @@ pub trait NonFungibleTokenReceiver {
     );
 }
 
-impl NonFungibleTokenApproval for NonFungibleToken {
-    fn nft_approve(
+impl NonFungibleToken {
+    /// Like [`NonFungibleTokenApproval::nft_approve`], with the owner paying for the approval
+    /// with `storage_balances` instead of the attached deposit.
+    pub fn nft_approve_with_storage(
         &mut self,
         token_id: TokenId,
         account_id: AccountId,
         msg: Option<String>,
+        storage_balances: Option<&mut StorageBalances>,
     ) -> Option<Promise> {
         assert_at_least_one_yocto();
         let approvals_by_id = self
@@ -62,10 +66,15 @@ impl NonFungibleTokenApproval for NonFungibleToken {
 
         // If this approval replaced existing for same account, no storage was used.
         // Otherwise, require that enough deposit was attached to pay for storage, and refund
-        // excess.
+        // excess. If using Storage Management, the owner's storage balance pays instead.
         let storage_used =
             if old_approval_id.is_none() { bytes_for_approved_account_id(&account_id) } else { 0 };
-        refund_deposit(storage_used);
+        if let Some(storage_balances) = storage_balances {
+            storage_balances.charge(&owner_id, storage_used);
+            refund_deposit(0);
+        } else {
+            refund_deposit(storage_used);
+        }
 
         // if given `msg`, schedule call to `nft_on_approve` and return it. Else, return None.
         msg.map(|msg| {
@@ -81,7 +90,14 @@ impl NonFungibleTokenApproval for NonFungibleToken {
         })
     }
 
-    fn nft_revoke(&mut self, token_id: TokenId, account_id: AccountId) {
+    /// Like [`NonFungibleTokenApproval::nft_revoke`], releasing the storage of the approval to
+    /// `storage_balances` instead of refunding it.
+    pub fn nft_revoke_with_storage(
+        &mut self,
+        token_id: TokenId,
+        account_id: AccountId,
+        storage_balances: Option<&mut StorageBalances>,
+    ) {
         assert_one_yocto();
         let approvals_by_id = self.approvals_by_id.as_mut().unwrap_or_else(|| {
             env::panic_str("NFT does not support Approval Management");
@@ -96,7 +112,8 @@ impl NonFungibleTokenApproval for NonFungibleToken {
         if let Some(approved_account_ids) = &mut approvals_by_id.get(&token_id) {
             // if account_id was already not approved, do nothing
             if approved_account_ids.remove(&account_id).is_some() {
-                refund_approved_account_ids_iter(
+                release_approved_account_ids_iter(
+                    storage_balances,
                     predecessor_account_id,
                     core::iter::once(&account_id),
                 );
@@ -111,7 +128,13 @@ impl NonFungibleTokenApproval for NonFungibleToken {
         }
     }
 
-    fn nft_revoke_all(&mut self, token_id: TokenId) {
+    /// Like [`NonFungibleTokenApproval::nft_revoke_all`], releasing the storage of the
+    /// approvals to `storage_balances` instead of refunding it.
+    pub fn nft_revoke_all_with_storage(
+        &mut self,
+        token_id: TokenId,
+        storage_balances: Option<&mut StorageBalances>,
+    ) {
         assert_one_yocto();
         let approvals_by_id = self.approvals_by_id.as_mut().unwrap_or_else(|| {
             env::panic_str("NFT does not support Approval Management");
@@ -125,11 +148,34 @@ impl NonFungibleTokenApproval for NonFungibleToken {
         // if token has no approvals, do nothing
         if let Some(approved_account_ids) = &mut approvals_by_id.get(&token_id) {
             // otherwise, refund owner for storage costs of all approvals...
-            refund_approved_account_ids(predecessor_account_id, approved_account_ids);
+            release_approved_account_ids_iter(
+                storage_balances,
+                predecessor_account_id,
+                approved_account_ids.keys(),
+            );
             // ...and remove whole HashMap of approvals
             approvals_by_id.remove(&token_id);
         }
     }
+}
+
+impl NonFungibleTokenApproval for NonFungibleToken {
+    fn nft_approve(
+        &mut self,
+        token_id: TokenId,
+        account_id: AccountId,
+        msg: Option<String>,
+    ) -> Option<Promise> {
+        self.nft_approve_with_storage(token_id, account_id, msg, None)
+    }
+
+    fn nft_revoke(&mut self, token_id: TokenId, account_id: AccountId) {
+        self.nft_revoke_with_storage(token_id, account_id, None)
+    }
+
+    fn nft_revoke_all(&mut self, token_id: TokenId) {
+        self.nft_revoke_all_with_storage(token_id, None)
+    }
 
     fn nft_is_approved(
         &self,
@@ -3,8 +3,9 @@ use crate::non_fungible_token::core::NonFungibleTokenCore;
 use crate::non_fungible_token::metadata::TokenMetadata;
 use crate::non_fungible_token::token::{Token, TokenId};
 use crate::non_fungible_token::utils::{
-    hash_account_id, refund_approved_account_ids, refund_deposit,
+    hash_account_id, refund_deposit, release_approvals, release_approved_account_ids_iter,
 };
+use crate::storage_management::StorageBalances;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::{LookupMap, TreeMap, UnorderedSet};
 use near_sdk::json_types::Base64VecU8;
@@ -50,6 +51,10 @@ pub trait NonFungibleTokenReceiver {
 ///     - NonFungibleTokenEnumeration -- interface for getting lists of tokens. NonFungibleToken provides methods for it.
 ///     - NonFungibleTokenMetadata -- return metadata for the token in NEP-177, up to contract to implement.
 ///
+/// The methods ending with `_with_storage` let the owners pay for their entries in
+/// `tokens_per_owner` and for their approvals with a [`StorageBalances`] kept next to the token,
+/// instead of attaching deposits.
+///
 /// For example usage, see examples/non-fungible-token/src/lib.rs.
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct NonFungibleToken {
@@ -197,12 +202,27 @@ impl NonFungibleToken {
         #[allow(clippy::ptr_arg)] token_id: &TokenId,
         from: &AccountId,
         to: &AccountId,
+    ) {
+        self.internal_move_token(token_id, from, to, None, false)
+    }
+
+    /// Moves the token from `from` to `to`. With `storage_balances`, the owners pay for their
+    /// entries in `tokens_per_owner`. A token returned to `to` by a callback, which must not
+    /// fail, is paid by the contract if `to` can't pay for it anymore.
+    fn internal_move_token(
+        &mut self,
+        #[allow(clippy::ptr_arg)] token_id: &TokenId,
+        from: &AccountId,
+        to: &AccountId,
+        mut storage_balances: Option<&mut StorageBalances>,
+        is_return: bool,
     ) {
         // update owner
         self.owner_by_id.insert(token_id, to);
 
         // if using Enumeration standard, update old & new owner's token lists
         if let Some(tokens_per_owner) = &mut self.tokens_per_owner {
+            let initial_storage_usage = env::storage_usage();
             // owner_tokens should always exist, so call `unwrap` without guard
             let mut owner_tokens = tokens_per_owner.get(from).unwrap_or_else(|| {
                 env::panic_str("Unable to access tokens per owner in unguarded call.")
@@ -213,7 +233,11 @@ impl NonFungibleToken {
             } else {
                 tokens_per_owner.insert(from, &owner_tokens);
             }
+            if let Some(storage_balances) = storage_balances.as_deref_mut() {
+                storage_balances.settle(from, initial_storage_usage);
+            }
 
+            let initial_storage_usage = env::storage_usage();
             let mut receiver_tokens = tokens_per_owner.get(to).unwrap_or_else(|| {
                 UnorderedSet::new(StorageKey::TokensPerOwner {
                     account_hash: env::sha256(to.as_bytes()),
@@ -221,6 +245,13 @@ impl NonFungibleToken {
             });
             receiver_tokens.insert(token_id);
             tokens_per_owner.insert(to, &receiver_tokens);
+            if let Some(storage_balances) = storage_balances {
+                if is_return {
+                    storage_balances.try_settle(to, initial_storage_usage);
+                } else {
+                    storage_balances.settle(to, initial_storage_usage);
+                }
+            }
         }
     }
 
@@ -234,6 +265,28 @@ impl NonFungibleToken {
         #[allow(clippy::ptr_arg)] token_id: &TokenId,
         approval_id: Option<u64>,
         memo: Option<String>,
+    ) -> (AccountId, Option<HashMap<AccountId, u64>>) {
+        self.internal_transfer_with_storage(
+            sender_id,
+            receiver_id,
+            token_id,
+            approval_id,
+            memo,
+            None,
+        )
+    }
+
+    /// Like [`NonFungibleToken::internal_transfer`], with the owners paying for their entries in
+    /// `tokens_per_owner` with `storage_balances`. The cleared approvals are still charged to
+    /// the previous owner, [`release_approvals`] gives them back once the transfer is final.
+    pub fn internal_transfer_with_storage(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        #[allow(clippy::ptr_arg)] token_id: &TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        storage_balances: Option<&mut StorageBalances>,
     ) -> (AccountId, Option<HashMap<AccountId, u64>>) {
         let owner_id =
             self.owner_by_id.get(token_id).unwrap_or_else(|| env::panic_str("Token not found"));
@@ -269,7 +322,7 @@ impl NonFungibleToken {
 
         require!(&owner_id != receiver_id, "Current and next owner must differ");
 
-        self.internal_transfer_unguarded(token_id, &owner_id, receiver_id);
+        self.internal_move_token(token_id, &owner_id, receiver_id, storage_balances, false);
 
         log!("Transfer {} from {} to {}", token_id, sender_id, receiver_id);
         if let Some(memo) = memo {
@@ -310,6 +363,20 @@ impl NonFungibleToken {
         token_id: TokenId,
         token_owner_id: AccountId,
         token_metadata: Option<TokenMetadata>,
+    ) -> Token {
+        self.internal_mint_with_storage(token_id, token_owner_id, token_metadata, None)
+    }
+
+    /// Like [`NonFungibleToken::internal_mint`], with `token_owner_id` paying for its entry in
+    /// `tokens_per_owner` with `storage_balances` rather than the attached deposit. The deposit
+    /// still pays for the token itself: the entry moves to the new owner on every transfer, while
+    /// the token stays where it is, so only the entry can be paid by the owner.
+    pub fn internal_mint_with_storage(
+        &mut self,
+        token_id: TokenId,
+        token_owner_id: AccountId,
+        token_metadata: Option<TokenMetadata>,
+        mut storage_balances: Option<&mut StorageBalances>,
     ) -> Token {
         let initial_storage_usage = env::storage_usage();
         if self.token_metadata_by_id.is_some() && token_metadata.is_none() {
@@ -331,8 +398,12 @@ impl NonFungibleToken {
             .as_mut()
             .and_then(|by_id| by_id.insert(&token_id, token_metadata.as_ref().unwrap()));
 
+        let token_storage_usage = env::storage_usage() - initial_storage_usage;
+
         // Enumeration extension: Record tokens_per_owner for use with enumeration view methods.
+        // With Storage Management, this is paid by the owner rather than the attached deposit.
         if let Some(tokens_per_owner) = &mut self.tokens_per_owner {
+            let initial_storage_usage = env::storage_usage();
             let mut token_ids = tokens_per_owner.get(&owner_id).unwrap_or_else(|| {
                 UnorderedSet::new(StorageKey::TokensPerOwner {
                     account_hash: env::sha256(owner_id.as_bytes()),
@@ -340,6 +411,9 @@ impl NonFungibleToken {
             });
             token_ids.insert(&token_id);
             tokens_per_owner.insert(&owner_id, &token_ids);
+            if let Some(storage_balances) = storage_balances.as_deref_mut() {
+                storage_balances.settle(&owner_id, initial_storage_usage);
+            }
         }
 
         // Approval Management extension: return empty HashMap as part of Token
@@ -347,37 +421,62 @@ impl NonFungibleToken {
             if self.approvals_by_id.is_some() { Some(HashMap::new()) } else { None };
 
         // Return any extra attached deposit not used for storage
-        refund_deposit(env::storage_usage() - initial_storage_usage);
+        if storage_balances.is_some() {
+            refund_deposit(token_storage_usage);
+        } else {
+            refund_deposit(env::storage_usage() - initial_storage_usage);
+        }
 
         Token { token_id, owner_id, metadata: token_metadata, approved_account_ids }
     }
 }
 
-impl NonFungibleTokenCore for NonFungibleToken {
-    fn nft_transfer(
+impl NonFungibleToken {
+    /// Like [`NonFungibleTokenCore::nft_transfer`], with the owners paying for their storage
+    /// with `storage_balances`.
+    pub fn nft_transfer_with_storage(
         &mut self,
         receiver_id: AccountId,
         token_id: TokenId,
         approval_id: Option<u64>,
         memo: Option<String>,
+        mut storage_balances: Option<&mut StorageBalances>,
     ) {
         assert_one_yocto();
         let sender_id = env::predecessor_account_id();
-        self.internal_transfer(&sender_id, &receiver_id, &token_id, approval_id, memo);
+        let (previous_owner_id, approved_account_ids) = self.internal_transfer_with_storage(
+            &sender_id,
+            &receiver_id,
+            &token_id,
+            approval_id,
+            memo,
+            storage_balances.as_deref_mut(),
+        );
+        release_approvals(storage_balances, &previous_owner_id, approved_account_ids.as_ref());
     }
 
-    fn nft_transfer_call(
+    /// Like [`NonFungibleTokenCore::nft_transfer_call`], with the owners paying for their
+    /// storage with `storage_balances`. The contract must resolve the transfer with
+    /// [`NonFungibleToken::nft_resolve_transfer_with_storage`].
+    pub fn nft_transfer_call_with_storage(
         &mut self,
         receiver_id: AccountId,
         token_id: TokenId,
         approval_id: Option<u64>,
         memo: Option<String>,
         msg: String,
+        storage_balances: Option<&mut StorageBalances>,
     ) -> PromiseOrValue<bool> {
         assert_one_yocto();
         let sender_id = env::predecessor_account_id();
-        let (old_owner, old_approvals) =
-            self.internal_transfer(&sender_id, &receiver_id, &token_id, approval_id, memo);
+        let (old_owner, old_approvals) = self.internal_transfer_with_storage(
+            &sender_id,
+            &receiver_id,
+            &token_id,
+            approval_id,
+            memo,
+            storage_balances,
+        );
         // Initiating receiver's call and the callback
         ext_receiver::nft_on_transfer(
             sender_id,
@@ -400,25 +499,17 @@ impl NonFungibleTokenCore for NonFungibleToken {
         .into()
     }
 
-    fn nft_token(&self, token_id: TokenId) -> Option<Token> {
-        let owner_id = self.owner_by_id.get(&token_id)?;
-        let metadata = self.token_metadata_by_id.as_ref().and_then(|by_id| by_id.get(&token_id));
-        let approved_account_ids = self
-            .approvals_by_id
-            .as_ref()
-            .and_then(|by_id| by_id.get(&token_id).or_else(|| Some(HashMap::new())));
-        Some(Token { token_id, owner_id, metadata, approved_account_ids })
-    }
-}
-
-impl NonFungibleTokenResolver for NonFungibleToken {
-    /// Returns true if token was successfully transferred to `receiver_id`.
-    fn nft_resolve_transfer(
+    /// Like [`NonFungibleTokenResolver::nft_resolve_transfer`], with the owners paying for
+    /// their storage with `storage_balances`. A returned token never fails the callback: if the
+    /// previous owner can't pay for it anymore, e.g. because it withdrew the storage released
+    /// by the transfer or unregistered, the contract pays instead.
+    pub fn nft_resolve_transfer_with_storage(
         &mut self,
         previous_owner_id: AccountId,
         receiver_id: AccountId,
         token_id: TokenId,
         approved_account_ids: Option<HashMap<AccountId, u64>>,
+        mut storage_balances: Option<&mut StorageBalances>,
     ) -> bool {
         // Get whether token should be returned
         let must_revert = match env::promise_result(0) {
@@ -435,6 +526,7 @@ impl NonFungibleTokenResolver for NonFungibleToken {
 
         // if call succeeded, return early
         if !must_revert {
+            release_approvals(storage_balances, &previous_owner_id, approved_account_ids.as_ref());
             return true;
         }
 
@@ -444,27 +536,46 @@ impl NonFungibleTokenResolver for NonFungibleToken {
         if let Some(current_owner) = self.owner_by_id.get(&token_id) {
             if current_owner != receiver_id {
                 // The token is not owned by the receiver anymore. Can't return it.
+                release_approvals(
+                    storage_balances,
+                    &previous_owner_id,
+                    approved_account_ids.as_ref(),
+                );
                 return true;
             }
         } else {
             // The token was burned and doesn't exist anymore.
             // Refund storage cost for storing approvals to original owner and return early.
             if let Some(approved_account_ids) = approved_account_ids {
-                refund_approved_account_ids(previous_owner_id, &approved_account_ids);
+                release_approved_account_ids_iter(
+                    storage_balances,
+                    previous_owner_id,
+                    approved_account_ids.keys(),
+                );
             }
             return true;
         };
 
         log!("Return token {} from @{} to @{}", token_id, receiver_id, previous_owner_id);
 
-        self.internal_transfer_unguarded(&token_id, &receiver_id, &previous_owner_id);
+        self.internal_move_token(
+            &token_id,
+            &receiver_id,
+            &previous_owner_id,
+            storage_balances.as_deref_mut(),
+            true,
+        );
 
         // If using Approval Management extension,
         // 1. revert any approvals receiver already set, refunding storage costs
         // 2. reset approvals to what previous owner had set before call to nft_transfer_call
         if let Some(by_id) = &mut self.approvals_by_id {
             if let Some(receiver_approvals) = by_id.get(&token_id) {
-                refund_approved_account_ids(receiver_id, &receiver_approvals);
+                release_approved_account_ids_iter(
+                    storage_balances,
+                    receiver_id,
+                    receiver_approvals.keys(),
+                );
             }
             if let Some(previous_owner_approvals) = approved_account_ids {
                 by_id.insert(&token_id, &previous_owner_approvals);
@@ -474,3 +585,306 @@ impl NonFungibleTokenResolver for NonFungibleToken {
         false
     }
 }
+
+impl NonFungibleTokenCore for NonFungibleToken {
+    fn nft_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+    ) {
+        self.nft_transfer_with_storage(receiver_id, token_id, approval_id, memo, None)
+    }
+
+    fn nft_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<bool> {
+        self.nft_transfer_call_with_storage(receiver_id, token_id, approval_id, memo, msg, None)
+    }
+
+    fn nft_token(&self, token_id: TokenId) -> Option<Token> {
+        let owner_id = self.owner_by_id.get(&token_id)?;
+        let metadata = self.token_metadata_by_id.as_ref().and_then(|by_id| by_id.get(&token_id));
+        let approved_account_ids = self
+            .approvals_by_id
+            .as_ref()
+            .and_then(|by_id| by_id.get(&token_id).or_else(|| Some(HashMap::new())));
+        Some(Token { token_id, owner_id, metadata, approved_account_ids })
+    }
+}
+
+impl NonFungibleTokenResolver for NonFungibleToken {
+    /// Returns true if token was successfully transferred to `receiver_id`.
+    fn nft_resolve_transfer(
+        &mut self,
+        previous_owner_id: AccountId,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approved_account_ids: Option<HashMap<AccountId, u64>>,
+    ) -> bool {
+        self.nft_resolve_transfer_with_storage(
+            previous_owner_id,
+            receiver_id,
+            token_id,
+            approved_account_ids,
+            None,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage_management::StorageManagement;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    const STORAGE_DEPOSIT: Balance = 10u128.pow(23);
+    const MINT_DEPOSIT: Balance = 10u128.pow(22);
+
+    fn context(predecessor_account_id: AccountId, attached_deposit: Balance) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .predecessor_account_id(predecessor_account_id)
+            .attached_deposit(attached_deposit);
+        builder
+    }
+
+    /// A token with the enumeration and approval extensions, "t" minted for bob and charlie
+    /// registered to receive it.
+    fn setup() -> (NonFungibleToken, StorageBalances) {
+        testing_env!(context(accounts(0), 0).build());
+        let mut nft = NonFungibleToken::new(
+            b"o".to_vec(),
+            accounts(0),
+            None::<Vec<u8>>,
+            Some(b"e".to_vec()),
+            Some(b"a".to_vec()),
+        );
+        let mut storage_balances = StorageBalances::new(b"s".to_vec(), None);
+        for account_id in [accounts(1), accounts(2)] {
+            testing_env!(context(account_id, STORAGE_DEPOSIT).build());
+            storage_balances.storage_deposit(None, None);
+        }
+        testing_env!(context(accounts(0), MINT_DEPOSIT).build());
+        nft.internal_mint_with_storage(
+            "t".to_string(),
+            accounts(1),
+            None,
+            Some(&mut storage_balances),
+        );
+        (nft, storage_balances)
+    }
+
+    /// Transfers "t" from bob to charlie with `nft_transfer_call`.
+    fn transfer_call(nft: &mut NonFungibleToken, storage_balances: &mut StorageBalances) {
+        testing_env!(context(accounts(1), 1).build());
+        nft.nft_transfer_call_with_storage(
+            accounts(2),
+            "t".to_string(),
+            None,
+            None,
+            "".to_string(),
+            Some(storage_balances),
+        );
+    }
+
+    /// Resolves the transfer of "t" from bob to charlie with `result` as the result of
+    /// `nft_on_transfer`.
+    fn resolve(
+        nft: &mut NonFungibleToken,
+        storage_balances: &mut StorageBalances,
+        approved_account_ids: Option<HashMap<AccountId, u64>>,
+        result: PromiseResult,
+    ) -> bool {
+        testing_env!(
+            context(accounts(0), 0).build(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            vec![result],
+        );
+        nft.nft_resolve_transfer_with_storage(
+            accounts(1),
+            accounts(2),
+            "t".to_string(),
+            approved_account_ids,
+            Some(storage_balances),
+        )
+    }
+
+    fn owner_of(nft: &NonFungibleToken) -> AccountId {
+        nft.nft_token("t".to_string()).unwrap().owner_id
+    }
+
+    fn available_storage_balance(
+        storage_balances: &StorageBalances,
+        account_id: AccountId,
+    ) -> Balance {
+        storage_balances.storage_balance_of(account_id).unwrap().available.0
+    }
+
+    #[test]
+    fn owners_pay_for_tokens_per_owner() {
+        let (mut nft, mut storage_balances) = setup();
+        let registration_cost = storage_balances.storage_balance_bounds().min.0;
+        let available = STORAGE_DEPOSIT - registration_cost;
+        assert!(available_storage_balance(&storage_balances, accounts(1)) < available);
+
+        testing_env!(context(accounts(1), 1).build());
+        nft.nft_transfer_with_storage(
+            accounts(2),
+            "t".to_string(),
+            None,
+            None,
+            Some(&mut storage_balances),
+        );
+        assert_eq!(owner_of(&nft), accounts(2));
+        assert_eq!(available_storage_balance(&storage_balances, accounts(1)), available);
+        assert!(available_storage_balance(&storage_balances, accounts(2)) < available);
+    }
+
+    #[test]
+    fn mint_splits_storage_between_deposit_and_owner() {
+        let (mut nft, mut storage_balances) = setup();
+        let available = available_storage_balance(&storage_balances, accounts(2));
+        testing_env!(context(accounts(0), MINT_DEPOSIT).build());
+        let initial_storage_usage = env::storage_usage();
+        nft.internal_mint_with_storage(
+            "u".to_string(),
+            accounts(2),
+            None,
+            Some(&mut storage_balances),
+        );
+        let storage_cost =
+            Balance::from(env::storage_usage() - initial_storage_usage) * env::storage_byte_cost();
+        let refund: Balance = get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| receipt.actions)
+            .filter_map(|action| match action {
+                VmAction::Transfer { deposit } => Some(deposit),
+                _ => None,
+            })
+            .sum();
+
+        // Charlie pays for its entry in tokens_per_owner, the deposit for the token itself.
+        let drawn = available - available_storage_balance(&storage_balances, accounts(2));
+        let paid = MINT_DEPOSIT - refund;
+        assert!(drawn > 0);
+        assert!(paid > 0);
+        assert_eq!(drawn + paid, storage_cost);
+    }
+
+    #[test]
+    fn resolve_transfer_releases_approvals() {
+        let (mut nft, mut storage_balances) = setup();
+        testing_env!(context(accounts(1), 1).build());
+        nft.nft_approve_with_storage(
+            "t".to_string(),
+            accounts(3),
+            None,
+            Some(&mut storage_balances),
+        );
+
+        transfer_call(&mut nft, &mut storage_balances);
+        let approvals: HashMap<AccountId, u64> = vec![(accounts(3), 1)].into_iter().collect();
+        assert!(resolve(
+            &mut nft,
+            &mut storage_balances,
+            Some(approvals),
+            PromiseResult::Successful(b"false".to_vec())
+        ));
+        assert_eq!(owner_of(&nft), accounts(2));
+        // Bob doesn't pay for the token nor the approval anymore.
+        let registration_cost = storage_balances.storage_balance_bounds().min.0;
+        assert_eq!(
+            available_storage_balance(&storage_balances, accounts(1)),
+            STORAGE_DEPOSIT - registration_cost
+        );
+    }
+
+    #[test]
+    fn resolve_transfer_restores_approvals() {
+        let (mut nft, mut storage_balances) = setup();
+        testing_env!(context(accounts(1), 1).build());
+        nft.nft_approve_with_storage(
+            "t".to_string(),
+            accounts(3),
+            None,
+            Some(&mut storage_balances),
+        );
+        let available = available_storage_balance(&storage_balances, accounts(1));
+
+        transfer_call(&mut nft, &mut storage_balances);
+        let approvals: HashMap<AccountId, u64> = vec![(accounts(3), 1)].into_iter().collect();
+        assert!(!resolve(
+            &mut nft,
+            &mut storage_balances,
+            Some(approvals.clone()),
+            PromiseResult::Successful(b"true".to_vec())
+        ));
+        let token = nft.nft_token("t".to_string()).unwrap();
+        assert_eq!(token.owner_id, accounts(1));
+        assert_eq!(token.approved_account_ids, Some(approvals));
+        assert_eq!(available_storage_balance(&storage_balances, accounts(1)), available);
+    }
+
+    #[test]
+    fn returns_token_to_owner_without_storage_balance() {
+        let (mut nft, mut storage_balances) = setup();
+        transfer_call(&mut nft, &mut storage_balances);
+        testing_env!(context(accounts(1), 1).build());
+        storage_balances.storage_withdraw(None);
+
+        assert!(!resolve(&mut nft, &mut storage_balances, None, PromiseResult::Failed));
+        assert_eq!(owner_of(&nft), accounts(1));
+        // The contract pays for the returned token.
+        assert_eq!(available_storage_balance(&storage_balances, accounts(1)), 0);
+    }
+
+    #[test]
+    fn returns_token_to_unregistered_owner() {
+        let (mut nft, mut storage_balances) = setup();
+        transfer_call(&mut nft, &mut storage_balances);
+        testing_env!(context(accounts(1), 1).build());
+        assert!(storage_balances.storage_unregister(None));
+
+        assert!(!resolve(&mut nft, &mut storage_balances, None, PromiseResult::Failed));
+        assert_eq!(owner_of(&nft), accounts(1));
+        assert!(!storage_balances.is_registered(&accounts(1)));
+        assert_eq!(
+            nft.tokens_per_owner.as_ref().unwrap().get(&accounts(1)).unwrap().to_vec(),
+            vec!["t".to_string()]
+        );
+    }
+
+    #[test]
+    fn forced_storage_unregister_burns_tokens() {
+        let (mut nft, mut storage_balances) = setup();
+        testing_env!(context(accounts(1), 1).build());
+        nft.nft_approve_with_storage(
+            "t".to_string(),
+            accounts(3),
+            None,
+            Some(&mut storage_balances),
+        );
+
+        testing_env!(context(accounts(1), 1).build());
+        let (account_id, storage) =
+            nft.internal_storage_unregister(Some(true), &mut storage_balances).unwrap();
+        assert_eq!(account_id, accounts(1));
+        assert_eq!(storage.deposit, STORAGE_DEPOSIT);
+        assert!(!storage_balances.is_registered(&accounts(1)));
+        assert!(nft.nft_token("t".to_string()).is_none());
+        assert!(nft.tokens_per_owner.as_ref().unwrap().get(&accounts(1)).is_none());
+        assert!(nft.approvals_by_id.as_ref().unwrap().get(&"t".to_string()).is_none());
+    }
+}
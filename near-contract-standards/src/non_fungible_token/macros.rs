@@ -1,8 +1,11 @@
 /// The core methods for a basic non-fungible token. Extension standards may be
 /// added in addition to this macro.
+/// Takes name of the Contract struct, the inner field for the token and optionally the field
+/// for the [`StorageBalances`](crate::storage_management::StorageBalances) with which the
+/// owners pay for their storage.
 #[macro_export]
 macro_rules! impl_non_fungible_token_core {
-    ($contract: ident, $token: ident) => {
+    ($contract: ident, $token: ident $(, $storage: ident)?) => {
         use std::collections::HashMap;
         use $crate::non_fungible_token::core::NonFungibleTokenCore;
         use $crate::non_fungible_token::core::NonFungibleTokenResolver;
@@ -17,7 +20,13 @@ macro_rules! impl_non_fungible_token_core {
                 approval_id: Option<u64>,
                 memo: Option<String>,
             ) {
-                self.$token.nft_transfer(receiver_id, token_id, approval_id, memo)
+                self.$token.nft_transfer_with_storage(
+                    receiver_id,
+                    token_id,
+                    approval_id,
+                    memo,
+                    $crate::__nft_storage_balances!(self $(, $storage)?),
+                )
             }
 
             #[payable]
@@ -29,7 +38,14 @@ macro_rules! impl_non_fungible_token_core {
                 memo: Option<String>,
                 msg: String,
             ) -> PromiseOrValue<bool> {
-                self.$token.nft_transfer_call(receiver_id, token_id, approval_id, memo, msg)
+                self.$token.nft_transfer_call_with_storage(
+                    receiver_id,
+                    token_id,
+                    approval_id,
+                    memo,
+                    msg,
+                    $crate::__nft_storage_balances!(self $(, $storage)?),
+                )
             }
 
             fn nft_token(&self, token_id: TokenId) -> Option<Token> {
@@ -47,11 +63,12 @@ macro_rules! impl_non_fungible_token_core {
                 token_id: TokenId,
                 approved_account_ids: Option<HashMap<AccountId, u64>>,
             ) -> bool {
-                self.$token.nft_resolve_transfer(
+                self.$token.nft_resolve_transfer_with_storage(
                     previous_owner_id,
                     receiver_id,
                     token_id,
                     approved_account_ids,
+                    $crate::__nft_storage_balances!(self $(, $storage)?),
                 )
             }
         }
@@ -60,9 +77,11 @@ macro_rules! impl_non_fungible_token_core {
 
 /// Non-fungible token approval management allows for an escrow system where
 /// multiple approvals per token exist.
+/// With the optional field for the storage balances, the owners pay for their approvals with
+/// their storage balance instead of attaching deposits.
 #[macro_export]
 macro_rules! impl_non_fungible_token_approval {
-    ($contract: ident, $token: ident) => {
+    ($contract: ident, $token: ident $(, $storage: ident)?) => {
         use $crate::non_fungible_token::approval::NonFungibleTokenApproval;
 
         #[near_bindgen]
@@ -74,17 +93,29 @@ macro_rules! impl_non_fungible_token_approval {
                 account_id: AccountId,
                 msg: Option<String>,
             ) -> Option<Promise> {
-                self.$token.nft_approve(token_id, account_id, msg)
+                self.$token.nft_approve_with_storage(
+                    token_id,
+                    account_id,
+                    msg,
+                    $crate::__nft_storage_balances!(self $(, $storage)?),
+                )
             }
 
             #[payable]
             fn nft_revoke(&mut self, token_id: TokenId, account_id: AccountId) {
-                self.$token.nft_revoke(token_id, account_id)
+                self.$token.nft_revoke_with_storage(
+                    token_id,
+                    account_id,
+                    $crate::__nft_storage_balances!(self $(, $storage)?),
+                )
             }
 
             #[payable]
             fn nft_revoke_all(&mut self, token_id: TokenId) {
-                self.$token.nft_revoke_all(token_id)
+                self.$token.nft_revoke_all_with_storage(
+                    token_id,
+                    $crate::__nft_storage_balances!(self $(, $storage)?),
+                )
             }
 
             fn nft_is_approved(
@@ -132,3 +163,106 @@ macro_rules! impl_non_fungible_token_enumeration {
         }
     };
 }
+
+/// Non-fungible token payouts let marketplaces pay the royalties of a token when it's sold.
+/// The royalties are kept in a [`Royalty`](crate::non_fungible_token::payout::Royalty) next to
+/// the token, and the optional field for the storage balances is the one given to
+/// `impl_non_fungible_token_core!`.
+#[macro_export]
+macro_rules! impl_non_fungible_token_payout {
+    ($contract: ident, $token: ident, $royalty: ident $(, $storage: ident)?) => {
+        use $crate::non_fungible_token::payout::{NonFungibleTokenPayout, Payout};
+
+        #[near_bindgen]
+        impl NonFungibleTokenPayout for $contract {
+            fn nft_payout(
+                &self,
+                token_id: TokenId,
+                balance: near_sdk::json_types::U128,
+                max_len_payout: Option<u32>,
+            ) -> Payout {
+                self.$royalty.nft_payout(&self.$token, token_id, balance, max_len_payout)
+            }
+
+            #[payable]
+            fn nft_transfer_payout(
+                &mut self,
+                receiver_id: AccountId,
+                token_id: TokenId,
+                approval_id: Option<u64>,
+                memo: Option<String>,
+                balance: near_sdk::json_types::U128,
+                max_len_payout: Option<u32>,
+            ) -> Payout {
+                self.$royalty.nft_transfer_payout(
+                    &mut self.$token,
+                    receiver_id,
+                    token_id,
+                    approval_id,
+                    memo,
+                    balance,
+                    max_len_payout,
+                    $crate::__nft_storage_balances!(self $(, $storage)?),
+                )
+            }
+        }
+    };
+}
+
+/// Non-fungible token storage management lets accounts prepay for the storage of their tokens
+/// and approvals with the [`StorageBalances`](crate::storage_management::StorageBalances) given
+/// to the other macros. Takes name of the Contract struct, the inner field for the token and
+/// the field for the storage balances.
+/// `storage_unregister` with `force` burns the tokens of the account.
+#[macro_export]
+macro_rules! impl_non_fungible_token_storage {
+    ($contract: ident, $token: ident, $storage: ident) => {
+        use $crate::storage_management::{StorageBalance, StorageBalanceBounds, StorageManagement};
+
+        #[near_bindgen]
+        impl StorageManagement for $contract {
+            #[payable]
+            fn storage_deposit(
+                &mut self,
+                account_id: Option<AccountId>,
+                registration_only: Option<bool>,
+            ) -> StorageBalance {
+                self.$storage.storage_deposit(account_id, registration_only)
+            }
+
+            #[payable]
+            fn storage_withdraw(
+                &mut self,
+                amount: Option<near_sdk::json_types::U128>,
+            ) -> StorageBalance {
+                self.$storage.storage_withdraw(amount)
+            }
+
+            #[payable]
+            fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+                self.$token.internal_storage_unregister(force, &mut self.$storage).is_some()
+            }
+
+            fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+                self.$storage.storage_balance_bounds()
+            }
+
+            fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+                self.$storage.storage_balance_of(account_id)
+            }
+        }
+    };
+}
+
+/// The storage balances passed by the macros to the methods ending with `_with_storage`, if the
+/// contract keeps them in the given field.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __nft_storage_balances {
+    ($self: ident) => {
+        None
+    };
+    ($self: ident, $storage: ident) => {
+        Some(&mut $self.$storage)
+    };
+}
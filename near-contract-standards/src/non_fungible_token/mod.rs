@@ -12,6 +12,11 @@ mod macros;
 /// Metadata traits and implementation according to the [NFT enumeration standard](https://nomicon.io/Standards/NonFungibleToken/Metadata.html).
 /// This covers both the contract metadata and the individual token metadata.
 pub mod metadata;
+/// The [payout standard](https://nomicon.io/Standards/NonFungibleToken/Payout.html) for NFTs,
+/// which marketplaces use to pay royalties.
+pub mod payout;
+/// Storage management for NFTs, with which accounts prepay for their storage.
+mod storage_impl;
 /// The Token struct for the non-fungible token.
 mod token;
 pub use self::token::{Token, TokenId};
@@ -0,0 +1,64 @@
+mod payout_impl;
+
+pub use payout_impl::*;
+
+use crate::non_fungible_token::token::TokenId;
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::AccountId;
+use std::collections::HashMap;
+
+/// The amounts of a sale price to pay to each account, the royalties and the rest to the owner.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Payout {
+    pub payout: HashMap<AccountId, U128>,
+}
+
+/// Used by marketplaces to pay the royalties of a token when it's sold, as described in the
+/// [payout standard]. The payout of a sale always sums up to the sale price.
+///
+/// [payout standard]: https://nomicon.io/Standards/NonFungibleToken/Payout.html
+pub trait NonFungibleTokenPayout {
+    /// Returns the payout of selling the token for `balance`.
+    ///
+    /// Requirements
+    /// * Contract MUST panic if the token doesn't exist
+    /// * Contract MUST panic if the payout has more than `max_len_payout` accounts
+    ///
+    /// Arguments:
+    /// * `token_id`: the token to sell
+    /// * `balance`: the sale price, as a string representing an unsigned 128-bit integer
+    /// * `max_len_payout`: the maximum number of accounts the marketplace can pay
+    fn nft_payout(&self, token_id: TokenId, balance: U128, max_len_payout: Option<u32>) -> Payout;
+
+    /// Transfers the token like `nft_transfer` and returns the payout of selling it for
+    /// `balance` to the previous owner.
+    ///
+    /// Requirements
+    /// * Caller of the method must attach a deposit of 1 yoctoⓃ for security purposes
+    /// * Contract MUST panic if called by someone other than token owner or,
+    ///   if using Approval Management, one of the approved accounts
+    /// * Contract MUST panic if the payout has more than `max_len_payout` accounts
+    ///
+    /// Arguments:
+    /// * `receiver_id`: the valid NEAR account receiving the token
+    /// * `token_id`: the token to transfer
+    /// * `approval_id`: expected approval ID. A number smaller than
+    ///   2^53, and therefore representable as JSON. See Approval Management
+    ///   standard for full explanation.
+    /// * `memo` (optional): for use cases that may benefit from indexing or
+    ///   providing information for a transfer
+    /// * `balance`: the sale price, as a string representing an unsigned 128-bit integer
+    /// * `max_len_payout`: the maximum number of accounts the marketplace can pay
+    #[allow(clippy::too_many_arguments)]
+    fn nft_transfer_payout(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        balance: U128,
+        max_len_payout: Option<u32>,
+    ) -> Payout;
+}
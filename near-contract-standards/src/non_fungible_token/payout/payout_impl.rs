@@ -0,0 +1,228 @@
+use super::Payout;
+use crate::non_fungible_token::token::TokenId;
+use crate::non_fungible_token::utils::release_approvals;
+use crate::non_fungible_token::NonFungibleToken;
+use crate::storage_management::StorageBalances;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, require, AccountId, Balance, IntoStorageKey};
+use std::collections::HashMap;
+
+/// Basis points of the whole sale price, royalties are given in 1/100 of a percent.
+pub const ONE_HUNDRED_PERCENT_IN_BPS: u32 = 10_000;
+
+/// Royalties of the tokens, which together with a [`NonFungibleToken`] implement the
+/// [payout standard](super::NonFungibleTokenPayout).
+///
+/// The royalty of a token is a map from the accounts to their share of the sale price in basis
+/// points, the owner at the time of the sale receives the rest. The royalty stays with the token
+/// when it's transferred. Contracts usually set it when minting the token.
+///
+/// For example usage, see `impl_non_fungible_token_payout!`.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Royalty {
+    pub royalty_by_id: LookupMap<TokenId, HashMap<AccountId, u32>>,
+}
+
+impl Royalty {
+    pub fn new<S>(prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        Self { royalty_by_id: LookupMap::new(prefix) }
+    }
+
+    /// Sets the royalty of the token and returns the previous one.
+    /// Panics if the shares sum up to more than [`ONE_HUNDRED_PERCENT_IN_BPS`].
+    ///
+    /// The storage is not paid by the caller, contracts setting the royalty while minting should
+    /// include it in the storage usage they charge for.
+    pub fn set(
+        &mut self,
+        #[allow(clippy::ptr_arg)] token_id: &TokenId,
+        royalty: &HashMap<AccountId, u32>,
+    ) -> Option<HashMap<AccountId, u32>> {
+        let total: u64 = royalty.values().map(|bps| *bps as u64).sum();
+        require!(
+            total <= ONE_HUNDRED_PERCENT_IN_BPS as u64,
+            format!("Royalties must not exceed {} basis points", ONE_HUNDRED_PERCENT_IN_BPS)
+        );
+        self.royalty_by_id.insert(token_id, royalty)
+    }
+
+    /// Returns the royalty of the token, empty if none was set.
+    pub fn get(&self, #[allow(clippy::ptr_arg)] token_id: &TokenId) -> HashMap<AccountId, u32> {
+        self.royalty_by_id.get(token_id).unwrap_or_default()
+    }
+
+    pub fn remove(
+        &mut self,
+        #[allow(clippy::ptr_arg)] token_id: &TokenId,
+    ) -> Option<HashMap<AccountId, u32>> {
+        self.royalty_by_id.remove(token_id)
+    }
+
+    /// Splits `balance` between the royalty accounts of the token and `owner_id`. Every royalty
+    /// account is part of the payout, even if its share is rounded down to zero.
+    pub fn internal_payout(
+        &self,
+        #[allow(clippy::ptr_arg)] token_id: &TokenId,
+        owner_id: &AccountId,
+        balance: Balance,
+        max_len_payout: Option<u32>,
+    ) -> Payout {
+        let royalty = self.get(token_id);
+        let mut payout = HashMap::with_capacity(royalty.len() + 1);
+        let mut paid: Balance = 0;
+        for (account_id, bps) in royalty {
+            let amount = royalty_amount(balance, bps);
+            paid += amount;
+            payout.insert(account_id, amount);
+        }
+        *payout.entry(owner_id.clone()).or_default() += balance - paid;
+        if let Some(max_len_payout) = max_len_payout {
+            require!(
+                payout.len() <= max_len_payout as usize,
+                format!(
+                    "Payout of {} accounts exceeds max_len_payout {}",
+                    payout.len(),
+                    max_len_payout
+                )
+            );
+        }
+        Payout {
+            payout: payout
+                .into_iter()
+                .map(|(account_id, amount)| (account_id, amount.into()))
+                .collect(),
+        }
+    }
+
+    pub fn nft_payout(
+        &self,
+        tokens: &NonFungibleToken,
+        token_id: TokenId,
+        balance: U128,
+        max_len_payout: Option<u32>,
+    ) -> Payout {
+        let owner_id =
+            tokens.owner_by_id.get(&token_id).unwrap_or_else(|| env::panic_str("Token not found"));
+        self.internal_payout(&token_id, &owner_id, balance.into(), max_len_payout)
+    }
+
+    /// Transfers the token with [`NonFungibleToken::internal_transfer_with_storage`] and returns
+    /// the payout of the previous owner.
+    #[allow(clippy::too_many_arguments)]
+    pub fn nft_transfer_payout(
+        &self,
+        tokens: &mut NonFungibleToken,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        balance: U128,
+        max_len_payout: Option<u32>,
+        mut storage_balances: Option<&mut StorageBalances>,
+    ) -> Payout {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        let (previous_owner_id, approved_account_ids) = tokens.internal_transfer_with_storage(
+            &sender_id,
+            &receiver_id,
+            &token_id,
+            approval_id,
+            memo,
+            storage_balances.as_deref_mut(),
+        );
+        release_approvals(storage_balances, &previous_owner_id, approved_account_ids.as_ref());
+        self.internal_payout(&token_id, &previous_owner_id, balance.into(), max_len_payout)
+    }
+}
+
+/// Returns `bps` basis points of `balance`, rounded down, without overflowing.
+fn royalty_amount(balance: Balance, bps: u32) -> Balance {
+    let bps = bps as Balance;
+    let one_hundred_percent = ONE_HUNDRED_PERCENT_IN_BPS as Balance;
+    balance / one_hundred_percent * bps + balance % one_hundred_percent * bps / one_hundred_percent
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn royalty(shares: &[(usize, u32)]) -> Royalty {
+        testing_env!(VMContextBuilder::new().build());
+        let mut royalty = Royalty::new(b"r".to_vec());
+        let shares = shares.iter().map(|(account, bps)| (accounts(*account), *bps)).collect();
+        royalty.set(&"0".to_string(), &shares);
+        royalty
+    }
+
+    fn amount(payout: &Payout, account: usize) -> Balance {
+        payout.payout[&accounts(account)].0
+    }
+
+    #[test]
+    fn royalty_amount_rounds_down() {
+        assert_eq!(royalty_amount(10_000, 1), 1);
+        assert_eq!(royalty_amount(9_999, 1), 0);
+        assert_eq!(royalty_amount(12_345, 2_500), 3_086);
+        assert_eq!(royalty_amount(1_000_001, 1_000), 100_000);
+        assert_eq!(royalty_amount(Balance::MAX, 10_000), Balance::MAX);
+        assert_eq!(royalty_amount(Balance::MAX, 0), 0);
+    }
+
+    #[test]
+    fn payout_sums_to_balance() {
+        let royalty = royalty(&[(1, 1_000), (2, 333)]);
+        let balance = 1_000_001;
+        let payout = royalty.internal_payout(&"0".to_string(), &accounts(0), balance, None);
+        assert_eq!(payout.payout.len(), 3);
+        assert_eq!(amount(&payout, 1), 100_000);
+        assert_eq!(amount(&payout, 2), 33_300);
+        assert_eq!(amount(&payout, 0), balance - 100_000 - 33_300);
+        assert_eq!(payout.payout.values().map(|amount| amount.0).sum::<Balance>(), balance);
+    }
+
+    #[test]
+    fn payout_includes_zero_shares_and_merges_owner() {
+        let royalty = royalty(&[(0, 500), (1, 1)]);
+        let payout = royalty.internal_payout(&"0".to_string(), &accounts(0), 100, None);
+        assert_eq!(payout.payout.len(), 2);
+        assert_eq!(amount(&payout, 1), 0);
+        assert_eq!(amount(&payout, 0), 100);
+    }
+
+    #[test]
+    fn payout_without_royalty() {
+        let royalty = royalty(&[]);
+        let payout = royalty.internal_payout(&"1".to_string(), &accounts(0), 100, Some(1));
+        assert_eq!(payout.payout.len(), 1);
+        assert_eq!(amount(&payout, 0), 100);
+    }
+
+    #[test]
+    fn royalty_of_one_hundred_percent() {
+        let royalty = royalty(&[(1, 4_000), (2, 6_000)]);
+        let payout = royalty.internal_payout(&"0".to_string(), &accounts(0), 1_000, Some(3));
+        assert_eq!(amount(&payout, 1), 400);
+        assert_eq!(amount(&payout, 2), 600);
+        assert_eq!(amount(&payout, 0), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Royalties must not exceed 10000 basis points")]
+    fn royalty_above_one_hundred_percent() {
+        royalty(&[(1, 4_000), (2, 6_001)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Payout of 3 accounts exceeds max_len_payout 2")]
+    fn payout_exceeds_max_len() {
+        let royalty = royalty(&[(1, 1_000), (2, 1_000)]);
+        royalty.internal_payout(&"0".to_string(), &accounts(0), 1_000, Some(2));
+    }
+}
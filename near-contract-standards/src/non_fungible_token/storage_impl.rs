@@ -0,0 +1,38 @@
+use crate::non_fungible_token::NonFungibleToken;
+use crate::storage_management::{AccountStorage, StorageBalances};
+use near_sdk::AccountId;
+
+impl NonFungibleToken {
+    /// Internal method that unregisters the predecessor from `storage_balances` and returns the
+    /// Account ID and its storage in case the account was unregistered. With `force`, the tokens
+    /// of the account are burned together with their approvals, as the refunded deposit doesn't
+    /// pay for them anymore.
+    ///
+    /// Only the tokens listed in `tokens_per_owner` can be burned, without the enumeration
+    /// extension the account doesn't pay for any tokens.
+    pub fn internal_storage_unregister(
+        &mut self,
+        force: Option<bool>,
+        storage_balances: &mut StorageBalances,
+    ) -> Option<(AccountId, AccountStorage)> {
+        let (account_id, storage) = storage_balances.internal_storage_unregister(force)?;
+        if let Some(mut token_ids) =
+            self.tokens_per_owner.as_mut().and_then(|by_owner| by_owner.remove(&account_id))
+        {
+            for token_id in token_ids.iter() {
+                self.owner_by_id.remove(&token_id);
+                if let Some(by_id) = self.token_metadata_by_id.as_mut() {
+                    by_id.remove(&token_id);
+                }
+                if let Some(by_id) = self.approvals_by_id.as_mut() {
+                    by_id.remove(&token_id);
+                }
+                if let Some(by_id) = self.next_approval_id_by_id.as_mut() {
+                    by_id.remove(&token_id);
+                }
+            }
+            token_ids.clear();
+        }
+        Some((account_id, storage))
+    }
+}
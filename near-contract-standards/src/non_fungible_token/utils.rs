@@ -1,3 +1,4 @@
+use crate::storage_management::StorageBalances;
 use near_sdk::{env, require, AccountId, Balance, CryptoHash, Promise};
 use std::collections::HashMap;
 use std::mem::size_of;
@@ -26,6 +27,39 @@ pub fn refund_approved_account_ids(
     refund_approved_account_ids_iter(account_id, approved_account_ids.keys())
 }
 
+/// Gives the storage of the approvals back to `account_id`, to its storage balance if the
+/// contract keeps storage balances and with a transfer otherwise.
+pub fn release_approved_account_ids_iter<'a, I>(
+    storage_balances: Option<&mut StorageBalances>,
+    account_id: AccountId,
+    approved_account_ids: I,
+) where
+    I: Iterator<Item = &'a AccountId>,
+{
+    if let Some(storage_balances) = storage_balances {
+        let storage_released: u64 = approved_account_ids.map(bytes_for_approved_account_id).sum();
+        storage_balances.release(&account_id, storage_released);
+    } else {
+        refund_approved_account_ids_iter(account_id, approved_account_ids);
+    }
+}
+
+/// Gives the storage of the approvals cleared by a final transfer back to the storage balance
+/// of the previous owner. Without storage balances nothing is refunded.
+pub fn release_approvals(
+    storage_balances: Option<&mut StorageBalances>,
+    previous_owner_id: &AccountId,
+    approved_account_ids: Option<&HashMap<AccountId, u64>>,
+) {
+    if let (Some(storage_balances), Some(approved_account_ids)) =
+        (storage_balances, approved_account_ids)
+    {
+        let storage_released: u64 =
+            approved_account_ids.keys().map(bytes_for_approved_account_id).sum();
+        storage_balances.release(previous_owner_id, storage_released);
+    }
+}
+
 pub fn refund_deposit(storage_used: u64) {
     let required_cost = env::storage_byte_cost() * Balance::from(storage_used);
     let attached_deposit = env::attached_deposit();
@@ -0,0 +1,379 @@
+use super::{
+    assert_failure, assert_paid, assert_success, balance, call, create_user, paid, parse_u128,
+    view, SetupCall,
+};
+use near_sdk::serde_json::{json, Value};
+use near_sdk::{AccountId, Balance};
+use near_sdk_sim::{init_simulator, to_yocto, ExecutionResult, UserAccount};
+
+const TOKEN_ID: &str = "token";
+const RECEIVER_ID: &str = "receiver";
+
+/// Conformance suite of the fungible token ([NEP-141]) and storage management ([NEP-145])
+/// standards.
+///
+/// The contract is initialized with the call returned by `init`, which is called with the ID of
+/// the owner account. The owner must hold the whole total supply after the initialization and be
+/// registered.
+///
+/// The callbacks of `ft_transfer_call` are checked with a receiver without a contract, which
+/// fails, and optionally with a [receiver contract](FtConformance::with_receiver).
+///
+/// [NEP-141]: https://nomicon.io/Standards/FungibleToken/Core.html
+/// [NEP-145]: https://nomicon.io/Standards/StorageManagement.html
+pub struct FtConformance {
+    wasm: Vec<u8>,
+    init: Box<dyn Fn(&AccountId) -> SetupCall>,
+    receiver_wasm: Option<Vec<u8>>,
+}
+
+/// Accounts of a single check. `alice` is registered, `bob` is not.
+struct Setup {
+    root: UserAccount,
+    token: UserAccount,
+    owner: UserAccount,
+    alice: UserAccount,
+    bob: UserAccount,
+}
+
+impl Setup {
+    fn call(
+        &self,
+        signer: &UserAccount,
+        method_name: &str,
+        args: Value,
+        deposit: Balance,
+    ) -> ExecutionResult {
+        call(signer, self.token.account_id(), method_name, args, deposit)
+    }
+
+    fn view(&self, method_name: &str, args: Value) -> Value {
+        view(&self.root, self.token.account_id(), method_name, args)
+    }
+
+    fn balance_of(&self, user: &UserAccount) -> Balance {
+        parse_u128(&self.view("ft_balance_of", json!({ "account_id": user.account_id() })))
+    }
+
+    fn total_supply(&self) -> Balance {
+        parse_u128(&self.view("ft_total_supply", json!({})))
+    }
+
+    fn storage_balance_of(&self, user: &UserAccount) -> Value {
+        self.view("storage_balance_of", json!({ "account_id": user.account_id() }))
+    }
+
+    fn min_storage_balance(&self) -> Balance {
+        parse_u128(&self.view("storage_balance_bounds", json!({}))["min"])
+    }
+
+    fn register(&self, user: &UserAccount) {
+        let res = self.call(
+            &self.root,
+            "storage_deposit",
+            json!({ "account_id": user.account_id() }),
+            self.min_storage_balance(),
+        );
+        assert_success(&res, "NEP-145: storage_deposit with the minimum balance must register");
+    }
+
+    fn transfer(
+        &self,
+        sender: &UserAccount,
+        receiver: &UserAccount,
+        amount: Balance,
+    ) -> ExecutionResult {
+        self.call(
+            sender,
+            "ft_transfer",
+            json!({ "receiver_id": receiver.account_id(), "amount": amount.to_string() }),
+            1,
+        )
+    }
+
+    fn transfer_call(&self, receiver_id: AccountId, amount: Balance, msg: &str) -> ExecutionResult {
+        self.call(
+            &self.owner,
+            "ft_transfer_call",
+            json!({ "receiver_id": receiver_id, "amount": amount.to_string(), "msg": msg }),
+            1,
+        )
+    }
+}
+
+impl FtConformance {
+    pub fn new<F>(wasm: &[u8], init: F) -> Self
+    where
+        F: Fn(&AccountId) -> SetupCall + 'static,
+    {
+        Self { wasm: wasm.to_vec(), init: Box::new(init), receiver_wasm: None }
+    }
+
+    /// Checks `ft_transfer_call` with a receiver contract, like `test-contract-defi` of the
+    /// fungible token example. The receiver is initialized with
+    /// `new(fungible_token_account_id)`, keeps all tokens for the `msg` `"take-my-money"` and
+    /// otherwise returns the amount of unused tokens given by `msg`.
+    pub fn with_receiver(mut self, wasm: &[u8]) -> Self {
+        self.receiver_wasm = Some(wasm.to_vec());
+        self
+    }
+
+    /// Runs all checks.
+    pub fn run(&self) {
+        self.check_total_supply();
+        self.check_transfer();
+        self.check_transfer_call_refund();
+        self.check_transfer_call_receiver();
+        self.check_storage_deposit();
+        self.check_storage_unregister();
+        self.check_storage_withdraw();
+    }
+
+    fn setup(&self) -> Setup {
+        let root = init_simulator(None);
+        let token =
+            root.deploy(&self.wasm, AccountId::new_unchecked(TOKEN_ID.to_string()), to_yocto("50"));
+        let owner = create_user(&root, "owner");
+        let res = (self.init)(&owner.account_id()).submit(&token, token.account_id());
+        assert_success(&res, "The initialization must succeed");
+        let setup = Setup {
+            alice: create_user(&root, "alice"),
+            bob: create_user(&root, "bob"),
+            root,
+            token,
+            owner,
+        };
+        setup.register(&setup.alice);
+        setup
+    }
+
+    /// NEP-141: the owner holds the whole total supply after the initialization.
+    pub fn check_total_supply(&self) {
+        let setup = self.setup();
+        let total_supply = setup.total_supply();
+        assert!(total_supply > 0, "NEP-141: the total supply must be positive");
+        assert_eq!(
+            setup.balance_of(&setup.owner),
+            total_supply,
+            "NEP-141: the owner must hold the total supply"
+        );
+        assert_eq!(setup.balance_of(&setup.bob), 0, "NEP-141: unknown accounts hold no tokens");
+    }
+
+    /// NEP-141: `ft_transfer` requires 1 yoctoNEAR, a registered receiver and enough tokens.
+    pub fn check_transfer(&self) {
+        let setup = self.setup();
+        let total_supply = setup.total_supply();
+        let amount = total_supply / 10;
+
+        let res = setup.call(
+            &setup.owner,
+            "ft_transfer",
+            json!({ "receiver_id": setup.alice.account_id(), "amount": amount.to_string() }),
+            0,
+        );
+        assert_failure(&res, "NEP-141: ft_transfer must require 1 yoctoNEAR");
+        assert_failure(
+            &setup.transfer(&setup.owner, &setup.bob, amount),
+            "NEP-141: ft_transfer to an unregistered account must fail",
+        );
+        assert_failure(
+            &setup.transfer(&setup.alice, &setup.owner, 1),
+            "NEP-141: ft_transfer of more tokens than the balance must fail",
+        );
+        assert_eq!(setup.balance_of(&setup.owner), total_supply);
+
+        let res = setup.transfer(&setup.owner, &setup.alice, amount);
+        assert_success(&res, "NEP-141: ft_transfer to a registered account must succeed");
+        assert_eq!(setup.balance_of(&setup.owner), total_supply - amount);
+        assert_eq!(setup.balance_of(&setup.alice), amount);
+        assert_eq!(setup.total_supply(), total_supply, "NEP-141: ft_transfer keeps the supply");
+    }
+
+    /// NEP-141: tokens are refunded when `ft_on_transfer` of the receiver fails.
+    pub fn check_transfer_call_refund(&self) {
+        let setup = self.setup();
+        setup.register(&setup.bob);
+        let total_supply = setup.total_supply();
+        let amount = total_supply / 10;
+
+        let res = setup.transfer_call(setup.bob.account_id(), amount, "");
+        assert_success(&res, "NEP-141: ft_transfer_call must succeed if the receiver fails");
+        assert_eq!(
+            parse_u128(&res.unwrap_json_value()),
+            0,
+            "NEP-141: ft_transfer_call must return 0 used tokens if the receiver fails"
+        );
+        assert_eq!(
+            setup.balance_of(&setup.owner),
+            total_supply,
+            "NEP-141: the tokens must be refunded if the receiver fails"
+        );
+        assert_eq!(setup.balance_of(&setup.bob), 0);
+    }
+
+    /// NEP-141: the unused tokens returned by `ft_on_transfer` are refunded. Skipped without a
+    /// receiver contract.
+    pub fn check_transfer_call_receiver(&self) {
+        let receiver_wasm = match &self.receiver_wasm {
+            Some(wasm) => wasm,
+            None => return,
+        };
+        let setup = self.setup();
+        let receiver = setup.root.deploy(
+            receiver_wasm,
+            AccountId::new_unchecked(RECEIVER_ID.to_string()),
+            to_yocto("50"),
+        );
+        let res = call(
+            &receiver,
+            receiver.account_id(),
+            "new",
+            json!({ "fungible_token_account_id": setup.token.account_id() }),
+            0,
+        );
+        assert_success(&res, "The receiver initialization must succeed");
+        setup.register(&receiver);
+        let total_supply = setup.total_supply();
+        let amount = total_supply / 10;
+
+        let res = setup.transfer_call(receiver.account_id(), amount, "take-my-money");
+        assert_success(&res, "NEP-141: ft_transfer_call must succeed");
+        assert_eq!(parse_u128(&res.unwrap_json_value()), amount);
+        assert_eq!(setup.balance_of(&receiver), amount);
+
+        let unused = amount / 4;
+        let res = setup.transfer_call(receiver.account_id(), amount, &unused.to_string());
+        assert_success(&res, "NEP-141: ft_transfer_call must succeed");
+        assert_eq!(
+            parse_u128(&res.unwrap_json_value()),
+            amount - unused,
+            "NEP-141: ft_transfer_call must return the used tokens"
+        );
+        assert_eq!(
+            setup.balance_of(&receiver),
+            2 * amount - unused,
+            "NEP-141: the unused tokens must be returned to the sender"
+        );
+        assert_eq!(setup.balance_of(&setup.owner), total_supply - 2 * amount + unused);
+        assert_eq!(setup.total_supply(), total_supply);
+    }
+
+    /// NEP-145: `storage_deposit` registers accounts and refunds everything above the minimum
+    /// balance.
+    pub fn check_storage_deposit(&self) {
+        let setup = self.setup();
+        let bounds = setup.view("storage_balance_bounds", json!({}));
+        let min = parse_u128(&bounds["min"]);
+        assert!(min > 0, "NEP-145: the minimum storage balance must be positive");
+        if !bounds["max"].is_null() {
+            assert!(parse_u128(&bounds["max"]) >= min, "NEP-145: max must be at least min");
+        }
+        assert!(
+            setup.storage_balance_of(&setup.bob).is_null(),
+            "NEP-145: storage_balance_of must return null for unregistered accounts"
+        );
+
+        let deposit = |deposit: Balance| {
+            setup.call(
+                &setup.root,
+                "storage_deposit",
+                json!({ "account_id": setup.bob.account_id(), "registration_only": true }),
+                deposit,
+            )
+        };
+        assert_failure(&deposit(min - 1), "NEP-145: deposits below the minimum must fail");
+        assert!(setup.storage_balance_of(&setup.bob).is_null());
+
+        let balance_before = balance(&setup.root);
+        let res = deposit(to_yocto("1"));
+        assert_success(&res, "NEP-145: storage_deposit must register");
+        assert_paid(
+            &setup.root,
+            balance_before,
+            &res,
+            min as i128,
+            "NEP-145: the deposit above the minimum must be refunded with registration_only",
+        );
+        let storage_balance = setup.storage_balance_of(&setup.bob);
+        assert_eq!(parse_u128(&storage_balance["total"]), min);
+
+        let balance_before = balance(&setup.root);
+        let res = deposit(to_yocto("1"));
+        assert_success(&res, "NEP-145: storage_deposit must succeed");
+        assert_paid(
+            &setup.root,
+            balance_before,
+            &res,
+            0,
+            "NEP-145: the deposit for a registered account must be refunded with \
+             registration_only",
+        );
+        assert_eq!(setup.storage_balance_of(&setup.bob), storage_balance);
+    }
+
+    /// NEP-145: `storage_unregister` requires 1 yoctoNEAR and no tokens unless forced and
+    /// returns the storage balance.
+    pub fn check_storage_unregister(&self) {
+        let setup = self.setup();
+        let min = setup.min_storage_balance();
+        let unregister = |user: &UserAccount, deposit: Balance| {
+            setup.call(user, "storage_unregister", json!({}), deposit)
+        };
+
+        assert_failure(
+            &unregister(&setup.alice, 0),
+            "NEP-145: storage_unregister must require 1 yoctoNEAR",
+        );
+        assert_failure(
+            &unregister(&setup.owner, 1),
+            "NEP-145: storage_unregister of an account with tokens must fail without force",
+        );
+        assert_eq!(setup.balance_of(&setup.owner), setup.total_supply());
+
+        let balance_before = balance(&setup.alice);
+        let res = unregister(&setup.alice, 1);
+        assert_success(&res, "NEP-145: storage_unregister must succeed without tokens");
+        assert_eq!(res.unwrap_json_value(), json!(true));
+        // The attached yoctoNEAR may be kept or returned with the storage balance.
+        let paid = paid(&setup.alice, balance_before, &res);
+        assert!(
+            paid == 1 - min as i128 || paid == -(min as i128),
+            "NEP-145: storage_unregister must return the storage balance, {} paid {} yoctoNEAR",
+            setup.alice.account_id(),
+            paid
+        );
+        assert!(setup.storage_balance_of(&setup.alice).is_null());
+
+        let res = unregister(&setup.alice, 1);
+        assert_success(&res, "NEP-145: storage_unregister of an unregistered account");
+        assert_eq!(
+            res.unwrap_json_value(),
+            json!(false),
+            "NEP-145: storage_unregister must return false for unregistered accounts"
+        );
+    }
+
+    /// NEP-145: `storage_withdraw` requires 1 yoctoNEAR and can't withdraw more than available.
+    pub fn check_storage_withdraw(&self) {
+        let setup = self.setup();
+        let available = parse_u128(&setup.storage_balance_of(&setup.alice)["available"]);
+        let withdraw = |amount: Option<Balance>, deposit: Balance| {
+            let amount = amount.map(|amount| amount.to_string());
+            setup.call(&setup.alice, "storage_withdraw", json!({ "amount": amount }), deposit)
+        };
+
+        assert_failure(&withdraw(None, 0), "NEP-145: storage_withdraw must require 1 yoctoNEAR");
+        assert_failure(
+            &withdraw(Some(available + 1), 1),
+            "NEP-145: storage_withdraw of more than available must fail",
+        );
+        assert_failure(
+            &setup.call(&setup.bob, "storage_withdraw", json!({}), 1),
+            "NEP-145: storage_withdraw of an unregistered account must fail",
+        );
+        let res = withdraw(None, 1);
+        assert_success(&res, "NEP-145: storage_withdraw of the available balance must succeed");
+        assert_eq!(parse_u128(&res.unwrap_json_value()["available"]), 0);
+    }
+}
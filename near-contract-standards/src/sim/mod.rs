@@ -0,0 +1,127 @@
+//! Conformance test suites which check that a compiled contract follows the token standards.
+//!
+//! The suites deploy the contract in a fresh [`near_sdk_sim`] runtime for every check and panic
+//! with the violated standard on the first failure. Only the standard methods are called, so any
+//! contract can be checked, not only the ones built with this crate. The contract specific
+//! calls, like the initialization and minting, are passed in as [`SetupCall`]s.
+//!
+//! ```no_run
+//! use near_contract_standards::sim::{FtConformance, SetupCall};
+//! use near_sdk::serde_json::json;
+//!
+//! let ft_wasm = std::fs::read("res/fungible_token.wasm").unwrap();
+//! let receiver_wasm = std::fs::read("res/defi.wasm").unwrap();
+//! FtConformance::new(&ft_wasm, |owner_id| {
+//!     SetupCall::new(
+//!         "new_default_meta",
+//!         json!({ "owner_id": owner_id, "total_supply": "1000000" }),
+//!     )
+//! })
+//! .with_receiver(&receiver_wasm)
+//! .run();
+//! ```
+
+mod fungible_token;
+mod non_fungible_token;
+
+pub use self::fungible_token::FtConformance;
+pub use self::non_fungible_token::NftConformance;
+
+use near_sdk::serde_json::{self, Value};
+use near_sdk::{AccountId, Balance};
+use near_sdk_sim::{to_yocto, ExecutionResult, UserAccount, DEFAULT_GAS};
+
+/// A contract specific function call, e.g. the initialization or minting of a token.
+#[derive(Debug, Clone)]
+pub struct SetupCall {
+    pub method_name: String,
+    /// The JSON arguments of the call.
+    pub args: Value,
+    pub deposit: Balance,
+}
+
+impl SetupCall {
+    pub fn new(method_name: &str, args: Value) -> Self {
+        Self { method_name: method_name.to_string(), args, deposit: 0 }
+    }
+
+    pub fn with_deposit(mut self, deposit: Balance) -> Self {
+        self.deposit = deposit;
+        self
+    }
+
+    fn submit(&self, signer: &UserAccount, contract_id: AccountId) -> ExecutionResult {
+        call(signer, contract_id, &self.method_name, self.args.clone(), self.deposit)
+    }
+}
+
+fn call(
+    signer: &UserAccount,
+    contract_id: AccountId,
+    method_name: &str,
+    args: Value,
+    deposit: Balance,
+) -> ExecutionResult {
+    signer.call(contract_id, method_name, &serde_json::to_vec(&args).unwrap(), DEFAULT_GAS, deposit)
+}
+
+fn view(user: &UserAccount, contract_id: AccountId, method_name: &str, args: Value) -> Value {
+    user.view(contract_id, method_name, &serde_json::to_vec(&args).unwrap()).unwrap_json_value()
+}
+
+fn create_user(root: &UserAccount, account_id: &str) -> UserAccount {
+    root.create_user(AccountId::new_unchecked(account_id.to_string()), to_yocto("100"))
+}
+
+fn balance(user: &UserAccount) -> Balance {
+    user.account().unwrap().amount
+}
+
+/// Parses a `U128` JSON string.
+fn parse_u128(value: &Value) -> u128 {
+    value.as_str().and_then(|value| value.parse().ok()).unwrap_or_else(|| {
+        panic!("Expected a number encoded as string, got {}", value);
+    })
+}
+
+fn assert_success(res: &ExecutionResult, requirement: &str) {
+    assert!(res.is_ok(), "{}, the call failed:\n{}", requirement, res.receipt_tree());
+}
+
+fn assert_failure(res: &ExecutionResult, requirement: &str) {
+    assert!(!res.is_ok(), "{}, the call succeeded:\n{}", requirement, res.receipt_tree());
+}
+
+/// The balance burnt for the gas of the last call, which returned `res`, and all receipts
+/// created by it. The signer pays exactly this amount for the gas, the prepaid gas which isn't
+/// burnt is refunded.
+fn gas_cost(res: &ExecutionResult) -> Balance {
+    res.promise_results().iter().flatten().map(ExecutionResult::tokens_burnt).sum()
+}
+
+/// The balance `user` paid besides the gas of the last call, which returned `res`, since its
+/// balance was `balance_before`. Negative if `user` received more than it paid.
+fn paid(user: &UserAccount, balance_before: Balance, res: &ExecutionResult) -> i128 {
+    balance_before as i128 - balance(user) as i128 - gas_cost(res) as i128
+}
+
+/// Asserts that `user` [`paid`] exactly `cost`, i.e. the rest of the attached deposit was
+/// refunded.
+fn assert_paid(
+    user: &UserAccount,
+    balance_before: Balance,
+    res: &ExecutionResult,
+    cost: i128,
+    requirement: &str,
+) {
+    let paid = paid(user, balance_before, res);
+    assert_eq!(
+        paid,
+        cost,
+        "{}, {} paid {} yoctoNEAR instead of {}",
+        requirement,
+        user.account_id(),
+        paid,
+        cost
+    );
+}
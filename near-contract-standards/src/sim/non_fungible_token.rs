@@ -0,0 +1,577 @@
+use super::{
+    assert_failure, assert_paid, assert_success, balance, call, create_user, parse_u128, view,
+    SetupCall,
+};
+use crate::non_fungible_token::TokenId;
+use near_sdk::serde_json::{json, Value};
+use near_sdk::{AccountId, Balance};
+use near_sdk_sim::{init_simulator, to_yocto, ExecutionResult, UserAccount};
+
+const NFT_ID: &str = "nft";
+const RECEIVER_ID: &str = "receiver";
+/// The token minted to the owner by the setup of every check.
+const TOKEN_ID: &str = "0";
+/// The storage deposit of the accounts registered with storage management, 0.1 NEAR.
+const STORAGE_DEPOSIT: Balance = 100_000_000_000_000_000_000_000;
+
+type MintCall = dyn Fn(&TokenId, &AccountId) -> SetupCall;
+
+/// Conformance suite of the non-fungible token core ([NEP-171]), metadata ([NEP-177]),
+/// approval management ([NEP-178]) and enumeration ([NEP-181]) standards.
+///
+/// The contract is initialized with the call returned by `init`, which is called with the ID of
+/// the owner account. Tokens are minted by the owner with the call returned by `mint`, which is
+/// called with the token ID and the ID of the account receiving the token.
+///
+/// The callbacks of `nft_transfer_call` are checked with a receiver without a contract, which
+/// fails, and optionally with a [receiver contract](NftConformance::with_receiver). The storage
+/// management ([NEP-145]) is only checked if
+/// [enabled](NftConformance::with_storage_management).
+///
+/// [NEP-171]: https://nomicon.io/Standards/NonFungibleToken/Core.html
+/// [NEP-177]: https://nomicon.io/Standards/NonFungibleToken/Metadata.html
+/// [NEP-178]: https://nomicon.io/Standards/NonFungibleToken/ApprovalManagement.html
+/// [NEP-181]: https://nomicon.io/Standards/NonFungibleToken/Enumeration.html
+/// [NEP-145]: https://nomicon.io/Standards/StorageManagement.html
+pub struct NftConformance {
+    wasm: Vec<u8>,
+    init: Box<dyn Fn(&AccountId) -> SetupCall>,
+    mint: Box<MintCall>,
+    receiver_wasm: Option<Vec<u8>>,
+    storage_management: bool,
+}
+
+/// Accounts of a single check. `owner` owns the token [`TOKEN_ID`].
+struct Setup {
+    root: UserAccount,
+    nft: UserAccount,
+    owner: UserAccount,
+    alice: UserAccount,
+    bob: UserAccount,
+}
+
+impl Setup {
+    fn call(
+        &self,
+        signer: &UserAccount,
+        method_name: &str,
+        args: Value,
+        deposit: Balance,
+    ) -> ExecutionResult {
+        call(signer, self.nft.account_id(), method_name, args, deposit)
+    }
+
+    fn view(&self, method_name: &str, args: Value) -> Value {
+        view(&self.root, self.nft.account_id(), method_name, args)
+    }
+
+    fn token(&self, token_id: &str) -> Value {
+        self.view("nft_token", json!({ "token_id": token_id }))
+    }
+
+    fn owner_of(&self, token_id: &str) -> AccountId {
+        let token = self.token(token_id);
+        AccountId::new_unchecked(
+            token["owner_id"]
+                .as_str()
+                .unwrap_or_else(|| panic!("NEP-171: token {} must exist", token_id))
+                .to_string(),
+        )
+    }
+
+    fn transfer(
+        &self,
+        sender: &UserAccount,
+        receiver: &UserAccount,
+        approval_id: Option<u64>,
+    ) -> ExecutionResult {
+        self.call(
+            sender,
+            "nft_transfer",
+            json!({
+                "receiver_id": receiver.account_id(),
+                "token_id": TOKEN_ID,
+                "approval_id": approval_id,
+            }),
+            1,
+        )
+    }
+
+    fn transfer_call(&self, receiver_id: AccountId, msg: &str) -> ExecutionResult {
+        self.call(
+            &self.owner,
+            "nft_transfer_call",
+            json!({ "receiver_id": receiver_id, "token_id": TOKEN_ID, "msg": msg }),
+            1,
+        )
+    }
+
+    fn approve(&self, account: &UserAccount, deposit: Balance) -> ExecutionResult {
+        self.call(
+            &self.owner,
+            "nft_approve",
+            json!({ "token_id": TOKEN_ID, "account_id": account.account_id() }),
+            deposit,
+        )
+    }
+
+    fn is_approved(&self, account: &UserAccount, approval_id: Option<u64>) -> bool {
+        let approved = self.view(
+            "nft_is_approved",
+            json!({
+                "token_id": TOKEN_ID,
+                "approved_account_id": account.account_id(),
+                "approval_id": approval_id,
+            }),
+        );
+        approved.as_bool().expect("NEP-178: nft_is_approved must return a bool")
+    }
+
+    fn storage_deposit(&self, account: &UserAccount) -> ExecutionResult {
+        self.call(
+            &self.root,
+            "storage_deposit",
+            json!({ "account_id": account.account_id() }),
+            STORAGE_DEPOSIT,
+        )
+    }
+
+    fn storage_balance_of(&self, account: &UserAccount) -> Value {
+        self.view("storage_balance_of", json!({ "account_id": account.account_id() }))
+    }
+
+    fn available_storage_balance(&self, account: &UserAccount) -> Balance {
+        parse_u128(&self.storage_balance_of(account)["available"])
+    }
+}
+
+impl NftConformance {
+    pub fn new<I, M>(wasm: &[u8], init: I, mint: M) -> Self
+    where
+        I: Fn(&AccountId) -> SetupCall + 'static,
+        M: Fn(&TokenId, &AccountId) -> SetupCall + 'static,
+    {
+        Self {
+            wasm: wasm.to_vec(),
+            init: Box::new(init),
+            mint: Box::new(mint),
+            receiver_wasm: None,
+            storage_management: false,
+        }
+    }
+
+    /// Checks `nft_transfer_call` with a receiver contract, like `test-token-receiver` of the
+    /// non-fungible token example. The receiver is initialized with
+    /// `new(non_fungible_token_account_id)` and keeps or returns the token for the `msg`s
+    /// `"keep-it-now"`, `"keep-it-later"`, `"return-it-now"` and `"return-it-later"`.
+    pub fn with_receiver(mut self, wasm: &[u8]) -> Self {
+        self.receiver_wasm = Some(wasm.to_vec());
+        self
+    }
+
+    /// Checks the storage management of contracts where accounts prepay the storage of their
+    /// tokens with `storage_deposit`, like `test-storage-nft` of the non-fungible token example.
+    /// The owner, alice and bob are registered with 0.1 NEAR before the token is minted.
+    pub fn with_storage_management(mut self) -> Self {
+        self.storage_management = true;
+        self
+    }
+
+    /// Runs all checks.
+    pub fn run(&self) {
+        self.check_token();
+        self.check_transfer();
+        self.check_transfer_call_refund();
+        self.check_transfer_call_receiver();
+        self.check_metadata();
+        self.check_approve();
+        self.check_approved_transfer();
+        self.check_revoke();
+        self.check_enumeration();
+        self.check_storage_deposit();
+        self.check_storage_mint();
+        self.check_storage_transfer();
+    }
+
+    fn setup(&self) -> Setup {
+        let root = init_simulator(None);
+        let nft =
+            root.deploy(&self.wasm, AccountId::new_unchecked(NFT_ID.to_string()), to_yocto("50"));
+        let owner = create_user(&root, "owner");
+        let res = (self.init)(&owner.account_id()).submit(&nft, nft.account_id());
+        assert_success(&res, "The initialization must succeed");
+        let setup = Setup {
+            alice: create_user(&root, "alice"),
+            bob: create_user(&root, "bob"),
+            root,
+            nft,
+            owner,
+        };
+        if self.storage_management {
+            for account in &[&setup.owner, &setup.alice, &setup.bob] {
+                let res = setup.storage_deposit(account);
+                assert_success(&res, "NEP-145: storage_deposit must register");
+            }
+        }
+        self.mint(&setup, TOKEN_ID, &setup.owner);
+        setup
+    }
+
+    fn mint(&self, setup: &Setup, token_id: &str, receiver: &UserAccount) {
+        let res = (self.mint)(&token_id.to_string(), &receiver.account_id())
+            .submit(&setup.owner, setup.nft.account_id());
+        assert_success(&res, "Minting must succeed");
+    }
+
+    /// NEP-171: `nft_token` returns the owner of existing tokens and null otherwise.
+    pub fn check_token(&self) {
+        let setup = self.setup();
+        let token = setup.token(TOKEN_ID);
+        assert_eq!(token["token_id"], json!(TOKEN_ID), "NEP-171: nft_token must return the ID");
+        assert_eq!(setup.owner_of(TOKEN_ID), setup.owner.account_id());
+        assert!(
+            setup.token("missing").is_null(),
+            "NEP-171: nft_token must return null for tokens which don't exist"
+        );
+    }
+
+    /// NEP-171: `nft_transfer` requires 1 yoctoNEAR and can only be called by the owner.
+    pub fn check_transfer(&self) {
+        let setup = self.setup();
+        let res = setup.call(
+            &setup.owner,
+            "nft_transfer",
+            json!({ "receiver_id": setup.alice.account_id(), "token_id": TOKEN_ID }),
+            0,
+        );
+        assert_failure(&res, "NEP-171: nft_transfer must require 1 yoctoNEAR");
+        assert_failure(
+            &setup.transfer(&setup.alice, &setup.bob, None),
+            "NEP-171: nft_transfer by an account which isn't the owner or approved must fail",
+        );
+        assert_eq!(setup.owner_of(TOKEN_ID), setup.owner.account_id());
+
+        let res = setup.transfer(&setup.owner, &setup.alice, None);
+        assert_success(&res, "NEP-171: nft_transfer by the owner must succeed");
+        assert_eq!(setup.owner_of(TOKEN_ID), setup.alice.account_id());
+        assert_failure(
+            &setup.transfer(&setup.owner, &setup.bob, None),
+            "NEP-171: nft_transfer by the previous owner must fail",
+        );
+    }
+
+    /// NEP-171: the token is returned when `nft_on_transfer` of the receiver fails.
+    pub fn check_transfer_call_refund(&self) {
+        let setup = self.setup();
+        let res = setup.transfer_call(setup.bob.account_id(), "");
+        assert_success(&res, "NEP-171: nft_transfer_call must succeed if the receiver fails");
+        assert_eq!(
+            res.unwrap_json_value(),
+            json!(false),
+            "NEP-171: nft_transfer_call must return false if the receiver fails"
+        );
+        assert_eq!(
+            setup.owner_of(TOKEN_ID),
+            setup.owner.account_id(),
+            "NEP-171: the token must be returned if the receiver fails"
+        );
+    }
+
+    /// NEP-171: the token is returned if `nft_on_transfer` returns `true`. Skipped without a
+    /// receiver contract.
+    pub fn check_transfer_call_receiver(&self) {
+        let receiver_wasm = match &self.receiver_wasm {
+            Some(wasm) => wasm,
+            None => return,
+        };
+        let setup = self.setup();
+        let receiver = setup.root.deploy(
+            receiver_wasm,
+            AccountId::new_unchecked(RECEIVER_ID.to_string()),
+            to_yocto("50"),
+        );
+        let res = call(
+            &receiver,
+            receiver.account_id(),
+            "new",
+            json!({ "non_fungible_token_account_id": setup.nft.account_id() }),
+            0,
+        );
+        assert_success(&res, "The receiver initialization must succeed");
+
+        for msg in &["return-it-now", "return-it-later"] {
+            let res = setup.transfer_call(receiver.account_id(), msg);
+            assert_success(&res, "NEP-171: nft_transfer_call must succeed");
+            assert_eq!(
+                res.unwrap_json_value(),
+                json!(false),
+                "NEP-171: nft_transfer_call must return false if the token is returned"
+            );
+            assert_eq!(
+                setup.owner_of(TOKEN_ID),
+                setup.owner.account_id(),
+                "NEP-171: the token must be returned if nft_on_transfer returns true"
+            );
+        }
+
+        let res = setup.transfer_call(receiver.account_id(), "keep-it-later");
+        assert_success(&res, "NEP-171: nft_transfer_call must succeed");
+        assert_eq!(
+            res.unwrap_json_value(),
+            json!(true),
+            "NEP-171: nft_transfer_call must return true if the token is kept"
+        );
+        assert_eq!(setup.owner_of(TOKEN_ID), receiver.account_id());
+    }
+
+    /// NEP-177: the contract and tokens have metadata.
+    pub fn check_metadata(&self) {
+        let setup = self.setup();
+        let metadata = setup.view("nft_metadata", json!({}));
+        let spec = metadata["spec"].as_str().unwrap_or_default();
+        assert!(spec.starts_with("nft-"), "NEP-177: spec must start with `nft-`, got {}", spec);
+        assert!(
+            !metadata["name"].as_str().unwrap_or_default().is_empty(),
+            "NEP-177: the contract metadata must have a name"
+        );
+        assert!(
+            !metadata["symbol"].as_str().unwrap_or_default().is_empty(),
+            "NEP-177: the contract metadata must have a symbol"
+        );
+        assert!(
+            setup.token(TOKEN_ID)["metadata"].is_object(),
+            "NEP-177: nft_token must return the token metadata"
+        );
+    }
+
+    /// NEP-178: `nft_approve` requires a deposit for the storage, refunds the rest and returns
+    /// incrementing approval IDs.
+    pub fn check_approve(&self) {
+        let setup = self.setup();
+        assert_failure(
+            &setup.approve(&setup.alice, 0),
+            "NEP-178: nft_approve must require at least 1 yoctoNEAR",
+        );
+        let res = setup.call(
+            &setup.alice,
+            "nft_approve",
+            json!({ "token_id": TOKEN_ID, "account_id": setup.bob.account_id() }),
+            to_yocto("1"),
+        );
+        assert_failure(&res, "NEP-178: nft_approve by an account which isn't the owner must fail");
+        assert!(!setup.is_approved(&setup.alice, None));
+
+        let balance_before = balance(&setup.owner);
+        let res = setup.approve(&setup.alice, to_yocto("1"));
+        assert_success(&res, "NEP-178: nft_approve by the owner must succeed");
+        assert_paid(
+            &setup.owner,
+            balance_before,
+            &res,
+            res.storage_report().storage_cost(NFT_ID),
+            "NEP-178: the deposit above the storage cost must be refunded",
+        );
+        assert!(setup.is_approved(&setup.alice, None), "NEP-178: the account must be approved");
+        let approvals = &setup.token(TOKEN_ID)["approved_account_ids"];
+        let approval_id = approvals[setup.alice.account_id().as_str()]
+            .as_u64()
+            .expect("NEP-178: nft_token must return the approved account IDs");
+        assert!(setup.is_approved(&setup.alice, Some(approval_id)));
+        assert!(!setup.is_approved(&setup.alice, Some(approval_id + 1)));
+        assert!(!setup.is_approved(&setup.bob, None));
+
+        // Approving the same account again doesn't use more storage.
+        let balance_before = balance(&setup.owner);
+        let res = setup.approve(&setup.alice, 1);
+        assert_success(&res, "NEP-178: nft_approve must succeed");
+        assert_paid(&setup.owner, balance_before, &res, 1, "NEP-178: re-approving uses no storage");
+        let approvals = &setup.token(TOKEN_ID)["approved_account_ids"];
+        assert!(
+            approvals[setup.alice.account_id().as_str()].as_u64() > Some(approval_id),
+            "NEP-178: approval IDs must increase"
+        );
+    }
+
+    /// NEP-178: approved accounts can transfer the token, which clears all approvals.
+    pub fn check_approved_transfer(&self) {
+        let setup = self.setup();
+        setup.approve(&setup.alice, to_yocto("1")).assert_success();
+        let approvals = &setup.token(TOKEN_ID)["approved_account_ids"];
+        let approval_id = approvals[setup.alice.account_id().as_str()].as_u64().unwrap();
+
+        assert_failure(
+            &setup.transfer(&setup.alice, &setup.bob, Some(approval_id + 1)),
+            "NEP-178: nft_transfer with a wrong approval ID must fail",
+        );
+        assert_success(
+            &setup.transfer(&setup.alice, &setup.bob, Some(approval_id)),
+            "NEP-178: nft_transfer by an approved account must succeed",
+        );
+        assert_eq!(setup.owner_of(TOKEN_ID), setup.bob.account_id());
+        assert!(
+            !setup.is_approved(&setup.alice, None),
+            "NEP-178: approvals must be cleared when the token is transferred"
+        );
+        assert_failure(
+            &setup.transfer(&setup.alice, &setup.owner, Some(approval_id)),
+            "NEP-178: nft_transfer with a cleared approval must fail",
+        );
+    }
+
+    /// NEP-178: `nft_revoke` and `nft_revoke_all` require 1 yoctoNEAR and remove approvals.
+    pub fn check_revoke(&self) {
+        let setup = self.setup();
+        setup.approve(&setup.alice, to_yocto("1")).assert_success();
+        setup.approve(&setup.bob, to_yocto("1")).assert_success();
+        let revoke = |deposit: Balance| {
+            setup.call(
+                &setup.owner,
+                "nft_revoke",
+                json!({ "token_id": TOKEN_ID, "account_id": setup.alice.account_id() }),
+                deposit,
+            )
+        };
+
+        assert_failure(&revoke(0), "NEP-178: nft_revoke must require 1 yoctoNEAR");
+        assert_success(&revoke(1), "NEP-178: nft_revoke by the owner must succeed");
+        assert!(!setup.is_approved(&setup.alice, None), "NEP-178: nft_revoke must revoke");
+        assert!(setup.is_approved(&setup.bob, None), "NEP-178: nft_revoke must keep others");
+
+        let revoke_all = |deposit: Balance| {
+            setup.call(&setup.owner, "nft_revoke_all", json!({ "token_id": TOKEN_ID }), deposit)
+        };
+        assert_failure(&revoke_all(0), "NEP-178: nft_revoke_all must require 1 yoctoNEAR");
+        assert_success(&revoke_all(1), "NEP-178: nft_revoke_all by the owner must succeed");
+        assert!(!setup.is_approved(&setup.bob, None), "NEP-178: nft_revoke_all must revoke all");
+    }
+
+    /// NEP-181: tokens can be enumerated in total and by owner.
+    pub fn check_enumeration(&self) {
+        let setup = self.setup();
+        self.mint(&setup, "1", &setup.alice);
+        self.mint(&setup, "2", &setup.alice);
+        let supply_for_owner = |user: &UserAccount| {
+            parse_u128(
+                &setup.view("nft_supply_for_owner", json!({ "account_id": user.account_id() })),
+            )
+        };
+        let tokens_for_owner = |user: &UserAccount, from_index: u128, limit: u64| {
+            setup.view(
+                "nft_tokens_for_owner",
+                json!({
+                    "account_id": user.account_id(),
+                    "from_index": from_index.to_string(),
+                    "limit": limit,
+                }),
+            )
+        };
+        let len = |tokens: Value| tokens.as_array().map(Vec::len).unwrap_or_default();
+
+        assert_eq!(parse_u128(&setup.view("nft_total_supply", json!({}))), 3);
+        assert_eq!(len(setup.view("nft_tokens", json!({}))), 3);
+        assert_eq!(
+            len(setup.view("nft_tokens", json!({ "from_index": "1", "limit": 5 }))),
+            2,
+            "NEP-181: nft_tokens must skip `from_index` tokens"
+        );
+        assert_eq!(
+            len(setup.view("nft_tokens", json!({ "limit": 2 }))),
+            2,
+            "NEP-181: nft_tokens must return at most `limit` tokens"
+        );
+
+        assert_eq!(supply_for_owner(&setup.alice), 2);
+        assert_eq!(supply_for_owner(&setup.owner), 1);
+        assert_eq!(supply_for_owner(&setup.bob), 0);
+        assert_eq!(len(tokens_for_owner(&setup.alice, 0, 5)), 2);
+        assert_eq!(len(tokens_for_owner(&setup.alice, 1, 5)), 1);
+        assert_eq!(len(tokens_for_owner(&setup.alice, 0, 1)), 1);
+        assert_eq!(len(tokens_for_owner(&setup.bob, 0, 5)), 0);
+
+        setup.transfer(&setup.owner, &setup.bob, None).assert_success();
+        assert_eq!(
+            supply_for_owner(&setup.bob),
+            1,
+            "NEP-181: the supply for the owner must be updated on transfer"
+        );
+        assert_eq!(supply_for_owner(&setup.owner), 0);
+    }
+
+    /// NEP-145: `storage_deposit` registers the account with the attached deposit, which
+    /// `storage_balance_of` returns. Skipped without storage management.
+    pub fn check_storage_deposit(&self) {
+        if !self.storage_management {
+            return;
+        }
+        let setup = self.setup();
+        let carol = create_user(&setup.root, "carol");
+        let min = parse_u128(&setup.view("storage_balance_bounds", json!({}))["min"]);
+        assert!(min <= STORAGE_DEPOSIT, "NEP-145: the minimum storage balance is above 0.1 NEAR");
+        assert!(
+            setup.storage_balance_of(&carol).is_null(),
+            "NEP-145: storage_balance_of must return null for unregistered accounts"
+        );
+
+        let balance_before = balance(&setup.root);
+        let res = setup.storage_deposit(&carol);
+        assert_success(&res, "NEP-145: storage_deposit must register");
+        assert_paid(
+            &setup.root,
+            balance_before,
+            &res,
+            STORAGE_DEPOSIT as i128,
+            "NEP-145: storage_deposit must keep the whole deposit",
+        );
+        let storage_balance = setup.storage_balance_of(&carol);
+        assert_eq!(
+            parse_u128(&storage_balance["total"]),
+            STORAGE_DEPOSIT,
+            "NEP-145: storage_balance_of must return the deposit"
+        );
+        assert!(parse_u128(&storage_balance["available"]) <= STORAGE_DEPOSIT);
+    }
+
+    /// NEP-145: minting a token draws its storage from the prepaid storage balance of the
+    /// receiver. Skipped without storage management.
+    pub fn check_storage_mint(&self) {
+        if !self.storage_management {
+            return;
+        }
+        let setup = self.setup();
+        let available_before = setup.available_storage_balance(&setup.alice);
+        let res = (self.mint)(&"1".to_string(), &setup.alice.account_id())
+            .submit(&setup.owner, setup.nft.account_id());
+        assert_success(&res, "Minting must succeed");
+        let drawn = available_before - setup.available_storage_balance(&setup.alice);
+        assert!(
+            drawn > 0 && (drawn as i128) <= res.storage_report().storage_cost(NFT_ID),
+            "NEP-145: minting must draw the storage of the token from the storage balance of the \
+             receiver, drew {} yoctoNEAR",
+            drawn
+        );
+    }
+
+    /// NEP-145: transferring a token draws the storage of the new owner from its prepaid storage
+    /// balance and releases the storage of the previous owner. Skipped without storage
+    /// management.
+    pub fn check_storage_transfer(&self) {
+        if !self.storage_management {
+            return;
+        }
+        let setup = self.setup();
+        let owner_available_before = setup.available_storage_balance(&setup.owner);
+        let alice_available_before = setup.available_storage_balance(&setup.alice);
+        assert_success(
+            &setup.transfer(&setup.owner, &setup.alice, None),
+            "NEP-171: nft_transfer by the owner must succeed",
+        );
+        assert!(
+            setup.available_storage_balance(&setup.alice) < alice_available_before,
+            "NEP-145: nft_transfer must draw the storage of the receiver from its storage balance"
+        );
+        assert!(
+            setup.available_storage_balance(&setup.owner) > owner_available_before,
+            "NEP-145: nft_transfer must release the storage of the previous owner"
+        );
+    }
+}
@@ -0,0 +1,42 @@
+/// Exposes the storage management standard on a contract, with the registrations and storage
+/// deposits kept by a [`StorageBalances`](crate::storage_management::StorageBalances).
+/// Takes name of the Contract struct and the inner field for the storage balances.
+#[macro_export]
+macro_rules! impl_storage_management {
+    ($contract: ident, $storage: ident) => {
+        use $crate::storage_management::{StorageBalance, StorageBalanceBounds, StorageManagement};
+
+        #[near_bindgen]
+        impl StorageManagement for $contract {
+            #[payable]
+            fn storage_deposit(
+                &mut self,
+                account_id: Option<AccountId>,
+                registration_only: Option<bool>,
+            ) -> StorageBalance {
+                self.$storage.storage_deposit(account_id, registration_only)
+            }
+
+            #[payable]
+            fn storage_withdraw(
+                &mut self,
+                amount: Option<near_sdk::json_types::U128>,
+            ) -> StorageBalance {
+                self.$storage.storage_withdraw(amount)
+            }
+
+            #[payable]
+            fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+                self.$storage.storage_unregister(force)
+            }
+
+            fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+                self.$storage.storage_balance_bounds()
+            }
+
+            fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+                self.$storage.storage_balance_of(account_id)
+            }
+        }
+    };
+}
@@ -1,3 +1,9 @@
+mod macros;
+mod storage_balances;
+
+pub use macros::*;
+pub use storage_balances::*;
+
 use near_sdk::json_types::U128;
 use near_sdk::serde::Serialize;
 use near_sdk::AccountId;
@@ -0,0 +1,504 @@
+use crate::storage_management::{StorageBalance, StorageBalanceBounds, StorageManagement};
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::json_types::U128;
+use near_sdk::{
+    assert_one_yocto, env, log, require, AccountId, Balance, IntoStorageKey, Promise, StorageUsage,
+};
+
+/// The storage deposit of an account and the bytes it pays for.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, PartialEq)]
+pub struct AccountStorage {
+    /// The total deposit of the account.
+    pub deposit: Balance,
+    /// The bytes paid by the deposit, including the registration of the account.
+    pub bytes_used: StorageUsage,
+}
+
+impl AccountStorage {
+    /// The part of the deposit not paying for any bytes.
+    pub fn available(&self) -> Balance {
+        self.deposit.saturating_sub(Balance::from(self.bytes_used) * env::storage_byte_cost())
+    }
+}
+
+/// Implementation of the [storage management standard] that any contract can use to make
+/// accounts pay for the storage they use.
+///
+/// Accounts register and prepay with `storage_deposit`. The contract then draws from their
+/// deposits with [`StorageBalances::charge`] whenever it stores data on behalf of an account,
+/// and gives the storage back with [`StorageBalances::release`] when the data is removed.
+/// Usually the bytes are measured from the change of `env::storage_usage()` around the change
+/// of the state, which [`StorageBalances::settle`] does.
+///
+/// `storage_balance_bounds.min` pays for the registration of the account itself, and
+/// `storage_balance_bounds.max` limits the deposit of an account if a maximum is set.
+///
+/// [storage management standard]: https://nomicon.io/Standards/StorageManagement.html
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct StorageBalances {
+    /// AccountID -> Account storage deposit and bytes used.
+    pub accounts: LookupMap<AccountId, AccountStorage>,
+
+    /// The storage size in bytes for registering one account.
+    pub account_storage_usage: StorageUsage,
+
+    /// The maximum number of bytes an account can prepay for, unlimited if `None`.
+    pub max_storage_usage: Option<StorageUsage>,
+}
+
+impl StorageBalances {
+    pub fn new<S>(prefix: S, max_storage_usage: Option<StorageUsage>) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let mut this =
+            Self { accounts: LookupMap::new(prefix), account_storage_usage: 0, max_storage_usage };
+        this.measure_account_storage_usage();
+        if let Some(max_storage_usage) = max_storage_usage {
+            require!(
+                max_storage_usage >= this.account_storage_usage,
+                "The maximum storage usage must cover the registration of an account"
+            );
+        }
+        this
+    }
+
+    fn measure_account_storage_usage(&mut self) {
+        let initial_storage_usage = env::storage_usage();
+        let tmp_account_id = AccountId::new_unchecked("a".repeat(64));
+        self.accounts.insert(&tmp_account_id, &AccountStorage { deposit: 0, bytes_used: 0 });
+        self.account_storage_usage = env::storage_usage() - initial_storage_usage;
+        self.accounts.remove(&tmp_account_id);
+    }
+
+    pub fn is_registered(&self, account_id: &AccountId) -> bool {
+        self.accounts.contains_key(account_id)
+    }
+
+    /// Returns the storage of a registered account, panics otherwise.
+    pub fn internal_unwrap_account(&self, account_id: &AccountId) -> AccountStorage {
+        self.accounts.get(account_id).unwrap_or_else(|| {
+            env::panic_str(format!("The account {} is not registered", &account_id).as_str())
+        })
+    }
+
+    /// Registers the account with `deposit`, which must pay for the registration.
+    pub fn internal_register_account(&mut self, account_id: &AccountId, deposit: Balance) {
+        let storage = AccountStorage { deposit, bytes_used: self.account_storage_usage };
+        require!(storage.deposit >= self.storage_balance_bounds().min.0, "The deposit is too low");
+        if self.accounts.insert(account_id, &storage).is_some() {
+            env::panic_str("The account is already registered");
+        }
+    }
+
+    /// Draws the cost of `bytes` from the available storage balance of the account.
+    /// Panics if the account is not registered or its available balance doesn't cover them.
+    pub fn charge(&mut self, account_id: &AccountId, bytes: StorageUsage) {
+        if bytes == 0 {
+            return;
+        }
+        let mut storage = self.internal_unwrap_account(account_id);
+        let required_cost = Balance::from(bytes) * env::storage_byte_cost();
+        let available = storage.available();
+        if available < required_cost {
+            env::panic_str(
+                format!(
+                    "The account {} needs {} yoctoNEAR more of storage balance",
+                    account_id,
+                    required_cost - available
+                )
+                .as_str(),
+            )
+        }
+        storage.bytes_used += bytes;
+        self.accounts.insert(account_id, &storage);
+    }
+
+    /// Gives the cost of `bytes` back to the available storage balance of the account. Nothing
+    /// is released for accounts that aren't registered, and never the registration itself.
+    pub fn release(&mut self, account_id: &AccountId, bytes: StorageUsage) {
+        if bytes == 0 {
+            return;
+        }
+        if let Some(mut storage) = self.accounts.get(account_id) {
+            storage.bytes_used =
+                storage.bytes_used.saturating_sub(bytes).max(self.account_storage_usage);
+            self.accounts.insert(account_id, &storage);
+        }
+    }
+
+    /// Charges the account for the storage used since `initial_storage_usage`, or releases the
+    /// storage freed since then.
+    pub fn settle(&mut self, account_id: &AccountId, initial_storage_usage: StorageUsage) {
+        let storage_usage = env::storage_usage();
+        if storage_usage > initial_storage_usage {
+            self.charge(account_id, storage_usage - initial_storage_usage);
+        } else {
+            self.release(account_id, initial_storage_usage - storage_usage);
+        }
+    }
+
+    /// Like [`StorageBalances::settle`], but never panics, for callbacks which must not fail.
+    /// Storage the account can't pay for, e.g. because it withdrew its balance or unregistered
+    /// in the meantime, is paid by the contract. Returns whether the account paid for it.
+    pub fn try_settle(
+        &mut self,
+        account_id: &AccountId,
+        initial_storage_usage: StorageUsage,
+    ) -> bool {
+        let storage_usage = env::storage_usage();
+        if storage_usage <= initial_storage_usage {
+            self.release(account_id, initial_storage_usage - storage_usage);
+            return true;
+        }
+        let bytes = storage_usage - initial_storage_usage;
+        match self.accounts.get(account_id) {
+            Some(storage)
+                if storage.available() >= Balance::from(bytes) * env::storage_byte_cost() =>
+            {
+                self.charge(account_id, bytes);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Internal method that returns the Account ID and its storage in case the account was
+    /// unregistered. With `force`, the account is unregistered even if it still pays for more
+    /// than its registration.
+    ///
+    /// The whole deposit is refunded, including the part paying for the bytes still in use, so
+    /// the caller must remove the data of the account in the same call. Otherwise the contract
+    /// pays for the storage left behind.
+    pub fn internal_storage_unregister(
+        &mut self,
+        force: Option<bool>,
+    ) -> Option<(AccountId, AccountStorage)> {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let force = force.unwrap_or(false);
+        if let Some(storage) = self.accounts.get(&account_id) {
+            if storage.bytes_used <= self.account_storage_usage || force {
+                self.accounts.remove(&account_id);
+                if storage.deposit > 0 {
+                    Promise::new(account_id.clone()).transfer(storage.deposit);
+                }
+                Some((account_id, storage))
+            } else {
+                env::panic_str("Can't unregister the account with storage in use without force")
+            }
+        } else {
+            log!("The account {} is not registered", &account_id);
+            None
+        }
+    }
+
+    fn internal_storage_balance_of(&self, account_id: &AccountId) -> Option<StorageBalance> {
+        self.accounts.get(account_id).map(|storage| StorageBalance {
+            total: storage.deposit.into(),
+            available: storage.available().into(),
+        })
+    }
+}
+
+impl StorageManagement for StorageBalances {
+    fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance {
+        let amount: Balance = env::attached_deposit();
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        let registration_only = registration_only.unwrap_or(false);
+        let bounds = self.storage_balance_bounds();
+        let max_balance = bounds.max.map_or(Balance::MAX, |max| max.0);
+
+        let refund = if let Some(mut storage) = self.accounts.get(&account_id) {
+            if registration_only {
+                log!("The account is already registered, refunding the deposit");
+                amount
+            } else {
+                let max_balance = std::cmp::max(max_balance, storage.deposit);
+                let deposit = std::cmp::min(storage.deposit.saturating_add(amount), max_balance);
+                let refund = amount - (deposit - storage.deposit);
+                storage.deposit = deposit;
+                self.accounts.insert(&account_id, &storage);
+                refund
+            }
+        } else {
+            let min_balance = bounds.min.0;
+            if amount < min_balance {
+                env::panic_str("The attached deposit is less than the minimum storage balance");
+            }
+            let deposit =
+                if registration_only { min_balance } else { std::cmp::min(amount, max_balance) };
+            self.internal_register_account(&account_id, deposit);
+            amount - deposit
+        };
+        if refund > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+        self.internal_storage_balance_of(&account_id).unwrap()
+    }
+
+    fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        assert_one_yocto();
+        let predecessor_account_id = env::predecessor_account_id();
+        let mut storage = self.internal_unwrap_account(&predecessor_account_id);
+        let available = storage.available();
+        let amount = amount.map_or(available, |amount| amount.0);
+        if amount > available {
+            env::panic_str("The amount is greater than the available storage balance");
+        }
+        if amount > 0 {
+            storage.deposit -= amount;
+            self.accounts.insert(&predecessor_account_id, &storage);
+            Promise::new(predecessor_account_id.clone()).transfer(amount);
+        }
+        self.internal_storage_balance_of(&predecessor_account_id).unwrap()
+    }
+
+    /// Only the contract knows how to remove the data of an account, so this implementation
+    /// panics if the account still pays for more than its registration, even with `force`.
+    /// Contracts supporting `force` call [`StorageBalances::internal_storage_unregister`] and
+    /// remove the data of the account themselves.
+    fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+        if force.unwrap_or(false) {
+            let predecessor_account_id = env::predecessor_account_id();
+            if let Some(storage) = self.accounts.get(&predecessor_account_id) {
+                require!(
+                    storage.bytes_used <= self.account_storage_usage,
+                    "Force unregistration is not supported"
+                );
+            }
+        }
+        self.internal_storage_unregister(force).is_some()
+    }
+
+    fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        let byte_cost = env::storage_byte_cost();
+        StorageBalanceBounds {
+            min: (Balance::from(self.account_storage_usage) * byte_cost).into(),
+            max: self.max_storage_usage.map(|max| (Balance::from(max) * byte_cost).into()),
+        }
+    }
+
+    fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        self.internal_storage_balance_of(&account_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    const DEPOSIT: Balance = 10u128.pow(22);
+
+    fn context(attached_deposit: Balance) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(attached_deposit);
+        builder
+    }
+
+    fn cost(bytes: StorageUsage) -> Balance {
+        Balance::from(bytes) * env::storage_byte_cost()
+    }
+
+    /// Storage balances with bob registered with `deposit`.
+    fn setup(max_storage_usage: Option<StorageUsage>, deposit: Balance) -> StorageBalances {
+        testing_env!(context(deposit).build());
+        let mut storage_balances = StorageBalances::new(b"s".to_vec(), max_storage_usage);
+        storage_balances.storage_deposit(None, None);
+        storage_balances
+    }
+
+    fn refunds() -> Vec<Balance> {
+        get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| receipt.actions)
+            .filter_map(|action| match action {
+                VmAction::Transfer { deposit } => Some(deposit),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn charge_and_release() {
+        let mut storage_balances = setup(None, DEPOSIT);
+        let registration = storage_balances.account_storage_usage;
+        let storage = storage_balances.internal_unwrap_account(&accounts(1));
+        assert_eq!(storage.available(), DEPOSIT - cost(registration));
+
+        storage_balances.charge(&accounts(1), 100);
+        let storage = storage_balances.internal_unwrap_account(&accounts(1));
+        assert_eq!(storage.bytes_used, registration + 100);
+        assert_eq!(storage.available(), DEPOSIT - cost(registration + 100));
+
+        storage_balances.release(&accounts(1), 40);
+        assert_eq!(
+            storage_balances.internal_unwrap_account(&accounts(1)).bytes_used,
+            registration + 60
+        );
+
+        // The registration itself is never released.
+        storage_balances.release(&accounts(1), 1000);
+        assert_eq!(storage_balances.internal_unwrap_account(&accounts(1)).bytes_used, registration);
+
+        // Releasing for an account that isn't registered does nothing.
+        storage_balances.release(&accounts(2), 100);
+        assert!(!storage_balances.is_registered(&accounts(2)));
+    }
+
+    #[test]
+    fn settle_measures_storage_usage() {
+        let mut storage_balances = setup(None, DEPOSIT);
+        let registration = storage_balances.account_storage_usage;
+        let mut data = LookupMap::new(b"d".to_vec());
+
+        let initial_storage_usage = env::storage_usage();
+        data.insert(&accounts(1), &vec![0u8; 100]);
+        let bytes = env::storage_usage() - initial_storage_usage;
+        storage_balances.settle(&accounts(1), initial_storage_usage);
+        assert_eq!(
+            storage_balances.internal_unwrap_account(&accounts(1)).bytes_used,
+            registration + bytes
+        );
+
+        let initial_storage_usage = env::storage_usage();
+        data.remove(&accounts(1));
+        storage_balances.settle(&accounts(1), initial_storage_usage);
+        assert_eq!(storage_balances.internal_unwrap_account(&accounts(1)).bytes_used, registration);
+    }
+
+    #[test]
+    fn try_settle_falls_back_to_contract() {
+        let mut storage_balances = StorageBalances::new(b"s".to_vec(), None);
+        let min_balance = storage_balances.storage_balance_bounds().min.0;
+        testing_env!(context(min_balance).build());
+        storage_balances.storage_deposit(None, None);
+        let mut data = LookupMap::new(b"d".to_vec());
+
+        // Bob has no available balance, and charlie isn't registered.
+        for account_id in [accounts(1), accounts(2)] {
+            let initial_storage_usage = env::storage_usage();
+            data.insert(&account_id, &vec![0u8; 100]);
+            assert!(!storage_balances.try_settle(&account_id, initial_storage_usage));
+        }
+        assert_eq!(
+            storage_balances.internal_unwrap_account(&accounts(1)).bytes_used,
+            storage_balances.account_storage_usage
+        );
+    }
+
+    #[test]
+    fn storage_deposit_clamps_to_max() {
+        let mut storage_balances = StorageBalances::new(b"s".to_vec(), None);
+        let registration = storage_balances.account_storage_usage;
+        storage_balances.max_storage_usage = Some(registration + 100);
+
+        testing_env!(context(DEPOSIT).build());
+        let balance = storage_balances.storage_deposit(None, None);
+        assert_eq!(balance.total.0, cost(registration + 100));
+        assert_eq!(balance.available.0, cost(100));
+        assert_eq!(refunds(), vec![DEPOSIT - cost(registration + 100)]);
+
+        // A deposit of an account at the maximum is refunded entirely.
+        testing_env!(context(DEPOSIT).build());
+        let balance = storage_balances.storage_deposit(None, None);
+        assert_eq!(balance.total.0, cost(registration + 100));
+        assert_eq!(refunds(), vec![DEPOSIT]);
+    }
+
+    #[test]
+    fn storage_deposit_registration_only() {
+        let mut storage_balances = StorageBalances::new(b"s".to_vec(), None);
+        let min_balance = storage_balances.storage_balance_bounds().min.0;
+
+        testing_env!(context(DEPOSIT).build());
+        let balance = storage_balances.storage_deposit(None, Some(true));
+        assert_eq!((balance.total.0, balance.available.0), (min_balance, 0));
+        assert_eq!(refunds(), vec![DEPOSIT - min_balance]);
+
+        testing_env!(context(DEPOSIT).build());
+        storage_balances.storage_deposit(None, Some(true));
+        assert_eq!(refunds(), vec![DEPOSIT]);
+        assert_eq!(storage_balances.internal_unwrap_account(&accounts(1)).deposit, min_balance);
+    }
+
+    #[test]
+    fn storage_deposit_for_other_account() {
+        let mut storage_balances = StorageBalances::new(b"s".to_vec(), None);
+        testing_env!(context(DEPOSIT).build());
+        storage_balances.storage_deposit(Some(accounts(2)), None);
+        assert!(!storage_balances.is_registered(&accounts(1)));
+        assert_eq!(storage_balances.storage_balance_of(accounts(2)).unwrap().total.0, DEPOSIT);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "The maximum storage usage must cover the registration of an account"
+    )]
+    fn max_below_registration() {
+        testing_env!(context(0).build());
+        StorageBalances::new(b"s".to_vec(), Some(1));
+    }
+
+    #[test]
+    fn storage_withdraw_available_balance() {
+        let mut storage_balances = setup(None, DEPOSIT);
+        storage_balances.charge(&accounts(1), 100);
+        let available = storage_balances.internal_unwrap_account(&accounts(1)).available();
+
+        testing_env!(context(1).build());
+        let balance = storage_balances.storage_withdraw(None);
+        assert_eq!(balance.available.0, 0);
+        assert_eq!(balance.total.0, DEPOSIT - available);
+        assert_eq!(refunds(), vec![available]);
+    }
+
+    #[test]
+    fn forced_unregister_refunds_whole_deposit() {
+        let mut storage_balances = setup(None, DEPOSIT);
+        storage_balances.charge(&accounts(1), 100);
+
+        testing_env!(context(1).build());
+        let (account_id, storage) =
+            storage_balances.internal_storage_unregister(Some(true)).unwrap();
+        assert_eq!(account_id, accounts(1));
+        assert_eq!(storage.bytes_used, storage_balances.account_storage_usage + 100);
+        assert!(!storage_balances.is_registered(&accounts(1)));
+        assert_eq!(refunds(), vec![DEPOSIT]);
+
+        testing_env!(context(1).build());
+        assert!(storage_balances.internal_storage_unregister(Some(true)).is_none());
+    }
+
+    #[test]
+    fn unregister_without_storage_in_use() {
+        let mut storage_balances = setup(None, DEPOSIT);
+        storage_balances.charge(&accounts(1), 100);
+        storage_balances.release(&accounts(1), 100);
+
+        testing_env!(context(1).build());
+        assert!(storage_balances.storage_unregister(Some(true)));
+        assert_eq!(refunds(), vec![DEPOSIT]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Force unregistration is not supported")]
+    fn storage_unregister_with_storage_in_use() {
+        let mut storage_balances = setup(None, DEPOSIT);
+        storage_balances.charge(&accounts(1), 100);
+
+        testing_env!(context(1).build());
+        storage_balances.storage_unregister(Some(true));
+    }
+}
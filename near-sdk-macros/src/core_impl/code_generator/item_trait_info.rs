@@ -10,7 +10,10 @@ impl ItemTraitInfo {
             result.extend(method.method_wrapper());
         }
         let mod_name = &self.mod_name;
+        // Attributes of the trait, e.g. lint levels, apply to the generated wrappers.
+        let attrs = &self.original.attrs;
         quote! {
+           #(#attrs)*
            pub mod #mod_name {
                 use super::*;
                 use near_sdk::{Gas, Balance, AccountId, Promise};
@@ -129,4 +132,36 @@ mod tests {
         };
         assert_eq!(actual.to_string(), expected.to_string());
     }
+
+    #[test]
+    fn trait_attributes() {
+        let mut t: ItemTrait = syn::parse2(
+            quote!{
+              #[allow(clippy::too_many_arguments)]
+              trait TestExt {
+                fn test(&self);
+              }
+            }
+        ).unwrap();
+        let info = ItemTraitInfo::new(&mut t, None).unwrap();
+        let actual = info.wrapped_module();
+
+        let expected = quote! {
+          #[allow(clippy::too_many_arguments)]
+          pub mod test_ext {
+            use super::*;
+            use near_sdk::{Gas, Balance, AccountId, Promise};
+            pub fn test(__account_id: AccountId, __balance: near_sdk::Balance, __gas: near_sdk::Gas) -> near_sdk::Promise {
+                let args = vec![];
+                near_sdk::Promise::new(__account_id).function_call(
+                    "test".to_string(),
+                    args,
+                    __balance,
+                    __gas,
+                )
+            }
+          }
+        };
+        assert_eq!(actual.to_string(), expected.to_string());
+    }
 }
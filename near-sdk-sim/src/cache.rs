@@ -1,18 +1,153 @@
+//! Disk cache of compiled contracts.
+//!
+//! The VM looks up compiled contracts by a key which it derives from the hash of the wasm code
+//! and the VM config, so the same contract is compiled only once no matter which crate or test
+//! deploys it. Entries are stored in a directory per protocol version, the least recently used
+//! ones are removed once the directory grows above the size cap.
+//!
+//! The cache is configured with [`ContractCacheConfig`], by default from the
+//! `NEAR_SDK_SIM_CACHE_DIR` and `NEAR_SDK_SIM_CACHE_MAX_SIZE` environment variables. Pointing
+//! all crates of a workspace and CI jobs to the same directory lets them share compilations.
+//!
+//! # Examples
+//! ```
+//! use near_sdk_sim::cache::{create_cache, ContractCacheConfig};
+//!
+//! create_cache().set_config(ContractCacheConfig {
+//!     dir: std::env::temp_dir().join("contract_cache"),
+//!     max_size: 256 * 1024 * 1024,
+//! });
+//! let root = near_sdk_sim::init_simulator(None);
+//! // ... deploy and call contracts
+//! println!("{:?}", create_cache().stats());
+//! ```
+
 use crate::types::CompiledContractCache;
+use near_primitives::version::PROTOCOL_VERSION;
+use once_cell::sync::Lazy;
 use std::collections::HashMap;
-use std::fs::{File, OpenOptions};
-use std::io::{Read, Write};
+use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
-/// This provides a disc cache for compiled contracts.
-/// The cached contracts are located `CARGO_MANIFEST_DIR/target/contract_cache`.
+/// Compiled contracts shared by all runtimes of the process, so simulations running in parallel
+/// threads compile every contract only once.
+static CACHE: Lazy<ContractCache> = Lazy::new(ContractCache::new);
+
+/// Used to name temporary files. Together with the process id in the name, the counter makes
+/// the names unique across threads and processes.
+static TMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Version of the layout of the cache directory.
+const CACHE_FORMAT_VERSION: u32 = 1;
+const TMP_FILE_EXTENSION: &str = "tmp";
+const DEFAULT_MAX_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// Where the compiled contracts are stored and how much space they may use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractCacheConfig {
+    /// The directory of the cache. Defaults to `NEAR_SDK_SIM_CACHE_DIR` or
+    /// `CARGO_MANIFEST_DIR/target/contract_cache` if it isn't set.
+    pub dir: PathBuf,
+    /// The maximum size in bytes of the compiled contracts of the current protocol version,
+    /// both on disk and in memory. Defaults to `NEAR_SDK_SIM_CACHE_MAX_SIZE` or 1 GiB if it
+    /// isn't set.
+    pub max_size: u64,
+}
+
+impl Default for ContractCacheConfig {
+    fn default() -> Self {
+        let dir = match std::env::var_os("NEAR_SDK_SIM_CACHE_DIR") {
+            Some(dir) => PathBuf::from(dir),
+            None => {
+                let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+                Path::new(&manifest_dir).join("target").join("contract_cache")
+            }
+        };
+        let max_size = std::env::var("NEAR_SDK_SIM_CACHE_MAX_SIZE")
+            .ok()
+            .map(|size| size.parse().expect("NEAR_SDK_SIM_CACHE_MAX_SIZE must be a number"))
+            .unwrap_or(DEFAULT_MAX_SIZE);
+        Self { dir, max_size }
+    }
+}
+
+/// Lookups of compiled contracts since the cache was created or the stats were reset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups of contracts which were compiled or loaded before in this process.
+    pub memory_hits: u64,
+    /// Lookups of contracts which were loaded from the disk.
+    pub disk_hits: u64,
+    /// Lookups of contracts which had to be compiled.
+    pub misses: u64,
+    /// Contracts removed from the disk to stay below the size cap.
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    pub fn hits(&self) -> u64 {
+        self.memory_hits + self.disk_hits
+    }
+}
+
+struct Entry {
+    value: Vec<u8>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Inner {
+    config: ContractCacheConfig,
+    entries: HashMap<Vec<u8>, Entry>,
+    memory_size: u64,
+    /// Incremented on every access, orders the entries by their last use.
+    clock: u64,
+    stats: CacheStats,
+}
+
+impl Inner {
+    fn insert(&mut self, key: &[u8], value: &[u8]) -> Option<Vec<u8>> {
+        self.clock += 1;
+        let entry = Entry { value: value.to_vec(), last_used: self.clock };
+        self.memory_size += value.len() as u64;
+        let old = self.entries.insert(key.to_vec(), entry).map(|entry| entry.value);
+        if let Some(old) = &old {
+            self.memory_size -= old.len() as u64;
+        }
+        // Keeps the entry which was just inserted even if it's larger than the cap.
+        while self.memory_size > self.config.max_size && self.entries.len() > 1 {
+            let lru_key = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+                .unwrap();
+            let entry = self.entries.remove(&lru_key).unwrap();
+            self.memory_size -= entry.value.len() as u64;
+        }
+        old
+    }
+
+    fn get(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+        self.clock += 1;
+        let clock = self.clock;
+        self.entries.get_mut(key).map(|entry| {
+            entry.last_used = clock;
+            entry.value.clone()
+        })
+    }
+}
+
+/// This provides a disk cache for compiled contracts, see the [module](self) documentation.
+/// Clones share the entries, config and stats.
 #[derive(Clone, Default)]
 pub struct ContractCache {
-    data: Arc<Mutex<HashMap<Vec<u8>, Vec<u8>>>>,
+    inner: Arc<Mutex<Inner>>,
 }
 
-pub(crate) fn key_to_b58(key: &[u8]) -> String {
+fn key_to_b58(key: &[u8]) -> String {
     near_sdk::bs58::encode(key).into_string()
 }
 
@@ -21,71 +156,220 @@ impl ContractCache {
         ContractCache::default()
     }
 
-    fn path() -> PathBuf {
-        let s = std::env::var("CARGO_MANIFEST_DIR").unwrap();
-        Path::new(&s).join("target").join("contract_cache")
+    pub fn with_config(config: ContractCacheConfig) -> Self {
+        let cache = ContractCache::default();
+        cache.set_config(config);
+        cache
     }
 
-    fn open_file(&self, key: &[u8]) -> std::io::Result<File> {
-        let path = self.get_path(key);
-        // Ensure that the parent path exists
-        let prefix = path.parent().unwrap();
-        std::fs::create_dir_all(prefix).unwrap();
-        // Ensure we can read, write, and create file if it doesn't exist
-        OpenOptions::new().read(true).write(true).create(true).open(path)
+    pub fn config(&self) -> ContractCacheConfig {
+        self.inner.lock().unwrap().config.clone()
     }
 
-    fn get_path(&self, key: &[u8]) -> PathBuf {
-        ContractCache::path().join(key_to_b58(key))
+    /// Changes the config. Entries in the previous directory are kept there.
+    pub fn set_config(&self, config: ContractCacheConfig) {
+        self.inner.lock().unwrap().config = config;
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.inner.lock().unwrap().stats
     }
 
-    fn file_exists(&self, key: &[u8]) -> bool {
-        self.get_path(key).exists()
+    pub fn reset_stats(&self) {
+        self.inner.lock().unwrap().stats = CacheStats::default();
     }
 
+    /// The directory with the entries of the current protocol version.
+    pub fn path(&self) -> PathBuf {
+        self.config().dir.join(format!("v{}-protocol-{}", CACHE_FORMAT_VERSION, PROTOCOL_VERSION))
+    }
+
+    fn get_path(&self, key: &[u8]) -> PathBuf {
+        self.path().join(key_to_b58(key))
+    }
+
+    /// Inserts a compiled contract in memory only.
     pub fn insert(&self, key: &[u8], value: &[u8]) -> Option<Vec<u8>> {
-        self.data.lock().unwrap().insert(key.to_vec(), value.to_vec())
+        self.inner.lock().unwrap().insert(key, value)
     }
 
+    /// Returns a compiled contract from memory.
     pub fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
-        self.data.lock().unwrap().get(key).cloned()
+        self.inner.lock().unwrap().get(key)
+    }
+
+    /// Removes all entries from memory and the cache directory, including the entries of other
+    /// protocol versions.
+    pub fn clear(&self) -> std::io::Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.entries.clear();
+        inner.memory_size = 0;
+        match std::fs::remove_dir_all(&inner.config.dir) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
     }
 
-    #[allow(dead_code)]
-    pub(crate) fn to_arc(&self) -> Arc<ContractCache> {
-        Arc::new(self.clone())
+    /// Marks the file as recently used for the eviction.
+    fn touch(path: &Path) {
+        // Failing to update the time only makes the eviction less accurate.
+        let _ = filetime::set_file_mtime(path, filetime::FileTime::now());
+    }
+
+    /// Removes the least recently used files until the directory fits in the size cap.
+    fn evict(&self, dir: &Path) -> std::io::Result<()> {
+        let mut files = vec![];
+        let mut size = 0;
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension() == Some(OsStr::new(TMP_FILE_EXTENSION)) {
+                continue;
+            }
+            // Files may be removed by other processes at the same time.
+            if let Ok(metadata) = entry.metadata() {
+                size += metadata.len();
+                files.push((metadata.modified()?, metadata.len(), path));
+            }
+        }
+        let max_size = self.config().max_size;
+        if size <= max_size {
+            return Ok(());
+        }
+        files.sort();
+        let mut evictions = 0;
+        // The newest file is never removed, it was just written.
+        for (_, len, path) in files.iter().take(files.len() - 1) {
+            if size <= max_size {
+                break;
+            }
+            if std::fs::remove_file(path).is_ok() {
+                evictions += 1;
+            }
+            size -= len;
+        }
+        self.inner.lock().unwrap().stats.evictions += evictions;
+        Ok(())
     }
 }
 
 impl CompiledContractCache for ContractCache {
     fn put(&self, key: &[u8], value: &[u8]) -> Result<(), std::io::Error> {
         self.insert(key, value);
-        let mut file = self.open_file(key).expect("File failed to open");
-        let metadata = file.metadata()?;
-        if metadata.len() != value.len() as u64 {
-            file.write_all(value)?;
+        let path = self.get_path(key);
+        if std::fs::metadata(&path).ok().map(|metadata| metadata.len()) == Some(value.len() as u64)
+        {
+            ContractCache::touch(&path);
+            return Ok(());
         }
-        Ok(())
+        let dir = path.parent().unwrap();
+        std::fs::create_dir_all(dir)?;
+        // Write to a temporary file and rename it, so other threads and processes never read a
+        // partially written contract.
+        let tmp_path = path.with_extension(format!(
+            "{}-{}.{}",
+            std::process::id(),
+            TMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed),
+            TMP_FILE_EXTENSION
+        ));
+        std::fs::write(&tmp_path, value)?;
+        std::fs::rename(&tmp_path, &path)?;
+        self.evict(dir)
     }
 
     fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, std::io::Error> {
-        if (*self.data).lock().unwrap().contains_key(key) {
-            return Ok(self.get(key));
-        } else if self.file_exists(key) {
-            let mut file = self.open_file(key)?;
-            let mut contents = vec![];
-            file.read_to_end(&mut contents)?;
-            self.insert(key, &contents);
-            return Ok(Some(contents));
+        if let Some(value) = self.get(key) {
+            self.inner.lock().unwrap().stats.memory_hits += 1;
+            return Ok(Some(value));
+        }
+        let path = self.get_path(key);
+        match std::fs::read(&path) {
+            Ok(contents) => {
+                ContractCache::touch(&path);
+                let mut inner = self.inner.lock().unwrap();
+                inner.stats.disk_hits += 1;
+                inner.insert(key, &contents);
+                Ok(Some(contents))
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                self.inner.lock().unwrap().stats.misses += 1;
+                Ok(None)
+            }
+            Err(err) => Err(err),
         }
-        Ok(None)
     }
 }
 
+/// Returns the cache shared by all runtimes of the process.
 pub fn create_cache() -> ContractCache {
-    ContractCache::new()
+    CACHE.clone()
 }
 
 pub fn cache_to_arc(cache: &ContractCache) -> Arc<ContractCache> {
-    cache.to_arc()
+    Arc::new(cache.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(name: &str, max_size: u64) -> ContractCache {
+        let dir = std::env::temp_dir().join("near_sdk_sim_cache_tests").join(name);
+        let cache = ContractCache::with_config(ContractCacheConfig { dir, max_size });
+        cache.clear().unwrap();
+        cache
+    }
+
+    #[test]
+    fn put_and_get() {
+        let cache = cache("put_and_get", 1024);
+        assert_eq!(CompiledContractCache::get(&cache, b"a").unwrap(), None);
+        cache.put(b"a", b"compiled a").unwrap();
+        assert_eq!(CompiledContractCache::get(&cache, b"a").unwrap(), Some(b"compiled a".to_vec()));
+
+        // A new process only finds the entry on disk.
+        let other = ContractCache::with_config(cache.config());
+        assert_eq!(CompiledContractCache::get(&other, b"a").unwrap(), Some(b"compiled a".to_vec()));
+        assert_eq!(CompiledContractCache::get(&other, b"a").unwrap(), Some(b"compiled a".to_vec()));
+
+        assert_eq!(cache.stats(), CacheStats { memory_hits: 1, misses: 1, ..Default::default() });
+        assert_eq!(
+            other.stats(),
+            CacheStats { memory_hits: 1, disk_hits: 1, ..Default::default() }
+        );
+        assert_eq!(other.stats().hits(), 2);
+        cache.reset_stats();
+        assert_eq!(cache.stats(), CacheStats::default());
+        cache.clear().unwrap();
+        assert!(!cache.path().exists());
+    }
+
+    #[test]
+    fn lru_eviction() {
+        // A cache of its own, so the global cache used by other tests is never reconfigured.
+        let cache = cache("lru_eviction", 25);
+        let value = [0; 10];
+        // The modification times are set explicitly, filesystems with a coarse resolution would
+        // give all the files the same one.
+        let set_mtime = |key: &[u8], seconds| {
+            let time = filetime::FileTime::from_unix_time(1_000_000 + seconds, 0);
+            filetime::set_file_mtime(cache.get_path(key), time).unwrap();
+        };
+        cache.put(b"a", &value).unwrap();
+        set_mtime(b"a", 0);
+        cache.put(b"b", &value).unwrap();
+        set_mtime(b"b", 10);
+        // Using `a` makes `b` the least recently used entry.
+        cache.put(b"a", &value).unwrap();
+        set_mtime(b"a", 20);
+        cache.put(b"c", &value).unwrap();
+
+        assert_eq!(cache.stats().evictions, 1);
+        assert!(cache.get_path(b"a").exists());
+        assert!(!cache.get_path(b"b").exists());
+        assert!(cache.get_path(b"c").exists());
+        assert_eq!(cache.get(b"b"), None);
+        assert!(cache.get(b"a").is_some());
+        cache.clear().unwrap();
+    }
 }
@@ -0,0 +1,286 @@
+//! Builds contract crates to wasm from simulation tests.
+//!
+//! [`compile_project`] replaces the prebuilt binaries loaded with `include_bytes!`, so tests
+//! always run against the current sources. The crate is built with the toolchain installed for
+//! it, the same way as `cargo build --target wasm32-unknown-unknown --release`. The result is
+//! stored in the `wasm` subdirectory of the [contract cache](crate::cache) under a fingerprint of
+//! the sources, so the crate is built again only after one of its local files changed.
+//!
+//! # Examples
+//! ```no_run
+//! let wasm = near_sdk_sim::compile_project("../examples/fungible-token/ft");
+//! let root = near_sdk_sim::init_simulator(None);
+//! let contract = root.deploy(&wasm, "ft".parse().unwrap(), near_sdk_sim::STORAGE_AMOUNT);
+//! ```
+
+use crate::cache::create_cache;
+use near_primitives::hash::hash;
+use near_sdk::serde_json::{self, Value};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+const WASM_TARGET: &str = "wasm32-unknown-unknown";
+const RUSTFLAGS: &str = "-C link-arg=-s";
+
+/// Contracts built by this process, by the directory of the crate. Every crate has a lock of
+/// its own, which prevents concurrent builds of the same crate while other crates are built.
+static BUILT: Lazy<Mutex<HashMap<PathBuf, Arc<Mutex<Option<Vec<u8>>>>>>> =
+    Lazy::new(Default::default);
+
+/// The crate of the contract, as reported by `cargo metadata`.
+struct Project {
+    manifest_path: PathBuf,
+    package_name: String,
+    /// File name of the compiled contract, without the extension.
+    lib_name: String,
+    target_dir: PathBuf,
+    /// Directories of the crate and all of its local dependencies.
+    source_dirs: Vec<PathBuf>,
+    lock_file: PathBuf,
+}
+
+/// Builds the contract crate at `path` for `wasm32-unknown-unknown` in release mode and returns
+/// the wasm code. A relative `path` starts at the current directory, which is the directory of
+/// the crate under test when run by `cargo test`.
+///
+/// The code is cached by a fingerprint of the files of the crate and its local dependencies,
+/// `Cargo.lock` and the version of the toolchain. Only the latest build of every crate is kept.
+///
+/// Panics if the crate can't be built, with the output of cargo.
+pub fn compile_project<P: AsRef<Path>>(path: P) -> Vec<u8> {
+    let path = path.as_ref();
+    let manifest_dir = path.canonicalize().unwrap_or_else(|err| {
+        panic!("Can't find the contract crate at {}: {}", path.display(), err)
+    });
+    let project_lock = BUILT
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .entry(manifest_dir.clone())
+        .or_default()
+        .clone();
+    let mut built = project_lock.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(wasm) = built.as_ref() {
+        return wasm.clone();
+    }
+
+    let project = Project::load(&manifest_dir);
+    let fingerprint = fingerprint(&project).unwrap_or_else(|err| {
+        panic!("Failed to read the sources of {}: {}", project.package_name, err)
+    });
+    let cache_dir = create_cache().config().dir.join("wasm");
+    let cached_path = cache_dir.join(format!("{}-{}.wasm", project.lib_name, fingerprint));
+    let wasm = match std::fs::read(&cached_path) {
+        Ok(wasm) => wasm,
+        Err(_) => {
+            let wasm = project.build();
+            store(&cache_dir, &cached_path, &project.lib_name, &wasm)
+                .unwrap_or_else(|err| panic!("Failed to write {}: {}", cached_path.display(), err));
+            wasm
+        }
+    };
+    *built = Some(wasm.clone());
+    wasm
+}
+
+/// Runs a command in `dir`, so the toolchain override of the crate applies, and returns its
+/// standard output.
+fn run(command: &mut Command, dir: &Path) -> Vec<u8> {
+    let output = command
+        .current_dir(dir)
+        .output()
+        .unwrap_or_else(|err| panic!("Failed to run {:?}: {}", command, err));
+    if !output.status.success() {
+        panic!(
+            "{:?} returned non-zero code {}:\n{}",
+            command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    output.stdout
+}
+
+impl Project {
+    fn load(manifest_dir: &Path) -> Self {
+        let manifest_path = manifest_dir.join("Cargo.toml");
+        let metadata = run(
+            Command::new("cargo")
+                .args(["metadata", "--format-version", "1", "--manifest-path"])
+                .arg(&manifest_path),
+            manifest_dir,
+        );
+        let metadata: Value = serde_json::from_slice(&metadata).unwrap();
+        let packages = metadata["packages"].as_array().unwrap();
+        let package = packages
+            .iter()
+            .find(|package| {
+                package["manifest_path"].as_str().map(Path::new) == Some(manifest_path.as_path())
+            })
+            .unwrap_or_else(|| panic!("{} is not a package", manifest_path.display()));
+        let package_name = package["name"].as_str().unwrap().to_string();
+        let lib_name = package["targets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|target| {
+                target["crate_types"].as_array().unwrap().iter().any(|kind| kind == "cdylib")
+            })
+            .and_then(|target| target["name"].as_str())
+            .unwrap_or_else(|| panic!("{} has no cdylib target", package_name))
+            .replace('-', "_");
+        // Local packages have no source, their files are part of the fingerprint.
+        let source_dirs = packages
+            .iter()
+            .filter(|package| package["source"].is_null())
+            .map(|package| {
+                Path::new(package["manifest_path"].as_str().unwrap())
+                    .parent()
+                    .unwrap()
+                    .to_path_buf()
+            })
+            .collect();
+        Self {
+            manifest_path,
+            package_name,
+            lib_name,
+            target_dir: PathBuf::from(metadata["target_directory"].as_str().unwrap()),
+            source_dirs,
+            lock_file: Path::new(metadata["workspace_root"].as_str().unwrap()).join("Cargo.lock"),
+        }
+    }
+
+    fn dir(&self) -> &Path {
+        self.manifest_path.parent().unwrap()
+    }
+
+    fn build(&self) -> Vec<u8> {
+        run(
+            Command::new("cargo")
+                .env("RUSTFLAGS", RUSTFLAGS)
+                .args(["build", "--release", "--target", WASM_TARGET, "--manifest-path"])
+                .arg(&self.manifest_path)
+                .args(["--package", &self.package_name]),
+            self.dir(),
+        );
+        let path = self
+            .target_dir
+            .join(WASM_TARGET)
+            .join("release")
+            .join(format!("{}.wasm", self.lib_name));
+        std::fs::read(&path)
+            .unwrap_or_else(|err| panic!("Failed to read {}: {}", path.display(), err))
+    }
+}
+
+/// Hashes the toolchain version, `Cargo.lock` and the paths and contents of all source files.
+fn fingerprint(project: &Project) -> io::Result<String> {
+    let mut data = run(Command::new("rustc").arg("-vV"), project.dir());
+    data.extend_from_slice(RUSTFLAGS.as_bytes());
+    let mut files = vec![project.lock_file.clone()];
+    for dir in &project.source_dirs {
+        collect_files(dir, &mut files)?;
+    }
+    files.sort();
+    files.dedup();
+    for file in files {
+        let contents = match std::fs::read(&file) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        data.extend_from_slice(file.to_string_lossy().as_bytes());
+        data.extend_from_slice(&(contents.len() as u64).to_le_bytes());
+        data.extend_from_slice(&contents);
+    }
+    Ok(hash(&data).to_string())
+}
+
+/// Collects the files in `dir` recursively, skipping build outputs and hidden files.
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') || name == "target" {
+            continue;
+        }
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Writes the wasm to the cache and removes the previous builds of the crate.
+fn store(cache_dir: &Path, path: &Path, lib_name: &str, wasm: &[u8]) -> io::Result<()> {
+    std::fs::create_dir_all(cache_dir)?;
+    let prefix = format!("{}-", lib_name);
+    for entry in std::fs::read_dir(cache_dir)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with(&prefix) {
+            // Another process may have removed it already.
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+    // Write to a temporary file and rename it, so other processes never read a partial build.
+    // The name doesn't start with the prefix, so concurrent stores don't remove it.
+    let tmp_path = cache_dir.join(format!(".{}.{}.tmp", lib_name, std::process::id()));
+    std::fs::write(&tmp_path, wasm)?;
+    std::fs::rename(&tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_changes_with_sources() {
+        let dir = std::env::temp_dir().join("near_sdk_sim_compile_tests");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::create_dir_all(dir.join("target")).unwrap();
+        std::fs::write(dir.join("Cargo.toml"), "[package]").unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "fn a() {}").unwrap();
+        let project = Project {
+            manifest_path: dir.join("Cargo.toml"),
+            package_name: "test".to_string(),
+            lib_name: "test".to_string(),
+            target_dir: dir.join("target"),
+            source_dirs: vec![dir.clone()],
+            lock_file: dir.join("Cargo.lock"),
+        };
+
+        let initial = fingerprint(&project).unwrap();
+        std::fs::write(dir.join("target/test.wasm"), "build output").unwrap();
+        assert_eq!(fingerprint(&project).unwrap(), initial);
+        std::fs::write(dir.join("src/lib.rs"), "fn b() {}").unwrap();
+        let changed = fingerprint(&project).unwrap();
+        assert_ne!(changed, initial);
+        std::fs::rename(dir.join("src/lib.rs"), dir.join("src/main.rs")).unwrap();
+        assert_ne!(fingerprint(&project).unwrap(), changed);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn store_keeps_temporary_files_of_other_processes() {
+        let dir = std::env::temp_dir().join("near_sdk_sim_compile_store_tests");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let other_tmp_path = dir.join(".test.0.tmp");
+        std::fs::write(&other_tmp_path, "partial build").unwrap();
+
+        store(&dir, &dir.join("test-a.wasm"), "test", b"a").unwrap();
+        store(&dir, &dir.join("test-b.wasm"), "test", b"b").unwrap();
+        assert!(!dir.join("test-a.wasm").exists());
+        assert_eq!(std::fs::read(dir.join("test-b.wasm")).unwrap(), b"b");
+        assert!(other_tmp_path.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
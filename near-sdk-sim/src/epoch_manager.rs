@@ -0,0 +1,126 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use near_primitives::account::Account;
+use near_primitives::test_utils::MockEpochInfoProvider;
+use near_primitives::types::{AccountId, Balance, EpochHeight};
+use near_runtime::ValidatorAccountsUpdate;
+
+use crate::runtime::GenesisConfig;
+
+/// Simulates the validator set of the chain. Stake proposals made during an epoch are applied
+/// when it ends, validators are rewarded and the stake of validators which left is returned
+/// after the lockup period.
+#[derive(Clone)]
+pub(crate) struct EpochManager {
+    epoch_height: EpochHeight,
+    reward_rate: (u128, u128),
+    /// Validator sets of the last `unstake_lockup_epochs` epochs, the current one is the last.
+    validators: VecDeque<BTreeMap<AccountId, Balance>>,
+    lockup_epochs: usize,
+    /// The last stake proposal of every account in the current epoch.
+    proposals: BTreeMap<AccountId, Balance>,
+}
+
+impl EpochManager {
+    pub fn new(genesis: &GenesisConfig) -> Self {
+        let validators =
+            genesis.validators.iter().map(|info| (info.account_id.clone(), info.amount)).collect();
+        Self {
+            epoch_height: genesis.genesis_height / genesis.epoch_length,
+            reward_rate: genesis.validator_reward_rate,
+            validators: vec![validators].into(),
+            lockup_epochs: genesis.unstake_lockup_epochs.max(1) as usize,
+            proposals: BTreeMap::new(),
+        }
+    }
+
+    pub fn epoch_height(&self) -> EpochHeight {
+        self.epoch_height
+    }
+
+    /// Validators of the current epoch with their stake.
+    pub fn validators(&self) -> &BTreeMap<AccountId, Balance> {
+        self.validators.back().unwrap()
+    }
+
+    pub fn epoch_info_provider(&self) -> MockEpochInfoProvider {
+        MockEpochInfoProvider::new(
+            self.validators().iter().map(|(account_id, stake)| (account_id.clone(), *stake)),
+        )
+    }
+
+    pub fn add_proposal(&mut self, account_id: AccountId, stake: Balance) {
+        self.proposals.insert(account_id, stake);
+    }
+
+    /// Accounts which may have stake locked by the validator sets of the lockup period.
+    pub fn staked_accounts(&self) -> Vec<AccountId> {
+        let mut accounts: Vec<_> =
+            self.validators.iter().flat_map(|validators| validators.keys().cloned()).collect();
+        accounts.sort();
+        accounts.dedup();
+        accounts
+    }
+
+    /// Starts the epoch `epoch_height` and returns the update of the validator accounts, which
+    /// has to be applied with the first block of the epoch. `accounts` are the current states of
+    /// the [`staked_accounts`](EpochManager::staked_accounts).
+    pub fn start_epoch(
+        &mut self,
+        epoch_height: EpochHeight,
+        accounts: &HashMap<AccountId, Account>,
+    ) -> ValidatorAccountsUpdate {
+        let (numerator, denominator) = self.reward_rate;
+        // Rewards can only be given to existing accounts.
+        let validator_rewards: HashMap<AccountId, Balance> = self
+            .validators()
+            .iter()
+            .filter(|(account_id, _)| accounts.contains_key(*account_id))
+            .map(|(account_id, stake)| (account_id.clone(), stake * numerator / denominator))
+            .filter(|(_, reward)| *reward > 0)
+            .collect();
+
+        let mut next_validators = self.validators().clone();
+        for (account_id, stake) in next_validators.iter_mut() {
+            *stake += validator_rewards.get(account_id).copied().unwrap_or_default();
+        }
+        for (account_id, stake) in std::mem::take(&mut self.proposals) {
+            if stake == 0 {
+                next_validators.remove(&account_id);
+            } else {
+                let reward = validator_rewards.get(&account_id).copied().unwrap_or_default();
+                next_validators.insert(account_id, stake + reward);
+            }
+        }
+        self.validators.push_back(next_validators);
+        while self.validators.len() > self.lockup_epochs {
+            self.validators.pop_front();
+        }
+        self.epoch_height = epoch_height;
+
+        // The stake stays locked while the account is a validator in any epoch of the lockup
+        // period, everything above the largest of these stakes is returned.
+        let stake_info = accounts
+            .iter()
+            .map(|(account_id, account)| {
+                let max_of_stakes = self
+                    .validators
+                    .iter()
+                    .filter_map(|validators| validators.get(account_id))
+                    .max()
+                    .copied()
+                    .unwrap_or_default();
+                let reward = validator_rewards.get(account_id).copied().unwrap_or_default();
+                (account_id.clone(), max_of_stakes.min(account.locked + reward))
+            })
+            .collect();
+
+        ValidatorAccountsUpdate {
+            stake_info,
+            validator_rewards,
+            last_proposals: HashMap::new(),
+            protocol_treasury_account_id: None,
+            slashing_info: HashMap::new(),
+        }
+    }
+}
@@ -0,0 +1,338 @@
+//! Gas and storage usage of contract methods aggregated over a simulation run.
+//!
+//! Every [`RuntimeStandalone`](crate::runtime::RuntimeStandalone) records the function calls it
+//! executes into its [`GasReport`]. Reports of several tests can be merged into one, written to a
+//! file and compared against a committed baseline to catch regressions.
+//!
+//! # Examples
+//! ```no_run
+//! use near_sdk_sim::gas_report::GasReport;
+//!
+//! let root = near_sdk_sim::init_simulator(None);
+//! // ... deploy and call contracts
+//! let report = root.borrow_runtime().gas_report().clone();
+//! println!("{}", report);
+//!
+//! let baseline = GasReport::read_json("gas_baseline.json").unwrap();
+//! let regressions = report.compare(&baseline, 5);
+//! assert!(regressions.is_empty(), "{:#?}", regressions);
+//! report.write_json("gas_report.json").unwrap();
+//! ```
+
+use near_primitives::types::{AccountId, Balance, Gas};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::serde_json;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+/// Aggregated usage of a single contract method.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MethodStats {
+    pub calls: u64,
+    /// Total gas burnt by all calls.
+    pub gas_burnt: Gas,
+    /// The most gas burnt by a single call.
+    pub max_gas_burnt: Gas,
+    /// Total tokens burnt by all calls.
+    pub tokens_burnt: Balance,
+    /// Total change of the storage usage of the contract account in bytes.
+    pub storage_usage_delta: i64,
+}
+
+impl MethodStats {
+    /// The average gas burnt by a call.
+    pub fn avg_gas_burnt(&self) -> Gas {
+        self.gas_burnt.checked_div(self.calls).unwrap_or_default()
+    }
+
+    /// The average change of the storage usage in bytes by a call.
+    pub fn avg_storage_usage_delta(&self) -> i64 {
+        self.storage_usage_delta.checked_div(self.calls as i64).unwrap_or_default()
+    }
+
+    fn merge(&mut self, other: &MethodStats) {
+        self.calls += other.calls;
+        self.gas_burnt += other.gas_burnt;
+        self.max_gas_burnt = self.max_gas_burnt.max(other.max_gas_burnt);
+        self.tokens_burnt += other.tokens_burnt;
+        self.storage_usage_delta += other.storage_usage_delta;
+    }
+}
+
+/// A row of the JSON file written by [`GasReport::write_json`].
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+struct MethodEntry {
+    contract: AccountId,
+    method: String,
+    stats: MethodStats,
+}
+
+/// Gas burnt, tokens burnt and storage usage changes per contract and method.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GasReport {
+    methods: BTreeMap<(AccountId, String), MethodStats>,
+}
+
+impl GasReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a single call of `method` on `contract`.
+    pub fn record(
+        &mut self,
+        contract: &str,
+        method: &str,
+        gas_burnt: Gas,
+        tokens_burnt: Balance,
+        storage_usage_delta: i64,
+    ) {
+        self.methods.entry((contract.to_string(), method.to_string())).or_default().merge(
+            &MethodStats {
+                calls: 1,
+                gas_burnt,
+                max_gas_burnt: gas_burnt,
+                tokens_burnt,
+                storage_usage_delta,
+            },
+        );
+    }
+
+    /// Adds all calls recorded in `other` to this report.
+    pub fn merge(&mut self, other: &GasReport) {
+        for (key, stats) in other.methods.iter() {
+            self.methods.entry(key.clone()).or_default().merge(stats);
+        }
+    }
+
+    /// Returns the stats of `method` on `contract`, if it was called.
+    pub fn get(&self, contract: &str, method: &str) -> Option<&MethodStats> {
+        self.methods.get(&(contract.to_string(), method.to_string()))
+    }
+
+    /// Iterates over `(contract, method, stats)` sorted by contract and method.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str, &MethodStats)> {
+        self.methods.iter().map(|((contract, method), stats)| (&contract[..], &method[..], stats))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.methods.is_empty()
+    }
+
+    /// Removes all recorded calls.
+    pub fn clear(&mut self) {
+        self.methods.clear();
+    }
+
+    pub fn to_json(&self) -> String {
+        let entries: Vec<_> = self
+            .methods
+            .iter()
+            .map(|((contract, method), stats)| MethodEntry {
+                contract: contract.clone(),
+                method: method.clone(),
+                stats: stats.clone(),
+            })
+            .collect();
+        serde_json::to_string_pretty(&entries).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        let entries: Vec<MethodEntry> = serde_json::from_str(json)?;
+        Ok(Self {
+            methods: entries
+                .into_iter()
+                .map(|entry| ((entry.contract, entry.method), entry.stats))
+                .collect(),
+        })
+    }
+
+    pub fn write_json<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        std::fs::write(path, self.to_json())
+    }
+
+    pub fn read_json<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::from_json(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Writes the table rendered by `Display` to a file.
+    pub fn write_table<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+
+    /// Compares the average gas burnt and storage usage change of every method with `baseline`.
+    /// Returns the methods where the average gas burnt grew by more than `tolerance_percent`
+    /// percent or the average storage usage change grew at all. Methods missing from the
+    /// baseline are not compared.
+    pub fn compare(&self, baseline: &GasReport, tolerance_percent: u64) -> Vec<GasRegression> {
+        self.methods
+            .iter()
+            .filter_map(|(key, stats)| {
+                let base = baseline.methods.get(key)?;
+                let (gas, base_gas) = (stats.avg_gas_burnt(), base.avg_gas_burnt());
+                let (storage, base_storage) =
+                    (stats.avg_storage_usage_delta(), base.avg_storage_usage_delta());
+                let gas_regressed = u128::from(gas) * 100
+                    > u128::from(base_gas) * u128::from(100 + tolerance_percent);
+                if !gas_regressed && storage <= base_storage {
+                    return None;
+                }
+                Some(GasRegression {
+                    contract: key.0.clone(),
+                    method: key.1.clone(),
+                    baseline_avg_gas_burnt: base_gas,
+                    avg_gas_burnt: gas,
+                    baseline_avg_storage_usage_delta: base_storage,
+                    avg_storage_usage_delta: storage,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Renders a table with one row per contract method.
+impl fmt::Display for GasReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tgas = |gas: Gas| format!("{:.3}", gas as f64 / 1e12);
+        let header = [
+            "contract".to_string(),
+            "method".to_string(),
+            "calls".to_string(),
+            "avg Tgas".to_string(),
+            "max Tgas".to_string(),
+            "tokens burnt".to_string(),
+            "storage bytes".to_string(),
+        ];
+        let mut rows = vec![header];
+        for ((contract, method), stats) in self.methods.iter() {
+            rows.push([
+                contract.clone(),
+                method.clone(),
+                stats.calls.to_string(),
+                tgas(stats.avg_gas_burnt()),
+                tgas(stats.max_gas_burnt),
+                stats.tokens_burnt.to_string(),
+                stats.storage_usage_delta.to_string(),
+            ]);
+        }
+        let mut widths = [0; 7];
+        for row in rows.iter() {
+            for (width, cell) in widths.iter_mut().zip(row.iter()) {
+                *width = (*width).max(cell.len());
+            }
+        }
+        for row in rows.iter() {
+            // Names are aligned to the left, numbers to the right.
+            writeln!(
+                f,
+                "{:<w0$} | {:<w1$} | {:>w2$} | {:>w3$} | {:>w4$} | {:>w5$} | {:>w6$}",
+                row[0],
+                row[1],
+                row[2],
+                row[3],
+                row[4],
+                row[5],
+                row[6],
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2],
+                w3 = widths[3],
+                w4 = widths[4],
+                w5 = widths[5],
+                w6 = widths[6],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A method which uses more gas or storage than in the baseline, returned by
+/// [`GasReport::compare`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GasRegression {
+    pub contract: AccountId,
+    pub method: String,
+    pub baseline_avg_gas_burnt: Gas,
+    pub avg_gas_burnt: Gas,
+    pub baseline_avg_storage_usage_delta: i64,
+    pub avg_storage_usage_delta: i64,
+}
+
+impl fmt::Display for GasRegression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{}: average gas burnt {} -> {}, average storage usage change {} -> {} bytes",
+            self.contract,
+            self.method,
+            self.baseline_avg_gas_burnt,
+            self.avg_gas_burnt,
+            self.baseline_avg_storage_usage_delta,
+            self.avg_storage_usage_delta
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> GasReport {
+        let mut report = GasReport::new();
+        report.record("token", "ft_transfer", 10_000_000_000_000, 1_000, 0);
+        report.record("token", "ft_transfer", 20_000_000_000_000, 2_000, 0);
+        report.record("token", "storage_deposit", 5_000_000_000_000, 500, 125);
+        report
+    }
+
+    #[test]
+    fn record_and_merge() {
+        let mut report = report();
+        let stats = report.get("token", "ft_transfer").unwrap();
+        assert_eq!(stats.calls, 2);
+        assert_eq!(stats.avg_gas_burnt(), 15_000_000_000_000);
+        assert_eq!(stats.max_gas_burnt, 20_000_000_000_000);
+        assert_eq!(stats.tokens_burnt, 3_000);
+
+        report.merge(&self::report());
+        assert_eq!(report.get("token", "ft_transfer").unwrap().calls, 4);
+        assert_eq!(report.get("token", "storage_deposit").unwrap().storage_usage_delta, 250);
+        assert_eq!(report.get("token", "ft_balance_of"), None);
+    }
+
+    #[test]
+    fn json_roundtrip() {
+        let report = report();
+        assert_eq!(GasReport::from_json(&report.to_json()).unwrap(), report);
+    }
+
+    #[test]
+    fn table() {
+        let table = report().to_string();
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("contract | method "));
+        assert!(lines[1].starts_with("token    | ft_transfer     |     2 |   15.000 |   20.000 |"));
+    }
+
+    #[test]
+    fn compare() {
+        let baseline = report();
+        assert!(report().compare(&baseline, 0).is_empty());
+
+        let mut report = GasReport::new();
+        report.record("token", "ft_transfer", 16_000_000_000_000, 1_000, 0);
+        report.record("token", "storage_deposit", 5_000_000_000_000, 500, 130);
+        report.record("token", "ft_mint", 50_000_000_000_000, 500, 0);
+        assert_eq!(report.compare(&baseline, 10).len(), 1);
+        let regressions = report.compare(&baseline, 5);
+        assert_eq!(regressions.len(), 2);
+        assert_eq!(regressions[0].method, "ft_transfer");
+        assert_eq!(regressions[0].avg_gas_burnt, 16_000_000_000_000);
+        assert_eq!(regressions[1].method, "storage_deposit");
+    }
+}
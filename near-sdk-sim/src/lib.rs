@@ -8,10 +8,16 @@
 pub mod outcome;
 #[doc(inline)]
 pub use outcome::*;
-mod cache;
+pub mod cache;
+mod compile;
+mod epoch_manager;
+pub mod gas_report;
+pub mod property;
 pub mod runtime;
 pub mod units;
 pub mod user;
+#[doc(inline)]
+pub use compile::compile_project;
 pub use near_crypto;
 #[doc(hidden)]
 pub use near_primitives::*;
@@ -1,10 +1,10 @@
 use crate::hash::CryptoHash;
 use crate::runtime::{init_runtime, RuntimeStandalone};
-use crate::transaction::{ExecutionOutcome, ExecutionStatus};
+use crate::transaction::{Action, ExecutionOutcome, ExecutionStatus};
 use core::fmt;
 use near_primitives::profile::ProfileData;
 use near_primitives::transaction::ExecutionStatus::{SuccessReceiptId, SuccessValue};
-use near_primitives::types::AccountId;
+use near_primitives::types::{AccountId, Balance};
 use near_sdk::borsh::BorshDeserialize;
 use near_sdk::serde::de::DeserializeOwned;
 use near_sdk::serde::export::Formatter;
@@ -12,11 +12,15 @@ use near_sdk::serde_json::Value;
 use near_sdk::Gas;
 use std::borrow::Borrow;
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::rc::Rc;
 
 pub type TxResult = Result<ExecutionOutcome, ExecutionOutcome>;
 
+/// Prefix of the logs with events in the NEP-297 format.
+const EVENT_JSON_PREFIX: &str = "EVENT_JSON:";
+
 /// An ExecutionResult is created by a UserAccount submitting a transaction.
 /// It wraps an ExecutionOutcome which is the same object returned from an RPC call.
 #[derive(Clone)]
@@ -87,7 +91,106 @@ impl ExecutionResult {
 
     /// Asserts that the outcome is successful
     pub fn assert_success(&self) {
-        assert!(self.is_ok(), "Outcome {:#?} was a failure", self.outcome);
+        assert!(self.is_ok(), "Outcome {:#?} was a failure\n{}", self.outcome, self.receipt_tree());
+    }
+
+    /// Returns the tree of the transaction which created this outcome and all receipts created
+    /// by it, which can be printed to see the whole cascade of calls.
+    ///
+    /// # Examples
+    /// ```
+    /// use near_sdk_sim::{init_simulator, to_yocto};
+    /// let root = init_simulator(None);
+    /// let res = root.transfer("root".parse().unwrap(), to_yocto("1"));
+    /// let tree = res.receipt_tree();
+    /// assert_eq!(tree.executor_id, "root");
+    /// println!("{}", tree);
+    /// ```
+    pub fn receipt_tree(&self) -> ReceiptTree {
+        let runtime = (*self.runtime).borrow();
+        let mut root = self.hash;
+        while let Some(parent) = runtime.parent_of_outcome(&root) {
+            root = parent;
+        }
+        match runtime.outcome(&root) {
+            Some(outcome) => ReceiptTree::new(&runtime, root, outcome),
+            None => ReceiptTree::new(&runtime, self.hash, self.outcome.clone()),
+        }
+    }
+
+    /// Returns the events logged by the transaction and all receipts created by it, in the order
+    /// of the [`receipt_tree`](ExecutionResult::receipt_tree). Every log starting with
+    /// `EVENT_JSON:` is deserialized into `T`, events which don't match `T` are skipped, so an
+    /// enum tagged by the `event` field can pick the events of a single standard.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use near_sdk::serde::Deserialize;
+    /// use near_sdk::json_types::U128;
+    ///
+    /// #[derive(Deserialize)]
+    /// #[serde(crate = "near_sdk::serde", tag = "event", content = "data", rename_all = "snake_case")]
+    /// enum FtEvent {
+    ///     FtMint(Vec<FtMintData>),
+    ///     FtTransfer(Vec<FtTransferData>),
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// #[serde(crate = "near_sdk::serde")]
+    /// struct FtMintData { owner_id: String, amount: U128 }
+    ///
+    /// #[derive(Deserialize)]
+    /// #[serde(crate = "near_sdk::serde")]
+    /// struct FtTransferData { old_owner_id: String, new_owner_id: String, amount: U128 }
+    ///
+    /// # let res = near_sdk_sim::ExecutionResult::default();
+    /// // `res` is the result of an `ft_transfer_call`.
+    /// let events: Vec<FtEvent> = res.events();
+    /// assert!(matches!(&events[..], [FtEvent::FtTransfer(_), FtEvent::FtTransfer(_)]));
+    /// ```
+    pub fn events<T: DeserializeOwned>(&self) -> Vec<T> {
+        self.event_logs()
+            .iter()
+            .filter_map(|event| near_sdk::serde_json::from_str(event).ok())
+            .collect()
+    }
+
+    /// Returns the JSON of all events logged by the transaction and all receipts created by it,
+    /// without the `EVENT_JSON:` prefix.
+    pub fn event_logs(&self) -> Vec<String> {
+        let mut events = vec![];
+        self.receipt_tree().collect_event_logs(&mut events);
+        events
+    }
+
+    /// Returns the change of the storage usage of all accounts changed by the transaction and
+    /// the receipts created by it.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # let res = near_sdk_sim::ExecutionResult::default();
+    /// // `res` is the result of a `storage_deposit` call by alice.
+    /// let report = res.storage_report();
+    /// println!("{}", report);
+    /// assert_eq!(report.storage_usage_delta("token"), 125);
+    /// assert_eq!(report.storage_cost("token"), 125 * report.storage_amount_per_byte as i128);
+    /// ```
+    pub fn storage_report(&self) -> StorageReport {
+        let mut storage_usage_deltas = BTreeMap::new();
+        self.receipt_tree().collect_storage_usage_deltas(&mut storage_usage_deltas);
+        StorageReport {
+            storage_usage_deltas,
+            storage_amount_per_byte: (*self.runtime)
+                .borrow()
+                .genesis
+                .runtime_config
+                .storage_amount_per_byte,
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn has_event<T: DeserializeOwned, F: Fn(&T) -> bool>(&self, predicate: F) -> bool {
+        self.events().iter().any(predicate)
     }
 
     /// Lookup an execution result from a hash
@@ -170,6 +273,226 @@ impl ExecutionResult {
     }
 }
 
+/// A transaction or receipt with all the receipts created by it.
+/// Created with [`ExecutionResult::receipt_tree`], the `Display` implementation renders the
+/// whole tree.
+#[derive(Debug, Clone)]
+pub struct ReceiptTree {
+    pub hash: CryptoHash,
+    /// The id of the account on which the execution happens.
+    pub executor_id: AccountId,
+    /// The method name of the first function call action.
+    pub method_name: Option<String>,
+    /// The arguments of the first function call action.
+    pub args: Vec<u8>,
+    /// The sum of the deposits attached to all actions.
+    pub deposit: Balance,
+    pub gas_burnt: Gas,
+    pub logs: Vec<String>,
+    pub status: ExecutionStatus,
+    /// The change of the storage usage in bytes of the accounts changed by this transaction or
+    /// receipt.
+    pub storage_usage_deltas: BTreeMap<AccountId, i64>,
+    pub children: Vec<ReceiptTree>,
+}
+
+impl ReceiptTree {
+    fn new(runtime: &RuntimeStandalone, hash: CryptoHash, outcome: ExecutionOutcome) -> Self {
+        let actions = runtime.actions_of_outcome(&hash).unwrap_or_default();
+        let function_call = actions.iter().find_map(|action| match action {
+            Action::FunctionCall(function_call) => Some(function_call),
+            _ => None,
+        });
+        let deposit = actions
+            .iter()
+            .map(|action| match action {
+                Action::FunctionCall(function_call) => function_call.deposit,
+                Action::Transfer(transfer) => transfer.deposit,
+                _ => 0,
+            })
+            .sum();
+        let children = outcome
+            .receipt_ids
+            .iter()
+            .filter_map(|id| runtime.outcome(id).map(|child| ReceiptTree::new(runtime, *id, child)))
+            .collect();
+        Self {
+            hash,
+            executor_id: outcome.executor_id,
+            method_name: function_call.map(|function_call| function_call.method_name.clone()),
+            args: function_call.map(|function_call| function_call.args.clone()).unwrap_or_default(),
+            deposit,
+            gas_burnt: Gas(outcome.gas_burnt),
+            logs: outcome.logs,
+            status: outcome.status,
+            storage_usage_deltas: runtime
+                .storage_usage_deltas_of_outcome(&hash)
+                .cloned()
+                .unwrap_or_default(),
+            children,
+        }
+    }
+
+    /// Returns `true` if this transaction or receipt and all its children were successful.
+    pub fn is_ok(&self) -> bool {
+        !matches!(self.status, ExecutionStatus::Failure(_))
+            && self.children.iter().all(ReceiptTree::is_ok)
+    }
+
+    fn collect_storage_usage_deltas(&self, deltas: &mut BTreeMap<AccountId, i64>) {
+        for (account_id, delta) in self.storage_usage_deltas.iter() {
+            *deltas.entry(account_id.clone()).or_default() += delta;
+        }
+        for child in self.children.iter() {
+            child.collect_storage_usage_deltas(deltas);
+        }
+    }
+
+    fn collect_event_logs(&self, events: &mut Vec<String>) {
+        events.extend(
+            self.logs
+                .iter()
+                .filter_map(|log| log.strip_prefix(EVENT_JSON_PREFIX))
+                .map(String::from),
+        );
+        for child in self.children.iter() {
+            child.collect_event_logs(events);
+        }
+    }
+
+    fn fmt_tree(&self, f: &mut Formatter<'_>, prefix: &str, child_prefix: &str) -> fmt::Result {
+        write!(f, "{}{}", prefix, self.executor_id)?;
+        if let Some(method_name) = &self.method_name {
+            match std::str::from_utf8(&self.args) {
+                Ok(args) => write!(f, ": {}({})", method_name, args)?,
+                Err(_) => write!(f, ": {}(<{} bytes>)", method_name, self.args.len())?,
+            }
+        }
+        if self.deposit > 0 {
+            write!(f, " deposit: {}", self.deposit)?;
+        }
+        write!(f, " gas burnt: {:.2} Tgas ", self.gas_burnt.0 as f64 / 1e12)?;
+        match &self.status {
+            ExecutionStatus::SuccessValue(value) if value.is_empty() => writeln!(f, "Success")?,
+            ExecutionStatus::SuccessValue(value) => match std::str::from_utf8(value) {
+                Ok(value) => writeln!(f, "Success({})", value)?,
+                Err(_) => writeln!(f, "Success(<{} bytes>)", value.len())?,
+            },
+            ExecutionStatus::SuccessReceiptId(id) => writeln!(f, "Success(receipt {})", id)?,
+            ExecutionStatus::Failure(err) => writeln!(f, "Failure({})", err)?,
+            ExecutionStatus::Unknown => writeln!(f, "Unknown")?,
+        }
+        let log_prefix = if self.children.is_empty() { "    " } else { "│   " };
+        for log in self.logs.iter() {
+            writeln!(f, "{}{}log: {}", child_prefix, log_prefix, log)?;
+        }
+        for (account_id, delta) in self.storage_usage_deltas.iter() {
+            writeln!(f, "{}{}storage: {} {:+} bytes", child_prefix, log_prefix, account_id, delta)?;
+        }
+        for (i, child) in self.children.iter().enumerate() {
+            if i + 1 == self.children.len() {
+                child.fmt_tree(
+                    f,
+                    &format!("{}└── ", child_prefix),
+                    &format!("{}    ", child_prefix),
+                )?;
+            } else {
+                child.fmt_tree(
+                    f,
+                    &format!("{}├── ", child_prefix),
+                    &format!("{}│   ", child_prefix),
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Renders one line per transaction or receipt with its logs, e.g.
+/// ```text
+/// root: simple_call({"account_id": "status", "message": "ok"}) gas burnt: 2.43 Tgas Success(receipt ...)
+/// └── caller: simple_call({"account_id": "status", "message": "ok"}) gas burnt: 7.21 Tgas Success
+///     ├── status: set_status({"message":"ok"}) gas burnt: 5.07 Tgas Success
+///     │   │   log: caller set_status with message ok
+///     │   │   storage: status +110 bytes
+///     │   └── caller gas burnt: 0.22 Tgas Success
+///     └── root gas burnt: 0.22 Tgas Success
+/// ```
+impl fmt::Display for ReceiptTree {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_tree(f, "", "")
+    }
+}
+
+/// The change of the storage usage of the accounts changed by a transaction and all receipts
+/// created by it. Created with [`ExecutionResult::storage_report`], the `Display`
+/// implementation renders one line per account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageReport {
+    /// The change of the storage usage in bytes per account.
+    pub storage_usage_deltas: BTreeMap<AccountId, i64>,
+    /// The balance locked per byte of storage usage.
+    pub storage_amount_per_byte: Balance,
+}
+
+impl StorageReport {
+    /// The change of the storage usage of the account in bytes.
+    pub fn storage_usage_delta(&self, account_id: &str) -> i64 {
+        self.storage_usage_deltas.get(account_id).copied().unwrap_or_default()
+    }
+
+    /// The balance locked for the storage added to the account, negative if storage was freed.
+    pub fn storage_cost(&self, account_id: &str) -> i128 {
+        self.storage_usage_delta(account_id) as i128 * self.storage_amount_per_byte as i128
+    }
+}
+
+impl fmt::Display for StorageReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for account_id in self.storage_usage_deltas.keys() {
+            let cost = self.storage_cost(account_id);
+            let locked = if cost < 0 { "released" } else { "locked" };
+            writeln!(
+                f,
+                "{}: {:+} bytes, {} yoctoNEAR {}",
+                account_id,
+                self.storage_usage_delta(account_id),
+                cost.abs(),
+                locked
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Asserts that the transaction or any receipt created by it logged an event matching the
+/// pattern. The type of the events is inferred from the pattern, see
+/// [`ExecutionResult::events`].
+///
+/// # Examples
+/// ```no_run
+/// # use near_sdk::serde::Deserialize;
+/// # #[derive(Deserialize)]
+/// # #[serde(crate = "near_sdk::serde", tag = "event", content = "data", rename_all = "snake_case")]
+/// # enum NftEvent { NftMint { owner_id: String }, NftBurn { owner_id: String } }
+/// use near_sdk_sim::assert_event;
+/// # let res = near_sdk_sim::ExecutionResult::default();
+/// assert_event!(res, NftEvent::NftMint { .. });
+/// assert_event!(res, NftEvent::NftBurn { owner_id } if owner_id == "alice");
+/// ```
+#[macro_export]
+macro_rules! assert_event {
+    ($res:expr, $pattern:pat $(if $guard:expr)? $(,)?) => {{
+        let res = &$res;
+        assert!(
+            res.has_event(|event| matches!(event, $pattern $(if $guard)?)),
+            "No event matching `{}` was logged, events: {:#?}",
+            stringify!($pattern $(if $guard)?),
+            res.event_logs()
+        );
+    }};
+}
+
 #[doc(hidden)]
 pub fn outcome_into_result(
     outcome: (CryptoHash, ExecutionOutcome),
@@ -256,4 +579,99 @@ mod tests {
         );
         assert_eq!(value, result.unwrap_json_value());
     }
+
+    #[test]
+    fn receipt_tree_test() {
+        let root = crate::init_simulator(None);
+        let alice = root.create_user("alice".parse().unwrap(), crate::to_yocto("10"));
+        let res = root.transfer(alice.account_id(), crate::to_yocto("1"));
+
+        let tree = res.receipt_tree();
+        assert!(tree.is_ok());
+        assert_eq!(tree.executor_id, "root");
+        assert_eq!(tree.method_name, None);
+        assert_eq!(tree.deposit, crate::to_yocto("1"));
+        assert_eq!(tree.children[0].executor_id, "alice");
+        assert_eq!(tree.children[0].deposit, crate::to_yocto("1"));
+
+        let rendered = tree.to_string();
+        assert!(rendered.starts_with("root deposit: 1000000000000000000000000 gas burnt:"));
+        assert!(rendered.contains("└── alice deposit: 1000000000000000000000000"));
+    }
+
+    #[test]
+    fn storage_report_test() {
+        let root = crate::init_simulator(None);
+        let status = root.deploy(
+            include_bytes!("../../examples/status-message/res/status_message.wasm"),
+            "status".parse().unwrap(),
+            crate::to_yocto("35"),
+        );
+        let res = root.call(
+            status.account_id(),
+            "set_status",
+            br#"{"message": "hello"}"#,
+            crate::DEFAULT_GAS,
+            0,
+        );
+        res.assert_success();
+
+        let report = res.storage_report();
+        let delta = report.storage_usage_delta("status");
+        assert!(delta > 0);
+        assert_eq!(report.storage_usage_delta("root"), 0);
+        assert_eq!(
+            report.storage_cost("status"),
+            delta as i128 * report.storage_amount_per_byte as i128
+        );
+        assert_eq!(res.receipt_tree().children[0].storage_usage_deltas.get("status"), Some(&delta));
+        assert!(report.to_string().starts_with(&format!("status: +{} bytes", delta)));
+
+        let res = root.call(
+            status.account_id(),
+            "set_status",
+            br#"{"message": "hi"}"#,
+            crate::DEFAULT_GAS,
+            0,
+        );
+        assert_eq!(res.storage_report().storage_usage_delta("status"), -3);
+        status.assert_storage_covered();
+        root.assert_storage_covered();
+        assert!(status.storage_cost() > 0);
+    }
+
+    #[test]
+    fn events_test() {
+        use near_sdk::serde::Deserialize;
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        #[serde(crate = "near_sdk::serde", tag = "event", content = "data")]
+        #[serde(rename_all = "snake_case")]
+        enum NftEvent {
+            NftMint { owner_id: String, token_ids: Vec<String> },
+            NftBurn { owner_id: String, token_ids: Vec<String> },
+        }
+
+        let logs = vec![
+            "minting".to_string(),
+            r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_mint","data":{"owner_id":"alice","token_ids":["1"]}}"#.to_string(),
+            r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_mint","data":[]}"#.to_string(),
+        ];
+        let outcome = ExecutionOutcome { logs, ..Default::default() };
+        let result = outcome_into_result(
+            (CryptoHash::default(), outcome),
+            &Rc::new(RefCell::new(init_runtime(None).0)),
+        );
+        assert_eq!(result.event_logs().len(), 2);
+        assert_eq!(
+            result.events::<NftEvent>(),
+            vec![NftEvent::NftMint {
+                owner_id: "alice".to_string(),
+                token_ids: vec!["1".to_string()]
+            }]
+        );
+        assert_event!(result, NftEvent::NftMint { .. });
+        assert_event!(result, NftEvent::NftMint { owner_id, .. } if owner_id == "alice");
+        assert!(!result.has_event(|event| matches!(event, NftEvent::NftBurn { .. })));
+    }
 }
@@ -0,0 +1,326 @@
+//! Property-based testing of contracts with random sequences of calls.
+//!
+//! A [`PropertyTest`] sets up a fresh simulation for every run, applies a random sequence of
+//! contract calls signed by random users and checks the invariants after each step. The calls are
+//! described by a user defined operation type which implements [`Arbitrary`]. When a sequence
+//! breaks an invariant, it is shrunk to a minimal sequence which still breaks it, by removing
+//! steps and shrinking the operations, and the test panics with that sequence.
+//!
+//! # Examples
+//! ```no_run
+//! use near_sdk::json_types::U128;
+//! use near_sdk::serde_json::{self, json};
+//! use near_sdk_sim::property::{Arbitrary, Gen, PropertyTest};
+//! use near_sdk_sim::{init_simulator, UserAccount, DEFAULT_GAS, STORAGE_AMOUNT};
+//!
+//! #[derive(Debug, Clone)]
+//! enum Op {
+//!     Transfer { receiver: usize, amount: u128 },
+//! }
+//!
+//! impl Arbitrary for Op {
+//!     fn arbitrary(g: &mut Gen) -> Self {
+//!         Op::Transfer { receiver: usize::arbitrary(g) % 3, amount: u128::arbitrary(g) % 1000 }
+//!     }
+//! }
+//!
+//! PropertyTest::new(
+//!     || {
+//!         let root = init_simulator(None);
+//!         let wasm = std::fs::read("res/fungible_token.wasm").unwrap();
+//!         let token = root.deploy(&wasm, "token".parse().unwrap(), STORAGE_AMOUNT);
+//!         // ... initialize the token, create and register the users `user0` to `user2`
+//!         # let users: Vec<UserAccount> = vec![];
+//!         (users, token)
+//!     },
+//!     |token, user, op| match op {
+//!         Op::Transfer { receiver, amount } => {
+//!             let args = json!({ "receiver_id": format!("user{}", receiver), "amount": U128(*amount) });
+//!             user.call(token.account_id(), "ft_transfer", args.to_string().as_bytes(), DEFAULT_GAS, 1);
+//!         }
+//!     },
+//! )
+//! .invariant("total supply equals the sum of balances", |token, users| {
+//!     let view = |method: &str, args: serde_json::Value| {
+//!         token.view(token.account_id(), method, args.to_string().as_bytes()).unwrap_json::<U128>().0
+//!     };
+//!     let balances: u128 = users
+//!         .iter()
+//!         .map(|user| view("ft_balance_of", json!({ "account_id": user.account_id() })))
+//!         .sum();
+//!     balances == view("ft_total_supply", json!({}))
+//! })
+//! .run();
+//! ```
+
+use crate::UserAccount;
+use near_sdk::AccountId;
+use std::fmt;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+pub use quickcheck::{Arbitrary, Gen};
+
+/// The size passed to the generator of the operations.
+const GEN_SIZE: usize = 100;
+
+/// An operation applied by one of the users.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step<Op> {
+    /// Index of the user in the users returned by the setup.
+    pub user: usize,
+    pub op: Op,
+}
+
+impl<Op: Arbitrary> Arbitrary for Step<Op> {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self { user: usize::arbitrary(g), op: Op::arbitrary(g) }
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        let (user, op) = (self.user, self.op.clone());
+        let users = self.user.shrink().map(move |user| Step { user, op: op.clone() });
+        let ops = self.op.shrink().map(move |op| Step { user, op });
+        Box::new(users.chain(ops))
+    }
+}
+
+/// A sequence of steps which breaks an invariant or panics.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure<Op> {
+    /// The steps up to and including the failed one.
+    pub steps: Vec<Step<Op>>,
+    /// The accounts of the users the steps refer to.
+    pub account_ids: Vec<AccountId>,
+    pub message: String,
+}
+
+impl<Op: fmt::Debug> fmt::Display for Failure<Op> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.message)?;
+        write!(f, "Steps:")?;
+        for (i, step) in self.steps.iter().enumerate() {
+            write!(f, "\n  {}. {}: {:?}", i, self.account_ids[step.user], step.op)?;
+        }
+        Ok(())
+    }
+}
+
+type Setup<S> = Box<dyn Fn() -> (Vec<UserAccount>, S)>;
+type Apply<S, Op> = Box<dyn Fn(&mut S, &UserAccount, &Op)>;
+type Invariant<S> = Box<dyn Fn(&S, &[UserAccount]) -> bool>;
+
+/// Runs random sequences of operations of type `Op` against the simulation state `S`, see the
+/// [module](self) documentation.
+pub struct PropertyTest<S, Op> {
+    setup: Setup<S>,
+    apply: Apply<S, Op>,
+    invariants: Vec<(String, Invariant<S>)>,
+    tests: usize,
+    max_steps: usize,
+    max_shrinks: usize,
+}
+
+impl<S, Op: Arbitrary + fmt::Debug> PropertyTest<S, Op> {
+    /// `setup` creates a fresh simulation with the users which sign the operations and the state
+    /// passed to `apply` and the invariants, e.g. the contract accounts and a model of the
+    /// expected contract state. `apply` submits the calls of a single operation. It may panic to
+    /// fail the test, failed transactions are not failures on their own.
+    pub fn new<F, A>(setup: F, apply: A) -> Self
+    where
+        F: Fn() -> (Vec<UserAccount>, S) + 'static,
+        A: Fn(&mut S, &UserAccount, &Op) + 'static,
+    {
+        Self {
+            setup: Box::new(setup),
+            apply: Box::new(apply),
+            invariants: vec![],
+            tests: 100,
+            max_steps: 20,
+            max_shrinks: 1000,
+        }
+    }
+
+    /// Adds an invariant, which is checked after the setup and after every step.
+    pub fn invariant<F>(mut self, name: &str, invariant: F) -> Self
+    where
+        F: Fn(&S, &[UserAccount]) -> bool + 'static,
+    {
+        self.invariants.push((name.to_string(), Box::new(invariant)));
+        self
+    }
+
+    /// Sets the number of random sequences to run, 100 by default.
+    pub fn with_tests(mut self, tests: usize) -> Self {
+        self.tests = tests;
+        self
+    }
+
+    /// Sets the maximum length of the sequences, 20 by default.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Sets the maximum number of sequences run while shrinking a failure, 1000 by default.
+    pub fn with_max_shrinks(mut self, max_shrinks: usize) -> Self {
+        self.max_shrinks = max_shrinks;
+        self
+    }
+
+    /// Runs the random sequences and panics with the shrunk sequence of the first failure.
+    pub fn run(&self) {
+        if let Err(failure) = self.check() {
+            panic!("{}", failure);
+        }
+    }
+
+    /// Runs the random sequences and returns the shrunk sequence of the first failure.
+    pub fn check(&self) -> Result<(), Failure<Op>> {
+        let mut g = Gen::new(GEN_SIZE);
+        for _ in 0..self.tests {
+            let len = usize::arbitrary(&mut g) % (self.max_steps + 1);
+            let steps: Vec<_> = (0..len).map(|_| Step::arbitrary(&mut g)).collect();
+            if let Err(failure) = self.replay(&steps) {
+                return Err(self.shrink(failure));
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs a single sequence in a fresh simulation, e.g. to reproduce a failure.
+    pub fn replay(&self, steps: &[Step<Op>]) -> Result<(), Failure<Op>> {
+        let (users, mut state) = (self.setup)();
+        assert!(!users.is_empty(), "The setup must return at least one user");
+        let mut steps = steps.to_vec();
+        for step in steps.iter_mut() {
+            step.user %= users.len();
+        }
+        let failure = |len: usize, message: String| Failure {
+            steps: steps[..len].to_vec(),
+            account_ids: users.iter().map(|user| user.account_id()).collect(),
+            message,
+        };
+
+        self.check_invariants(&state, &users).map_err(|message| failure(0, message))?;
+        for (i, step) in steps.iter().enumerate() {
+            let user = &users[step.user];
+            catch_unwind(AssertUnwindSafe(|| (self.apply)(&mut state, user, &step.op))).map_err(
+                |panic| {
+                    let message = panic
+                        .downcast_ref::<&str>()
+                        .map(|message| message.to_string())
+                        .or_else(|| panic.downcast_ref::<String>().cloned())
+                        .unwrap_or_default();
+                    failure(i + 1, format!("Step {} panicked: {}", i, message))
+                },
+            )?;
+            self.check_invariants(&state, &users)
+                .map_err(|message| failure(i + 1, format!("{} after step {}", message, i)))?;
+        }
+        Ok(())
+    }
+
+    fn check_invariants(&self, state: &S, users: &[UserAccount]) -> Result<(), String> {
+        match self.invariants.iter().find(|(_, invariant)| !invariant(state, users)) {
+            Some((name, _)) => Err(format!("Invariant `{}` violated", name)),
+            None => Ok(()),
+        }
+    }
+
+    /// Replaces the failure with smaller failing sequences until none of the shrunk sequences
+    /// fails or the shrink limit is reached.
+    fn shrink(&self, mut failure: Failure<Op>) -> Failure<Op> {
+        let mut shrinks = 0;
+        'shrink: loop {
+            for steps in failure.steps.shrink() {
+                if shrinks == self.max_shrinks {
+                    break 'shrink;
+                }
+                shrinks += 1;
+                if let Err(smaller) = self.replay(&steps) {
+                    failure = smaller;
+                    continue 'shrink;
+                }
+            }
+            break;
+        }
+        failure
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{init_simulator, to_yocto, DEFAULT_GAS};
+    use near_sdk::serde_json::json;
+    use std::collections::HashMap;
+
+    #[test]
+    fn status_matches_model() {
+        PropertyTest::new(
+            || {
+                let root = init_simulator(None);
+                let status = root.deploy(
+                    include_bytes!("../../examples/status-message/res/status_message.wasm"),
+                    "status".parse().unwrap(),
+                    to_yocto("35"),
+                );
+                let alice = root.create_user("alice".parse().unwrap(), to_yocto("100"));
+                let bob = root.create_user("bob".parse().unwrap(), to_yocto("100"));
+                (vec![alice, bob], (status, HashMap::new()))
+            },
+            |(status, model), user, op: &u8| {
+                let message = format!("status {}", op);
+                let args = json!({ "message": message }).to_string();
+                user.call(status.account_id(), "set_status", args.as_bytes(), DEFAULT_GAS, 0)
+                    .assert_success();
+                model.insert(user.account_id(), message);
+            },
+        )
+        .invariant("status of every user matches the model", |(status, model), users| {
+            users.iter().all(|user| {
+                let args = json!({ "account_id": user.account_id() }).to_string();
+                let actual: Option<String> =
+                    status.view(status.account_id(), "get_status", args.as_bytes()).unwrap_json();
+                actual.as_ref() == model.get(&user.account_id())
+            })
+        })
+        .with_tests(5)
+        .with_max_steps(5)
+        .run();
+    }
+
+    #[test]
+    fn shrinks_failures() {
+        let test = PropertyTest::new(
+            || (vec![init_simulator(None)], 0u32),
+            |sum, _, op: &u8| *sum += *op as u32,
+        )
+        .invariant("sum below 100", |sum, _| *sum < 100);
+
+        // Shrinking is deterministic, unlike the sequences generated by `check`.
+        let steps: Vec<_> = [60u8, 70, 200].iter().map(|&op| Step { user: 0, op }).collect();
+        let failure = test.shrink(test.replay(&steps).unwrap_err());
+        assert_eq!(failure.steps.iter().map(|step| step.op as u32).sum::<u32>(), 100);
+        assert!(failure.steps.iter().all(|step| step.user == 0 && step.op > 0));
+        assert!(failure.to_string().starts_with("Invariant `sum below 100` violated after step"));
+        assert_eq!(test.replay(&failure.steps), Err(failure));
+    }
+
+    #[test]
+    fn reports_panics() {
+        let test = PropertyTest::new(
+            || (vec![init_simulator(None)], ()),
+            |_, _, op: &bool| assert!(!op, "op must be false"),
+        );
+        let failure = test.check().unwrap_err();
+        assert_eq!(failure.steps, vec![Step { user: 0, op: true }]);
+        assert_eq!(
+            failure.to_string(),
+            format!(
+                "Step 0 panicked: op must be false\nSteps:\n  0. {}: true",
+                failure.account_ids[0]
+            )
+        );
+    }
+}
@@ -1,34 +1,54 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::Path;
 use std::sync::Arc;
 
 use crate::cache::{cache_to_arc, create_cache, ContractCache};
+use crate::epoch_manager::EpochManager;
+use crate::gas_report::GasReport;
 use crate::ViewResult;
 use near_crypto::{InMemorySigner, KeyType, PublicKey, Signer};
 use near_pool::{types::PoolIterator, TransactionPool};
 use near_primitives::account::{AccessKey, Account};
 use near_primitives::errors::RuntimeError;
-use near_primitives::hash::CryptoHash;
+use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::profile::ProfileData;
-use near_primitives::receipt::Receipt;
+use near_primitives::receipt::{ActionReceipt, Receipt, ReceiptEnum};
 use near_primitives::runtime::config::RuntimeConfig;
 use near_primitives::state_record::StateRecord;
 use near_primitives::test_utils::account_new;
-use near_primitives::test_utils::MockEpochInfoProvider;
-use near_primitives::transaction::{ExecutionOutcome, ExecutionStatus, SignedTransaction};
+use near_primitives::transaction::{Action, ExecutionOutcome, ExecutionStatus, SignedTransaction};
+use near_primitives::trie_key::{trie_key_parsers, TrieKey};
 use near_primitives::types::{
     AccountInfo, Balance, BlockHeight, EpochHeight, EpochId, EpochInfoProvider, Gas,
-    StateChangeCause,
+    StateChangeCause, StorageUsage,
 };
 use near_primitives::version::PROTOCOL_VERSION;
 use near_primitives::views::ViewApplyState;
-use near_runtime::{state_viewer::TrieViewer, ApplyState, Runtime};
+use near_runtime::{
+    state_viewer::TrieViewer, ApplyResult, ApplyState, Runtime, ValidatorAccountsUpdate,
+};
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::serde_json;
 use near_sdk::{AccountId, Duration};
 use near_store::{
     get_access_key, get_account, set_account, test_utils::create_test_store, ShardTries, Store,
+    StoreUpdate, TrieChanges,
 };
 
 const DEFAULT_EPOCH_LENGTH: u64 = 3;
 const DEFAULT_BLOCK_PROD_TIME: Duration = 1_000_000_000;
+const DEFAULT_UNSTAKE_LOCKUP_EPOCHS: u64 = 3;
+/// The storage key under which `#[near_bindgen]` contracts store their state struct.
+const STATE_KEY: &[u8] = b"STATE";
+/// Fills the method names of the function calls failed with [`Fault::Fail`]. The names keep
+/// their length, which the fees depend on, and can't name a method of a Rust contract.
+const INJECTED_FAILURE_METHOD_CHAR: &str = "#";
+/// Receiver of the receipts without function calls failed with [`Fault::Fail`]. It isn't a valid
+/// account ID, so no account exists or can be created for it and every action fails.
+const INJECTED_FAILURE_RECEIVER: &str = "injected failure";
+/// Gas left to the function calls of receipts failed with [`Fault::OutOfGas`].
+const OUT_OF_GAS_FAULT_GAS: Gas = 1;
 
 pub fn init_runtime(
     genesis_config: Option<GenesisConfig>,
@@ -52,6 +72,12 @@ pub struct GenesisConfig {
     pub runtime_config: RuntimeConfig,
     pub state_records: Vec<StateRecord>,
     pub validators: Vec<AccountInfo>,
+    /// Reward added to the stake of every validator at the end of each epoch, as the fraction
+    /// `(numerator, denominator)` of its stake. There are no rewards by default.
+    pub validator_reward_rate: (u128, u128),
+    /// The number of epochs after the epoch in which a validator unstaked until its stake is
+    /// returned.
+    pub unstake_lockup_epochs: u64,
 }
 
 impl Default for GenesisConfig {
@@ -72,6 +98,8 @@ impl Default for GenesisConfig {
             runtime_config,
             state_records: vec![],
             validators: vec![],
+            validator_reward_rate: (0, 1),
+            unstake_lockup_epochs: DEFAULT_UNSTAKE_LOCKUP_EPOCHS,
         }
     }
 }
@@ -92,6 +120,35 @@ impl GenesisConfig {
         });
         signer
     }
+
+    /// Appends the state records from a JSON file to `state_records`. The file is either a
+    /// nearcore genesis file or state dump, where the records are under the `records` field, or
+    /// a plain array of records.
+    ///
+    /// Accounts, access keys, contract code and data are imported the same way nearcore does.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use near_sdk_sim::runtime::GenesisConfig;
+    /// let mut genesis = GenesisConfig::default();
+    /// genesis.load_state_records("tests/fixtures/genesis.json").unwrap();
+    /// let root = near_sdk_sim::init_simulator(Some(genesis));
+    /// ```
+    pub fn load_state_records<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let file = std::fs::File::open(path)?;
+        let json: serde_json::Value = serde_json::from_reader(io::BufReader::new(file))?;
+        let records = match json {
+            serde_json::Value::Object(mut genesis) => {
+                genesis.remove("records").ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "missing `records` field")
+                })?
+            }
+            records => records,
+        };
+        let records: Vec<StateRecord> = serde_json::from_value(records)?;
+        self.state_records.extend(records);
+        Ok(())
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -123,7 +180,7 @@ impl Block {
             prev_block: None,
             state_root: CryptoHash::default(),
             block_height: genesis_config.genesis_height,
-            epoch_height: 0,
+            epoch_height: genesis_config.genesis_height / genesis_config.epoch_length,
             block_timestamp: genesis_config.genesis_time,
             gas_price: genesis_config.gas_price,
             gas_limit: genesis_config.gas_limit,
@@ -150,6 +207,57 @@ impl Block {
     }
 }
 
+/// A fault forced on the receipts matched by [`RuntimeStandalone::inject_fault`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// The receipt fails. Function calls of the receipt call a method which doesn't exist, and
+    /// receipts without function calls are sent to an account which doesn't exist. No actions are
+    /// added, so the fees, the burnt gas and the refunds are the ones of a real failure.
+    Fail,
+    /// The function calls of the receipt run out of gas. Their prepaid gas is capped to a
+    /// minimal value and the rest is refunded to the signer like unused gas. Receipts without
+    /// function calls are not affected.
+    OutOfGas,
+    /// The receipt is executed the given number of blocks later than it would have been.
+    Delay(BlockHeight),
+}
+
+/// The receipt passed to the predicate of [`RuntimeStandalone::inject_fault`].
+#[derive(Debug)]
+pub struct ReceiptInfo<'a> {
+    pub receipt: &'a Receipt,
+    pub receiver_id: &'a str,
+    /// The method name of the first function call of the receipt.
+    pub method_name: Option<&'a str>,
+    /// The number of receipts between this receipt and the transaction which started the
+    /// chain, `0` for the receipt created from the transaction.
+    pub depth: u64,
+}
+
+type FaultPredicate = Box<dyn Fn(&ReceiptInfo) -> bool>;
+
+/// Identifier of a snapshot taken with [`RuntimeStandalone::snapshot`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SnapshotId(usize);
+
+/// Everything that changes in the runtime when blocks are produced.
+struct RuntimeSnapshot {
+    cur_block: Block,
+    pending_receipts: Vec<Receipt>,
+    delayed_receipts: Vec<(BlockHeight, Receipt)>,
+    receipt_depths: HashMap<CryptoHash, u64>,
+    receipt_actions: HashMap<CryptoHash, Vec<Action>>,
+    receipt_parents: HashMap<CryptoHash, CryptoHash>,
+    pending_transactions: Vec<SignedTransaction>,
+    transactions: HashMap<CryptoHash, SignedTransaction>,
+    outcomes: HashMap<CryptoHash, ExecutionOutcome>,
+    profile: HashMap<CryptoHash, ProfileData>,
+    last_outcomes: Vec<CryptoHash>,
+    storage_usage_deltas: HashMap<CryptoHash, BTreeMap<String, i64>>,
+    gas_report: GasReport,
+    epoch_manager: EpochManager,
+}
+
 pub struct RuntimeStandalone {
     pub genesis: GenesisConfig,
     tx_pool: TransactionPool,
@@ -161,8 +269,21 @@ pub struct RuntimeStandalone {
     tries: ShardTries,
     pending_receipts: Vec<Receipt>,
     epoch_info_provider: Box<dyn EpochInfoProvider>,
+    epoch_manager: EpochManager,
     pub last_outcomes: Vec<CryptoHash>,
     cache: ContractCache,
+    snapshots: Vec<RuntimeSnapshot>,
+    faults: Vec<(FaultPredicate, Fault)>,
+    /// Receipts held back by [`Fault::Delay`] with the height of the block they are executed in.
+    delayed_receipts: Vec<(BlockHeight, Receipt)>,
+    receipt_depths: HashMap<CryptoHash, u64>,
+    /// Actions of the executed receipts, used to display the receipt tree of an outcome.
+    receipt_actions: HashMap<CryptoHash, Vec<Action>>,
+    /// Receipt ID -> ID of the transaction or receipt which created it.
+    receipt_parents: HashMap<CryptoHash, CryptoHash>,
+    /// Receipt ID -> change of the storage usage of every account changed by the receipt.
+    storage_usage_deltas: HashMap<CryptoHash, BTreeMap<String, i64>>,
+    gas_report: GasReport,
 }
 
 impl RuntimeStandalone {
@@ -181,7 +302,7 @@ impl RuntimeStandalone {
         store_update.merge(s_update);
         store_update.commit().unwrap();
         genesis_block.state_root = state_root;
-        let validators = genesis.validators.clone();
+        let epoch_manager = EpochManager::new(&genesis);
         Self {
             genesis,
             tries,
@@ -192,11 +313,18 @@ impl RuntimeStandalone {
             cur_block: genesis_block,
             tx_pool: TransactionPool::new(),
             pending_receipts: vec![],
-            epoch_info_provider: Box::new(MockEpochInfoProvider::new(
-                validators.into_iter().map(|info| (info.account_id, info.amount)),
-            )),
+            epoch_info_provider: Box::new(epoch_manager.epoch_info_provider()),
+            epoch_manager,
             cache: create_cache(),
             last_outcomes: vec![],
+            snapshots: vec![],
+            faults: vec![],
+            delayed_receipts: vec![],
+            receipt_depths: HashMap::new(),
+            receipt_actions: HashMap::new(),
+            receipt_parents: HashMap::new(),
+            storage_usage_deltas: HashMap::new(),
+            gas_report: GasReport::new(),
         }
     }
 
@@ -224,7 +352,7 @@ impl RuntimeStandalone {
                         return Ok((outcome_hash, outcome.clone()))
                     }
                 };
-            } else if self.pending_receipts.is_empty() {
+            } else if !self.has_pending_receipts() {
                 unreachable!("Lost an outcome for the receipt hash {}", outcome_hash);
             }
         }
@@ -246,11 +374,39 @@ impl RuntimeStandalone {
         self.profile.get(hash).cloned()
     }
 
+    /// Returns the actions of the transaction or receipt with the given hash.
+    pub fn actions_of_outcome(&self, hash: &CryptoHash) -> Option<&[Action]> {
+        match self.transactions.get(hash) {
+            Some(tx) => Some(&tx.transaction.actions),
+            None => self.receipt_actions.get(hash).map(Vec::as_slice),
+        }
+    }
+
+    /// Returns the hash of the transaction or receipt which created the receipt with the given
+    /// hash.
+    pub fn parent_of_outcome(&self, hash: &CryptoHash) -> Option<CryptoHash> {
+        self.receipt_parents.get(hash).copied()
+    }
+
+    /// Returns the change of the storage usage in bytes of every account changed by the receipt
+    /// with the given hash.
+    pub fn storage_usage_deltas_of_outcome(
+        &self,
+        hash: &CryptoHash,
+    ) -> Option<&BTreeMap<String, i64>> {
+        self.storage_usage_deltas.get(hash)
+    }
+
+    /// Returns the balance an account must keep to cover the given storage usage.
+    pub fn storage_cost(&self, storage_usage: StorageUsage) -> Balance {
+        storage_usage as Balance * self.genesis.runtime_config.storage_amount_per_byte
+    }
+
     /// Processes all transactions and pending receipts until there is no pending_receipts left
     pub fn process_all(&mut self) -> Result<(), RuntimeError> {
         loop {
             self.produce_block()?;
-            if self.pending_receipts.is_empty() {
+            if !self.has_pending_receipts() {
                 return Ok(());
             }
         }
@@ -279,24 +435,52 @@ impl RuntimeStandalone {
             block_hash: Default::default(),
         };
 
-        let apply_result = self.runtime.apply(
+        let validator_accounts_update = self.update_epoch();
+        let (receipts, redirected_receivers) = self.prepare_receipts();
+        let mut apply_result = self.runtime.apply(
             self.tries.get_trie_for_shard(0),
             self.cur_block.state_root,
-            &None,
+            &validator_accounts_update,
             &apply_state,
-            &self.pending_receipts,
+            &receipts,
             &Self::prepare_transactions(&mut self.tx_pool),
             self.epoch_info_provider.as_ref(),
         )?;
+        for outcome in apply_result.outcomes.iter_mut() {
+            if let Some(receiver_id) = redirected_receivers.get(&outcome.id) {
+                outcome.outcome.executor_id = receiver_id.clone();
+            }
+        }
+        for receipt in apply_result.outgoing_receipts.iter() {
+            if let ReceiptEnum::Action(ActionReceipt { actions, .. }) = &receipt.receipt {
+                self.receipt_actions.insert(receipt.receipt_id, actions.clone());
+            }
+        }
+        for proposal in apply_result.validator_proposals.iter() {
+            self.epoch_manager.add_proposal(proposal.account_id().clone(), proposal.stake());
+        }
         self.pending_receipts = apply_result.outgoing_receipts;
         apply_result.outcomes.iter().for_each(|outcome| {
+            let transaction = self.transactions.get(&outcome.id);
+            let depth = if transaction.is_some() {
+                0
+            } else {
+                self.receipt_depths.get(&outcome.id).map_or(0, |depth| depth + 1)
+            };
+            for receipt_id in outcome.outcome.receipt_ids.iter() {
+                if let Some(tx) = transaction {
+                    self.receipt_actions.insert(*receipt_id, tx.transaction.actions.clone());
+                }
+                self.receipt_depths.insert(*receipt_id, depth);
+                self.receipt_parents.insert(*receipt_id, outcome.id);
+            }
             self.last_outcomes.push(outcome.id);
             self.outcomes.insert(outcome.id, outcome.outcome.clone());
             self.profile.insert(outcome.id, profile_data.clone());
         });
-        let (update, _) =
-            self.tries.apply_all(&apply_result.trie_changes, 0).expect("Unexpected Storage error");
-        update.commit().expect("Unexpected io error");
+        self.record_storage_usage_deltas(&apply_result);
+        self.record_gas_report(&apply_result);
+        self.apply_trie_changes(&apply_result.trie_changes);
         self.cur_block = self.cur_block.produce(
             apply_result.state_root,
             self.genesis.epoch_length,
@@ -306,6 +490,217 @@ impl RuntimeStandalone {
         Ok(())
     }
 
+    /// Starts a new epoch in the epoch manager if the current block is the first block of the
+    /// epoch. Returns the update of the validator accounts to apply with the block.
+    fn update_epoch(&mut self) -> Option<ValidatorAccountsUpdate> {
+        if self.epoch_manager.epoch_height() == self.cur_block.epoch_height {
+            return None;
+        }
+        let accounts = self
+            .epoch_manager
+            .staked_accounts()
+            .into_iter()
+            .filter_map(|account_id| {
+                self.view_account(&account_id).map(|account| (account_id, account))
+            })
+            .collect();
+        let update = self.epoch_manager.start_epoch(self.cur_block.epoch_height, &accounts);
+        self.epoch_info_provider = Box::new(self.epoch_manager.epoch_info_provider());
+        Some(update)
+    }
+
+    /// Forces `fault` on every receipt for which `predicate` returns `true`, until
+    /// [`clear_faults`](RuntimeStandalone::clear_faults) is called. Used to cover the
+    /// `PromiseResult::Failed` branches of callbacks.
+    ///
+    /// Only receipts sent between blocks are matched, so a transaction calling the signer's own
+    /// account can't be failed directly.
+    ///
+    /// # Examples
+    /// ```
+    /// use near_sdk_sim::runtime::{init_runtime, Fault};
+    /// let (mut runtime, _, _) = init_runtime(None);
+    /// runtime.inject_fault(Fault::Fail, |receipt| {
+    ///     receipt.receiver_id == "token" && receipt.method_name == Some("ft_on_transfer")
+    /// });
+    /// runtime.inject_fault(Fault::Delay(5), |receipt| receipt.depth > 1);
+    /// ```
+    pub fn inject_fault<F>(&mut self, fault: Fault, predicate: F)
+    where
+        F: Fn(&ReceiptInfo) -> bool + 'static,
+    {
+        self.faults.push((Box::new(predicate), fault));
+    }
+
+    /// Removes all faults added with [`inject_fault`](RuntimeStandalone::inject_fault).
+    /// Receipts which are already delayed are still executed later.
+    pub fn clear_faults(&mut self) {
+        self.faults.clear();
+    }
+
+    fn has_pending_receipts(&self) -> bool {
+        !self.pending_receipts.is_empty() || !self.delayed_receipts.is_empty()
+    }
+
+    /// Takes the receipts to be executed in the current block, applying the injected faults.
+    /// Returns the original receivers of the receipts sent to [`INJECTED_FAILURE_RECEIVER`] too.
+    fn prepare_receipts(&mut self) -> (Vec<Receipt>, HashMap<CryptoHash, String>) {
+        let block_height = self.cur_block.block_height;
+        let (ready, delayed): (Vec<_>, Vec<_>) = std::mem::take(&mut self.delayed_receipts)
+            .into_iter()
+            .partition(|(height, _)| *height <= block_height);
+        self.delayed_receipts = delayed;
+        // Delayed receipts already had their fault applied.
+        let mut receipts: Vec<Receipt> = ready.into_iter().map(|(_, receipt)| receipt).collect();
+        let mut redirected_receivers = HashMap::new();
+        for mut receipt in std::mem::take(&mut self.pending_receipts) {
+            match self.matching_fault(&receipt) {
+                Some(Fault::Delay(blocks)) => {
+                    self.delayed_receipts.push((block_height + blocks, receipt));
+                    continue;
+                }
+                Some(fault) => {
+                    let receiver_id = receipt.receiver_id.clone();
+                    if let Some(refund) = Self::apply_fault(&mut receipt, fault) {
+                        receipts.push(refund);
+                    }
+                    if receipt.receiver_id != receiver_id {
+                        redirected_receivers.insert(receipt.receipt_id, receiver_id);
+                    }
+                }
+                None => {}
+            }
+            receipts.push(receipt);
+        }
+        (receipts, redirected_receivers)
+    }
+
+    fn matching_fault(&self, receipt: &Receipt) -> Option<Fault> {
+        if self.faults.is_empty() {
+            return None;
+        }
+        let actions = match &receipt.receipt {
+            ReceiptEnum::Action(ActionReceipt { actions, .. }) => actions,
+            ReceiptEnum::Data(_) => return None,
+        };
+        let info = ReceiptInfo {
+            receipt,
+            receiver_id: &receipt.receiver_id,
+            method_name: actions.iter().find_map(|action| match action {
+                Action::FunctionCall(function_call) => Some(function_call.method_name.as_str()),
+                _ => None,
+            }),
+            depth: self.receipt_depths.get(&receipt.receipt_id).copied().unwrap_or_default(),
+        };
+        self.faults.iter().find(|(predicate, _)| predicate(&info)).map(|(_, fault)| *fault)
+    }
+
+    /// Applies `fault` to the receipt. Returns the receipt refunding the gas removed from the
+    /// receipt, if any.
+    fn apply_fault(receipt: &mut Receipt, fault: Fault) -> Option<Receipt> {
+        let (signer_id, gas_price, actions) = match &mut receipt.receipt {
+            ReceiptEnum::Action(ActionReceipt { signer_id, gas_price, actions, .. }) => {
+                (signer_id.clone(), *gas_price, actions)
+            }
+            ReceiptEnum::Data(_) => return None,
+        };
+        let mut has_function_call = false;
+        let mut removed_gas: Gas = 0;
+        for action in actions.iter_mut() {
+            if let Action::FunctionCall(function_call) = action {
+                has_function_call = true;
+                match fault {
+                    Fault::Fail => {
+                        function_call.method_name =
+                            INJECTED_FAILURE_METHOD_CHAR.repeat(function_call.method_name.len())
+                    }
+                    Fault::OutOfGas => {
+                        removed_gas += function_call.gas.saturating_sub(OUT_OF_GAS_FAULT_GAS);
+                        function_call.gas = function_call.gas.min(OUT_OF_GAS_FAULT_GAS);
+                    }
+                    Fault::Delay(_) => {}
+                }
+            }
+        }
+        if fault == Fault::Fail && !has_function_call {
+            receipt.receiver_id = INJECTED_FAILURE_RECEIVER.to_string();
+        }
+        if removed_gas == 0 {
+            return None;
+        }
+        let mut refund = Receipt::new_refund(&signer_id, removed_gas as Balance * gas_price);
+        refund.receipt_id = hash(&[receipt.receipt_id.as_ref(), b"out_of_gas_refund"].concat());
+        Some(refund)
+    }
+
+    /// Returns the gas and storage usage of all contract methods called on this runtime.
+    pub fn gas_report(&self) -> &GasReport {
+        &self.gas_report
+    }
+
+    pub fn gas_report_mut(&mut self) -> &mut GasReport {
+        &mut self.gas_report
+    }
+
+    /// Records the function call receipts of the block into the gas report.
+    fn record_gas_report(&mut self, apply_result: &ApplyResult) {
+        for outcome in apply_result.outcomes.iter() {
+            if self.transactions.contains_key(&outcome.id) {
+                continue;
+            }
+            let method_name = self.actions_of_outcome(&outcome.id).and_then(|actions| {
+                actions.iter().find_map(|action| match action {
+                    Action::FunctionCall(function_call) => Some(function_call.method_name.clone()),
+                    _ => None,
+                })
+            });
+            if let Some(method_name) = method_name {
+                self.gas_report.record(
+                    &outcome.outcome.executor_id,
+                    &method_name,
+                    outcome.outcome.gas_burnt,
+                    outcome.outcome.tokens_burnt,
+                    self.storage_usage_deltas
+                        .get(&outcome.id)
+                        .and_then(|deltas| deltas.get(&outcome.outcome.executor_id))
+                        .copied()
+                        .unwrap_or_default(),
+                );
+            }
+        }
+    }
+
+    /// Records the change of the storage usage of the accounts per receipt of the block.
+    fn record_storage_usage_deltas(&mut self, apply_result: &ApplyResult) {
+        let trie_update = self.tries.new_trie_update(0, self.cur_block.state_root);
+        for changes in apply_result.state_changes.iter() {
+            let account_id = match &changes.trie_key {
+                TrieKey::Account { account_id } => account_id,
+                _ => continue,
+            };
+            let mut storage_usage = get_account(&trie_update, account_id)
+                .expect("Unexpected Storage error")
+                .map_or(0, |account| account.storage_usage);
+            for change in changes.changes.iter() {
+                let new_storage_usage = change.data.as_ref().map_or(0, |data| {
+                    Account::try_from_slice(data).expect("Invalid account").storage_usage
+                });
+                if let StateChangeCause::ReceiptProcessing { receipt_hash } = &change.cause {
+                    let delta = new_storage_usage as i64 - storage_usage as i64;
+                    if delta != 0 {
+                        *self
+                            .storage_usage_deltas
+                            .entry(*receipt_hash)
+                            .or_default()
+                            .entry(account_id.clone())
+                            .or_default() += delta;
+                    }
+                }
+                storage_usage = new_storage_usage;
+            }
+        }
+    }
+
     /// Produce num_of_blocks blocks.
     /// # Examples
     ///
@@ -324,15 +719,214 @@ impl RuntimeStandalone {
         Ok(())
     }
 
+    /// Produces blocks until the current block has the height `height`. The epoch height is
+    /// updated as usual. Panics if `height` is below the current height.
+    ///
+    /// # Examples
+    /// ```
+    /// use near_sdk_sim::runtime::init_runtime;
+    /// let (mut runtime, _, _) = init_runtime(None);
+    /// runtime.advance_to_height(10).unwrap();
+    /// assert_eq!(runtime.current_block().block_height, 10);
+    /// assert_eq!(runtime.current_block().epoch_height, 3);
+    /// ```
+    pub fn advance_to_height(&mut self, height: BlockHeight) -> Result<(), RuntimeError> {
+        let block_height = self.cur_block.block_height;
+        assert!(height >= block_height, "Can't go back from height {} to {}", block_height, height);
+        self.produce_blocks(height - block_height)
+    }
+
+    /// Sets the timestamp in nanoseconds of the current block, which is returned by
+    /// `env::block_timestamp()` in the transactions and receipts it applies and in view calls.
+    /// The following blocks are `block_prod_time` apart from it. Block heights are not changed.
+    /// Panics if `timestamp` is before the current timestamp.
+    ///
+    /// # Examples
+    /// ```
+    /// use near_sdk_sim::runtime::init_runtime;
+    /// let (mut runtime, _, _) = init_runtime(None);
+    /// runtime.set_block_timestamp(1_600_000_000_000_000_000);
+    /// runtime.produce_block().unwrap();
+    /// assert_eq!(runtime.current_block().block_timestamp, 1_600_000_001_000_000_000);
+    /// ```
+    pub fn set_block_timestamp(&mut self, timestamp: u64) {
+        let block_timestamp = self.cur_block.block_timestamp;
+        assert!(
+            timestamp >= block_timestamp,
+            "Can't go back from timestamp {} to {}",
+            block_timestamp,
+            timestamp
+        );
+        self.cur_block.block_timestamp = timestamp;
+    }
+
+    /// Moves the timestamp of the current block `duration` nanoseconds forward, see
+    /// [`set_block_timestamp`](RuntimeStandalone::set_block_timestamp).
+    pub fn advance_time(&mut self, duration: Duration) {
+        self.set_block_timestamp(self.cur_block.block_timestamp + duration);
+    }
+
     /// Force alter account and change state_root.
     pub fn force_account_update(&mut self, account_id: AccountId, account: &Account) {
         let mut trie_update = self.tries.new_trie_update(0, self.cur_block.state_root);
         set_account(&mut trie_update, String::from(account_id), account);
         trie_update.commit(StateChangeCause::ValidatorAccountsUpdate);
         let (trie_changes, _) = trie_update.finalize().expect("Unexpected Storage error");
-        let (store_update, new_root) = self.tries.apply_all(&trie_changes, 0).unwrap();
-        store_update.commit().expect("No io errors expected");
-        self.cur_block.state_root = new_root;
+        self.cur_block.state_root = self.apply_trie_changes(&trie_changes);
+    }
+
+    /// Force write `value` under `key` in the storage of the contract and change state_root.
+    /// The `storage_usage` of the account is not updated.
+    pub fn patch_state(&mut self, account_id: AccountId, key: &[u8], value: &[u8]) {
+        let mut trie_update = self.tries.new_trie_update(0, self.cur_block.state_root);
+        trie_update.set(
+            TrieKey::ContractData { account_id: String::from(account_id), key: key.to_vec() },
+            value.to_vec(),
+        );
+        trie_update.commit(StateChangeCause::ValidatorAccountsUpdate);
+        let (trie_changes, _) = trie_update.finalize().expect("Unexpected Storage error");
+        self.cur_block.state_root = self.apply_trie_changes(&trie_changes);
+    }
+
+    /// Force write the Borsh serialized `state` as the root state of the contract, which is the
+    /// struct marked with `#[near_bindgen]`.
+    pub fn patch_contract_state<T: BorshSerialize>(&mut self, account_id: AccountId, state: &T) {
+        let value = state.try_to_vec().expect("Failed to serialize the contract state");
+        self.patch_state(account_id, STATE_KEY, &value);
+    }
+
+    /// Returns all key-value pairs in the storage of the contract with keys starting with
+    /// `prefix`, sorted by key.
+    ///
+    /// # Examples
+    /// ```
+    /// use near_sdk_sim::runtime::init_runtime;
+    /// let (mut runtime, _, root) = init_runtime(None);
+    /// runtime.patch_state(root.clone(), b"key", b"value");
+    /// assert_eq!(runtime.view_state(root.as_str(), b"k"), vec![(b"key".to_vec(), b"value".to_vec())]);
+    /// ```
+    pub fn view_state(&self, account_id: &str, prefix: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let account_id = account_id.to_string();
+        let trie_update = self.tries.new_trie_update(0, self.cur_block.state_root);
+        let query = trie_key_parsers::get_raw_prefix_for_contract_data(&account_id, prefix);
+        trie_update
+            .iter(&query)
+            .expect("Unexpected Storage error")
+            .map(|raw_key| {
+                let raw_key = raw_key.expect("Unexpected Storage error");
+                let key =
+                    trie_key_parsers::parse_data_key_from_contract_data_key(&raw_key, &account_id)
+                        .expect("Unexpected contract data key")
+                        .to_vec();
+                let value = trie_update
+                    .get(&TrieKey::ContractData {
+                        account_id: account_id.clone(),
+                        key: key.clone(),
+                    })
+                    .expect("Unexpected Storage error")
+                    .expect("Value for an iterated key must exist");
+                (key, value)
+            })
+            .collect()
+    }
+
+    /// Reads the root state of the contract, which is the struct marked with `#[near_bindgen]`.
+    /// Returns `None` if the contract has no state.
+    pub fn view_contract_state<T: BorshDeserialize>(&self, account_id: &str) -> Option<T> {
+        let trie_update = self.tries.new_trie_update(0, self.cur_block.state_root);
+        trie_update
+            .get(&TrieKey::ContractData {
+                account_id: account_id.to_string(),
+                key: STATE_KEY.to_vec(),
+            })
+            .expect("Unexpected Storage error")
+            .map(|value| {
+                T::try_from_slice(&value).expect("Failed to deserialize the contract state")
+            })
+    }
+
+    /// Commits the changes to the store and returns the new state root.
+    fn apply_trie_changes(&self, trie_changes: &TrieChanges) -> CryptoHash {
+        if self.snapshots.is_empty() {
+            let (store_update, new_root) =
+                self.tries.apply_all(trie_changes, 0).expect("Unexpected Storage error");
+            store_update.commit().expect("Unexpected io error");
+            new_root
+        } else {
+            // Deletions are skipped so the trie nodes of the snapshotted state roots are kept.
+            let mut store_update = StoreUpdate::new_with_tries(self.tries.clone());
+            self.tries
+                .apply_insertions(trie_changes, 0, &mut store_update)
+                .expect("Unexpected Storage error");
+            store_update.commit().expect("Unexpected io error");
+            trie_changes.new_root
+        }
+    }
+
+    /// Saves the current state of the runtime, which can be brought back with
+    /// [`restore`](RuntimeStandalone::restore) any number of times. This captures the state
+    /// root, the current block, pending receipts and transactions and all outcomes, along with
+    /// their receipt tree and the gas report.
+    ///
+    /// Once a snapshot is taken, the trie nodes of older states are no longer removed from the
+    /// store, so the memory used by the runtime grows with every block.
+    ///
+    /// # Examples
+    /// ```
+    /// use near_sdk_sim::runtime::init_runtime;
+    /// let (mut runtime, _, _) = init_runtime(None);
+    /// let snapshot = runtime.snapshot();
+    /// runtime.produce_blocks(5).unwrap();
+    /// runtime.restore(snapshot);
+    /// assert_eq!(runtime.current_block().block_height, 0);
+    /// ```
+    pub fn snapshot(&mut self) -> SnapshotId {
+        let pending_transactions = Self::drain_transactions(&mut self.tx_pool);
+        for tx in pending_transactions.iter() {
+            self.tx_pool.insert_transaction(tx.clone());
+        }
+        self.snapshots.push(RuntimeSnapshot {
+            cur_block: self.cur_block.clone(),
+            pending_receipts: self.pending_receipts.clone(),
+            delayed_receipts: self.delayed_receipts.clone(),
+            receipt_depths: self.receipt_depths.clone(),
+            receipt_actions: self.receipt_actions.clone(),
+            receipt_parents: self.receipt_parents.clone(),
+            pending_transactions,
+            transactions: self.transactions.clone(),
+            outcomes: self.outcomes.clone(),
+            profile: self.profile.clone(),
+            last_outcomes: self.last_outcomes.clone(),
+            storage_usage_deltas: self.storage_usage_deltas.clone(),
+            gas_report: self.gas_report.clone(),
+            epoch_manager: self.epoch_manager.clone(),
+        });
+        SnapshotId(self.snapshots.len() - 1)
+    }
+
+    /// Brings the runtime back to the state it was in when the snapshot was taken.
+    /// The snapshot is kept, so the same state can be restored again later.
+    pub fn restore(&mut self, id: SnapshotId) {
+        let snapshot =
+            self.snapshots.get(id.0).unwrap_or_else(|| panic!("Snapshot {:?} does not exist", id));
+        self.cur_block = snapshot.cur_block.clone();
+        self.pending_receipts = snapshot.pending_receipts.clone();
+        self.delayed_receipts = snapshot.delayed_receipts.clone();
+        self.receipt_depths = snapshot.receipt_depths.clone();
+        self.receipt_actions = snapshot.receipt_actions.clone();
+        self.receipt_parents = snapshot.receipt_parents.clone();
+        self.transactions = snapshot.transactions.clone();
+        self.outcomes = snapshot.outcomes.clone();
+        self.profile = snapshot.profile.clone();
+        self.last_outcomes = snapshot.last_outcomes.clone();
+        self.storage_usage_deltas = snapshot.storage_usage_deltas.clone();
+        self.gas_report = snapshot.gas_report.clone();
+        self.epoch_manager = snapshot.epoch_manager.clone();
+        self.epoch_info_provider = Box::new(self.epoch_manager.epoch_info_provider());
+        self.tx_pool = TransactionPool::new();
+        for tx in snapshot.pending_transactions.iter() {
+            self.tx_pool.insert_transaction(tx.clone());
+        }
     }
 
     pub fn view_account(&self, account_id: &str) -> Option<Account> {
@@ -389,10 +983,29 @@ impl RuntimeStandalone {
         &self.cur_block
     }
 
+    /// Returns the validators of the current epoch with their stake.
+    ///
+    /// Stake proposed with a `Stake` action becomes effective when the next epoch starts. A
+    /// validator which unstakes gets its stake back `unstake_lockup_epochs` epochs later.
+    pub fn current_validators(&self) -> &BTreeMap<String, Balance> {
+        self.epoch_manager.validators()
+    }
+
     pub fn pending_receipts(&self) -> &[Receipt] {
         &self.pending_receipts
     }
 
+    fn drain_transactions(tx_pool: &mut TransactionPool) -> Vec<SignedTransaction> {
+        let mut res = vec![];
+        let mut pool_iter = tx_pool.pool_iterator();
+        while let Some(iter) = pool_iter.next() {
+            while let Some(tx) = iter.next() {
+                res.push(tx);
+            }
+        }
+        res
+    }
+
     fn prepare_transactions(tx_pool: &mut TransactionPool) -> Vec<SignedTransaction> {
         let mut res = vec![];
         let mut pool_iter = tx_pool.pool_iterator();
@@ -409,6 +1022,7 @@ impl RuntimeStandalone {
 mod tests {
     use super::*;
     use crate::to_yocto;
+    use near_sdk::borsh;
 
     struct Foo {}
 
@@ -531,6 +1145,243 @@ mod tests {
         assert_eq!("\"caller status is ok!\"", caller_status);
     }
 
+    #[test]
+    fn test_gas_report() {
+        let (mut runtime, signer, _) = init_runtime(None);
+        let deploy = |nonce, account_id: &str, code: &[u8]| {
+            SignedTransaction::create_contract(
+                nonce,
+                signer.account_id.clone(),
+                account_id.into(),
+                code.into(),
+                to_yocto("35"),
+                signer.public_key(),
+                &signer,
+                CryptoHash::default(),
+            )
+        };
+        runtime
+            .resolve_tx(deploy(
+                1,
+                "status",
+                include_bytes!("../../examples/status-message/res/status_message.wasm"),
+            ))
+            .unwrap();
+        runtime
+            .resolve_tx(deploy(
+                2,
+                "caller",
+                include_bytes!(
+                    "../../examples/cross-contract-high-level/res/cross_contract_high_level.wasm"
+                ),
+            ))
+            .unwrap();
+        let status_storage_usage = runtime.view_account("status").unwrap().storage_usage;
+        let snapshot = runtime.snapshot();
+        runtime
+            .resolve_tx(SignedTransaction::call(
+                3,
+                signer.account_id.clone(),
+                "caller".into(),
+                &signer,
+                0,
+                "simple_call".into(),
+                b"{\"account_id\": \"status\", \"message\": \"hello\"}".to_vec(),
+                300_000_000_000_000,
+                CryptoHash::default(),
+            ))
+            .unwrap();
+        runtime.process_all().unwrap();
+
+        let report = runtime.gas_report();
+        let set_status = report.get("status", "set_status").unwrap();
+        assert_eq!(set_status.calls, 1);
+        assert!(set_status.gas_burnt > 0);
+        // Only the storage of the contract executing the method is counted.
+        let status_storage_delta =
+            runtime.view_account("status").unwrap().storage_usage - status_storage_usage;
+        assert!(set_status.storage_usage_delta > 0);
+        assert_eq!(set_status.storage_usage_delta, status_storage_delta as i64);
+        let simple_call = report.get("caller", "simple_call").unwrap();
+        assert_eq!(simple_call.calls, 1);
+        assert_eq!(simple_call.storage_usage_delta, 0);
+
+        // The calls made after the snapshot are removed from the report.
+        runtime.restore(snapshot);
+        assert!(runtime.gas_report().get("status", "set_status").is_none());
+        assert!(runtime.gas_report().get("caller", "simple_call").is_none());
+    }
+
+    #[test]
+    fn test_inject_fault() {
+        let (mut runtime, signer, _) = init_runtime(None);
+        let deploy = |nonce, account_id: &str, code: &[u8]| {
+            SignedTransaction::create_contract(
+                nonce,
+                signer.account_id.clone(),
+                account_id.into(),
+                code.into(),
+                to_yocto("35"),
+                signer.public_key(),
+                &signer,
+                CryptoHash::default(),
+            )
+        };
+        let simple_call = |nonce, message: &str| {
+            SignedTransaction::call(
+                nonce,
+                signer.account_id.clone(),
+                "caller".into(),
+                &signer,
+                0,
+                "simple_call".into(),
+                format!("{{\"account_id\": \"status\", \"message\": \"{}\"}}", message)
+                    .into_bytes(),
+                300_000_000_000_000,
+                CryptoHash::default(),
+            )
+        };
+        let status = |runtime: &RuntimeStandalone| {
+            let res =
+                runtime.view_method_call("status", "get_status", b"{\"account_id\": \"caller\"}");
+            String::from_utf8(res.unwrap()).unwrap()
+        };
+        runtime
+            .resolve_tx(deploy(
+                1,
+                "status",
+                include_bytes!("../../examples/status-message/res/status_message.wasm"),
+            ))
+            .unwrap();
+        runtime
+            .resolve_tx(deploy(
+                2,
+                "caller",
+                include_bytes!(
+                    "../../examples/cross-contract-high-level/res/cross_contract_high_level.wasm"
+                ),
+            ))
+            .unwrap();
+
+        runtime.inject_fault(Fault::Fail, |receipt| {
+            receipt.receiver_id == "status"
+                && receipt.method_name == Some("set_status")
+                && receipt.depth == 1
+        });
+        runtime.resolve_tx(simple_call(3, "failed")).unwrap();
+        runtime.process_all().unwrap();
+        assert_eq!(status(&runtime), "null");
+        assert!(runtime.last_outcomes.iter().any(|id| matches!(
+            runtime.outcome(id),
+            Some(ExecutionOutcome { status: ExecutionStatus::Failure(_), .. })
+        )));
+
+        runtime.clear_faults();
+        runtime.inject_fault(Fault::Delay(5), |receipt| receipt.receiver_id == "status");
+        runtime.resolve_tx(simple_call(4, "delayed")).unwrap();
+        let height = runtime.current_block().block_height;
+        assert_eq!(status(&runtime), "null");
+        runtime.process_all().unwrap();
+        assert!(runtime.current_block().block_height >= height + 5);
+        assert_eq!(status(&runtime), "\"delayed\"");
+    }
+
+    #[test]
+    fn test_inject_out_of_gas_fault_refunds_gas() {
+        let (mut runtime, signer, _) = init_runtime(None);
+        runtime
+            .resolve_tx(SignedTransaction::create_contract(
+                1,
+                signer.account_id.clone(),
+                "status".into(),
+                include_bytes!("../../examples/status-message/res/status_message.wasm").to_vec(),
+                to_yocto("35"),
+                signer.public_key(),
+                &signer,
+                CryptoHash::default(),
+            ))
+            .unwrap();
+        runtime.inject_fault(Fault::OutOfGas, |receipt| receipt.receiver_id == "status");
+
+        let prepaid_gas: Gas = 300_000_000_000_000;
+        let balance_before = runtime.view_account(&signer.account_id).unwrap().amount;
+        let (_, outcome) = runtime
+            .resolve_tx(SignedTransaction::call(
+                2,
+                signer.account_id.clone(),
+                "status".into(),
+                &signer,
+                0,
+                "set_status".into(),
+                b"{\"message\": \"hello\"}".to_vec(),
+                prepaid_gas,
+                CryptoHash::default(),
+            ))
+            .unwrap();
+        runtime.process_all().unwrap();
+        assert!(matches!(outcome.status, ExecutionStatus::Failure(_)));
+        assert_eq!(
+            runtime
+                .view_method_call("status", "get_status", b"{\"account_id\": \"root\"}")
+                .unwrap(),
+            b"null".to_vec()
+        );
+
+        // Only the gas burnt by the transaction and the receipt is paid, the removed prepaid gas
+        // is refunded.
+        let balance_after = runtime.view_account(&signer.account_id).unwrap().amount;
+        let gas_price = runtime.current_block().gas_price;
+        let cost = balance_before - balance_after;
+        assert!(cost > 0);
+        assert!(cost < (prepaid_gas as Balance) * gas_price / 10);
+    }
+
+    #[test]
+    fn test_inject_fail_fault_costs_like_real_failure() {
+        let (mut runtime, signer, _) = init_runtime(None);
+        runtime
+            .resolve_tx(SignedTransaction::create_contract(
+                1,
+                signer.account_id.clone(),
+                "status".into(),
+                include_bytes!("../../examples/status-message/res/status_message.wasm").to_vec(),
+                to_yocto("35"),
+                signer.public_key(),
+                &signer,
+                CryptoHash::default(),
+            ))
+            .unwrap();
+        let transfer = |runtime: &mut RuntimeStandalone, nonce, receiver_id: &str| {
+            let balance_before = runtime.view_account(&signer.account_id).unwrap().amount;
+            let (_, outcome) = runtime
+                .resolve_tx(SignedTransaction::send_money(
+                    nonce,
+                    signer.account_id.clone(),
+                    receiver_id.into(),
+                    &signer,
+                    to_yocto("1"),
+                    CryptoHash::default(),
+                ))
+                .unwrap();
+            runtime.process_all().unwrap();
+            let balance_after = runtime.view_account(&signer.account_id).unwrap().amount;
+            (outcome, balance_before - balance_after)
+        };
+
+        // A transfer to an account which doesn't exist fails for real.
+        let (outcome, real_cost) = transfer(&mut runtime, 2, "missing");
+        assert!(matches!(outcome.status, ExecutionStatus::Failure(_)));
+
+        runtime.inject_fault(Fault::Fail, |receipt| receipt.receiver_id == "status");
+        let status_balance = runtime.view_account("status").unwrap().amount;
+        let (outcome, injected_cost) = transfer(&mut runtime, 3, "status");
+        assert!(matches!(outcome.status, ExecutionStatus::Failure(_)));
+        assert_eq!(outcome.executor_id, "status");
+        assert_eq!(runtime.view_account("status").unwrap().amount, status_balance);
+        // The deposit is refunded and only the fees of the transfer are paid.
+        assert_eq!(injected_cost, real_cost);
+    }
+
     #[test]
     fn test_force_update_account() {
         let (mut runtime, _, _) = init_runtime(None);
@@ -540,6 +1391,301 @@ mod tests {
         assert_eq!(runtime.view_account("root").unwrap().locked, 10000);
     }
 
+    #[test]
+    fn test_snapshot_restore() {
+        let (mut runtime, signer, _) = init_runtime(None);
+        let create_alice = |nonce| {
+            SignedTransaction::create_account(
+                nonce,
+                signer.account_id.clone(),
+                "alice".into(),
+                to_yocto("1"),
+                signer.public_key(),
+                &signer,
+                CryptoHash::default(),
+            )
+        };
+        let snapshot = runtime.snapshot();
+        runtime.resolve_tx(create_alice(1)).unwrap();
+        runtime.process_all().unwrap();
+        assert!(runtime.view_account("alice").is_some());
+        let with_alice = runtime.snapshot();
+
+        runtime.restore(snapshot);
+        assert_eq!(runtime.current_block().block_height, 0);
+        assert_eq!(runtime.view_account("alice"), None);
+        // The nonce of the access key is restored as well.
+        runtime.resolve_tx(create_alice(1)).unwrap();
+        runtime.process_all().unwrap();
+        assert!(runtime.view_account("alice").is_some());
+
+        runtime.restore(with_alice);
+        runtime.force_account_update(
+            "alice".parse().unwrap(),
+            &Account { amount: 0, locked: 0, code_hash: CryptoHash::default(), storage_usage: 182 },
+        );
+        runtime.restore(with_alice);
+        assert_eq!(runtime.view_account("alice").unwrap().amount, to_yocto("1"));
+    }
+
+    #[test]
+    fn test_restore_discards_receipt_tree() {
+        let (mut runtime, signer, _) = init_runtime(None);
+        let snapshot = runtime.snapshot();
+        let (receipt_id, _) = runtime
+            .resolve_tx(SignedTransaction::create_account(
+                1,
+                signer.account_id.clone(),
+                "alice".into(),
+                to_yocto("1"),
+                signer.public_key(),
+                &signer,
+                CryptoHash::default(),
+            ))
+            .unwrap();
+        runtime.process_all().unwrap();
+        assert!(runtime.parent_of_outcome(&receipt_id).is_some());
+        assert!(runtime.actions_of_outcome(&receipt_id).is_some());
+
+        runtime.restore(snapshot);
+        assert_eq!(runtime.parent_of_outcome(&receipt_id), None);
+        assert_eq!(runtime.actions_of_outcome(&receipt_id), None);
+    }
+
+    #[test]
+    fn test_patch_and_view_state() {
+        #[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+        struct State {
+            owner: String,
+            counter: u64,
+        }
+
+        let (mut runtime, _, root) = init_runtime(None);
+        assert!(runtime.view_state("root", b"").is_empty());
+        assert_eq!(runtime.view_contract_state::<State>("root"), None);
+
+        runtime.patch_state(root.clone(), b"b:1", b"one");
+        runtime.patch_state(root.clone(), b"a:2", b"two");
+        runtime.patch_state(root.clone(), b"a:1", b"three");
+        assert_eq!(
+            runtime.view_state("root", b"a:"),
+            vec![(b"a:1".to_vec(), b"three".to_vec()), (b"a:2".to_vec(), b"two".to_vec())]
+        );
+        assert_eq!(runtime.view_state("root", b"").len(), 3);
+
+        let state = State { owner: "root".to_string(), counter: 7 };
+        runtime.patch_contract_state(root, &state);
+        assert_eq!(runtime.view_contract_state::<State>("root"), Some(state));
+        assert_eq!(runtime.view_state("root", b"").len(), 4);
+    }
+
+    #[test]
+    fn test_load_state_records() {
+        let signer = InMemorySigner::from_seed("alice", KeyType::ED25519, "alice");
+        let records = vec![
+            StateRecord::Account {
+                account_id: "alice".to_string(),
+                account: account_new(to_yocto("10"), CryptoHash::default()),
+            },
+            StateRecord::AccessKey {
+                account_id: "alice".to_string(),
+                public_key: signer.public_key(),
+                access_key: AccessKey::full_access(),
+            },
+            StateRecord::Data {
+                account_id: "alice".to_string(),
+                data_key: b"STATE".to_vec(),
+                value: b"value".to_vec(),
+            },
+        ];
+        let path = std::env::temp_dir().join("near_sdk_sim_test_load_state_records.json");
+        let genesis = serde_json::json!({ "protocol_version": 45, "records": records });
+        std::fs::write(&path, serde_json::to_vec(&genesis).unwrap()).unwrap();
+
+        let mut genesis = GenesisConfig::default();
+        genesis.load_state_records(&path).unwrap();
+        assert_eq!(genesis.state_records.len(), 3);
+        std::fs::write(&path, serde_json::to_vec(&records).unwrap()).unwrap();
+        genesis.load_state_records(&path).unwrap();
+        assert_eq!(genesis.state_records.len(), 6);
+        std::fs::remove_file(&path).unwrap();
+
+        let (runtime, _, _) = init_runtime(Some(genesis));
+        assert_eq!(runtime.view_account("alice").unwrap().amount, to_yocto("10"));
+        assert!(runtime.view_access_key("alice", &signer.public_key()).is_some());
+        assert_eq!(runtime.view_state("alice", b""), vec![(b"STATE".to_vec(), b"value".to_vec())]);
+    }
+
+    #[test]
+    fn test_time_travel() {
+        let mut genesis = GenesisConfig::default();
+        genesis.genesis_height = 7;
+        genesis.genesis_time = 1_000;
+        let (mut runtime, signer, _) = init_runtime(Some(genesis));
+        assert_eq!(runtime.current_block().epoch_height, 2);
+
+        runtime.advance_time(5_000);
+        assert_eq!(runtime.current_block().block_timestamp, 6_000);
+        assert_eq!(runtime.current_block().block_height, 7);
+        runtime.produce_block().unwrap();
+        assert_eq!(runtime.current_block().block_timestamp, 6_000 + DEFAULT_BLOCK_PROD_TIME);
+        assert_eq!(runtime.current_block().epoch_height, 2);
+
+        runtime.set_block_timestamp(DEFAULT_BLOCK_PROD_TIME * 100);
+        runtime.advance_to_height(12).unwrap();
+        let block = runtime.current_block();
+        assert_eq!(block.block_height, 12);
+        assert_eq!(block.epoch_height, 4);
+        assert_eq!(block.block_timestamp, DEFAULT_BLOCK_PROD_TIME * 104);
+
+        // Transactions are applied at the current time.
+        runtime.advance_time(DEFAULT_BLOCK_PROD_TIME);
+        runtime
+            .resolve_tx(SignedTransaction::create_account(
+                1,
+                signer.account_id.clone(),
+                "alice".into(),
+                to_yocto("1"),
+                signer.public_key(),
+                &signer,
+                CryptoHash::default(),
+            ))
+            .unwrap();
+        assert!(runtime.current_block().block_timestamp > DEFAULT_BLOCK_PROD_TIME * 105);
+        assert!(runtime.view_account("alice").is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't go back from height 5 to 4")]
+    fn test_advance_to_lower_height() {
+        let (mut runtime, _, _) = init_runtime(None);
+        runtime.produce_blocks(5).unwrap();
+        runtime.advance_to_height(4).unwrap();
+    }
+
+    #[test]
+    fn test_epochs_and_staking() {
+        let mut genesis = GenesisConfig::default();
+        let alice = InMemorySigner::from_seed("alice", KeyType::ED25519, "alice");
+        let mut alice_account = account_new(to_yocto("100"), CryptoHash::default());
+        alice_account.locked = to_yocto("100");
+        genesis
+            .state_records
+            .push(StateRecord::Account { account_id: "alice".to_string(), account: alice_account });
+        genesis.validators.push(AccountInfo {
+            account_id: "alice".to_string(),
+            public_key: alice.public_key(),
+            amount: to_yocto("100"),
+        });
+        genesis.validator_reward_rate = (1, 100);
+        genesis.unstake_lockup_epochs = 2;
+        let (mut runtime, signer, _) = init_runtime(Some(genesis));
+        let stake = |nonce, amount| {
+            SignedTransaction::stake(
+                nonce,
+                signer.account_id.clone(),
+                &signer,
+                amount,
+                signer.public_key(),
+                CryptoHash::default(),
+            )
+        };
+        let epoch_length = runtime.genesis.epoch_length;
+        // Blocks are applied at the start of the epoch, so the first block of the next epoch
+        // has to be produced as well.
+        let finish_epoch = |runtime: &mut RuntimeStandalone| {
+            let epoch_height = runtime.current_block().epoch_height;
+            while runtime.current_block().epoch_height == epoch_height {
+                runtime.produce_block().unwrap();
+            }
+            runtime.produce_block().unwrap();
+        };
+
+        assert_eq!(runtime.current_validators().get("alice"), Some(&to_yocto("100")));
+        runtime.resolve_tx(stake(1, to_yocto("50"))).unwrap();
+        runtime.process_all().unwrap();
+        assert_eq!(runtime.view_account("root").unwrap().locked, to_yocto("50"));
+        assert_eq!(runtime.current_validators().get("root"), None);
+
+        finish_epoch(&mut runtime);
+        assert!(runtime.current_block().block_height >= epoch_length);
+        assert_eq!(runtime.current_validators().get("alice"), Some(&to_yocto("101")));
+        assert_eq!(runtime.view_account("alice").unwrap().locked, to_yocto("101"));
+        assert_eq!(runtime.current_validators().get("root"), Some(&to_yocto("50")));
+
+        let amount = runtime.view_account("root").unwrap().amount;
+        runtime.resolve_tx(stake(2, 0)).unwrap();
+        runtime.process_all().unwrap();
+        finish_epoch(&mut runtime);
+        assert_eq!(runtime.current_validators().get("root"), None);
+        // The stake is locked until the end of the lockup period.
+        assert_eq!(runtime.view_account("root").unwrap().locked, to_yocto("50"));
+        finish_epoch(&mut runtime);
+        finish_epoch(&mut runtime);
+        let root = runtime.view_account("root").unwrap();
+        assert_eq!(root.locked, 0);
+        assert!(root.amount > amount);
+        assert_eq!(
+            runtime.current_validators().get("alice"),
+            Some(&runtime.view_account("alice").unwrap().locked)
+        );
+    }
+
+    #[test]
+    fn test_parallel_runtimes() {
+        let cache = create_cache();
+        cache.insert(b"test_parallel_runtimes", b"compiled");
+        assert_eq!(create_cache().get(b"test_parallel_runtimes"), Some(b"compiled".to_vec()));
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                std::thread::spawn(move || {
+                    let (mut runtime, signer, _) = init_runtime(None);
+                    runtime
+                        .resolve_tx(SignedTransaction::create_contract(
+                            1,
+                            signer.account_id.clone(),
+                            "status".into(),
+                            include_bytes!("../../examples/status-message/res/status_message.wasm")
+                                .as_ref()
+                                .into(),
+                            to_yocto("35"),
+                            signer.public_key(),
+                            &signer,
+                            CryptoHash::default(),
+                        ))
+                        .unwrap();
+                    let (_, outcome) = runtime
+                        .resolve_tx(SignedTransaction::call(
+                            2,
+                            signer.account_id.clone(),
+                            "status".into(),
+                            &signer,
+                            0,
+                            "set_status".into(),
+                            format!("{{\"message\": \"thread {}\"}}", i).into_bytes(),
+                            300_000_000_000_000,
+                            CryptoHash::default(),
+                        ))
+                        .unwrap();
+                    assert!(matches!(outcome.status, ExecutionStatus::SuccessValue(_)));
+                    let res = runtime.view_method_call(
+                        "status",
+                        "get_status",
+                        b"{\"account_id\": \"root\"}",
+                    );
+                    assert_eq!(
+                        String::from_utf8(res.unwrap()).unwrap(),
+                        format!("\"thread {}\"", i)
+                    );
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+
     #[test]
     fn can_produce_many_blocks_without_stack_overflow() {
         let (mut runtime, _signer, _) = init_runtime(None);
@@ -1,6 +1,7 @@
 use std::cell::{Ref, RefCell, RefMut};
 use std::fmt::{Debug, Formatter};
 use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use near_crypto::{InMemorySigner, KeyType, PublicKey, Signer};
 
@@ -10,7 +11,8 @@ use near_sdk::PendingContractTx;
 use crate::runtime::init_runtime;
 pub use crate::to_yocto;
 use crate::{
-    account::{AccessKey, Account},
+    account::{AccessKey, AccessKeyPermission, Account, FunctionCallPermission},
+    errors::RuntimeError,
     hash::CryptoHash,
     outcome_into_result,
     runtime::{GenesisConfig, RuntimeStandalone},
@@ -24,6 +26,10 @@ pub const STORAGE_AMOUNT: u128 = 50_000_000_000_000_000_000_000_000;
 
 type Runtime = Rc<RefCell<RuntimeStandalone>>;
 
+/// Used to generate a unique seed for every key created by
+/// [`UserAccount::add_function_call_key`].
+static KEY_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
 /// A transaction to be signed by the user which created it. Multiple actions can be chained together
 /// and then signed and sumited to be executed.
 ///
@@ -56,10 +62,16 @@ pub struct UserTransaction {
 impl UserTransaction {
     /// Sign and execute the transaction
     pub fn submit(self) -> ExecutionResult {
-        let res =
-            (*self.runtime).borrow_mut().resolve_tx(self.transaction.sign(&self.signer)).unwrap();
-        (*self.runtime).borrow_mut().process_all().unwrap();
-        outcome_into_result(res, &self.runtime)
+        self.try_submit().unwrap()
+    }
+
+    /// Sign and execute the transaction. Returns an error if the transaction is invalid, e.g. a
+    /// function call access key is used for a method it doesn't allow or its allowance is not
+    /// enough to cover the transaction.
+    pub fn try_submit(self) -> Result<ExecutionResult, RuntimeError> {
+        let res = (*self.runtime).borrow_mut().resolve_tx(self.transaction.sign(&self.signer))?;
+        (*self.runtime).borrow_mut().process_all()?;
+        Ok(outcome_into_result(res, &self.runtime))
     }
 
     /// Create account for the receiver of the transaction.
@@ -118,6 +130,10 @@ impl UserTransaction {
 }
 
 /// A user that can sign transactions.  It includes a signer and an account id.
+///
+/// The runtime is shared by all users created from the same simulator and can't be sent to other
+/// threads. Tests running in parallel each create their own simulator with [`init_simulator`],
+/// compiled contracts are shared between all of them.
 pub struct UserAccount {
     runtime: Rc<RefCell<RuntimeStandalone>>,
     pub account_id: AccountId,
@@ -149,6 +165,95 @@ impl UserAccount {
     pub fn account(&self) -> Option<Account> {
         (*self.runtime).borrow().view_account(self.account_id.as_str())
     }
+
+    /// The balance the account must keep to cover its current storage usage.
+    pub fn storage_cost(&self) -> Balance {
+        let storage_usage = self.account().map_or(0, |account| account.storage_usage);
+        (*self.runtime).borrow().storage_cost(storage_usage)
+    }
+
+    /// Asserts that the balance of the account, including the locked balance, covers its
+    /// storage usage, e.g. after a contract refunded storage deposits.
+    pub fn assert_storage_covered(&self) {
+        let account =
+            self.account().unwrap_or_else(|| panic!("Account {} does not exist", self.account_id));
+        let storage_cost = self.storage_cost();
+        assert!(
+            account.amount + account.locked >= storage_cost,
+            "{} has {} yoctoNEAR, but its storage usage of {} bytes requires {}",
+            self.account_id,
+            account.amount + account.locked,
+            account.storage_usage,
+            storage_cost
+        );
+    }
+
+    /// Look up the access key of the signer of this user on chain.
+    pub fn access_key(&self) -> Option<AccessKey> {
+        (*self.runtime)
+            .borrow()
+            .view_access_key(self.account_id.as_str(), &self.signer.public_key())
+    }
+
+    /// Returns the remaining allowance of the access key of the signer of this user. Returns
+    /// `None` if the key has full access or an unlimited allowance.
+    pub fn allowance(&self) -> Option<Balance> {
+        match self.access_key()?.permission {
+            AccessKeyPermission::FunctionCall(permission) => permission.allowance,
+            AccessKeyPermission::FullAccess => None,
+        }
+    }
+
+    /// Returns a user for the same account which signs transactions with `signer`.
+    /// The key of the signer has to be added to the account before it can be used.
+    pub fn with_signer(&self, signer: InMemorySigner) -> UserAccount {
+        UserAccount::new(&self.runtime, self.account_id(), signer)
+    }
+
+    /// Adds a new function call access key to the account, which allows calling `method_names`
+    /// on `receiver_id` or any method if `method_names` is empty. Fees are paid from the
+    /// `allowance`, which is unlimited if `None`.
+    ///
+    /// Returns a user for the same account which signs transactions with the new key.
+    ///
+    /// # Examples
+    /// ```
+    /// use near_sdk_sim::{init_simulator, to_yocto};
+    /// let root = init_simulator(None);
+    /// let alice = root.create_user("alice".parse().unwrap(), to_yocto("100"));
+    /// let alice_app = alice.add_function_call_key(
+    ///     "app".parse().unwrap(),
+    ///     &["set_greeting"],
+    ///     Some(to_yocto("0.25")),
+    /// );
+    /// assert_eq!(alice_app.allowance(), Some(to_yocto("0.25")));
+    /// ```
+    pub fn add_function_call_key(
+        &self,
+        receiver_id: AccountId,
+        method_names: &[&str],
+        allowance: Option<Balance>,
+    ) -> UserAccount {
+        let seed = format!(
+            "{}-function-call-key-{}",
+            self.account_id,
+            KEY_COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        let signer = InMemorySigner::from_seed(self.account_id.as_str(), KeyType::ED25519, &seed);
+        let access_key = AccessKey {
+            nonce: 0,
+            permission: AccessKeyPermission::FunctionCall(FunctionCallPermission {
+                allowance,
+                receiver_id: String::from(receiver_id),
+                method_names: method_names.iter().map(|name| name.to_string()).collect(),
+            }),
+        };
+        self.submit_transaction(
+            self.transaction(self.account_id()).add_key(signer.public_key(), access_key),
+        )
+        .assert_success();
+        self.with_signer(signer)
+    }
     /// Transfer yoctoNear to another account
     pub fn transfer(&self, to: AccountId, deposit: Balance) -> ExecutionResult {
         self.submit_transaction(self.transaction(to).transfer(deposit))
@@ -356,6 +461,9 @@ impl<T> ContractAccount<T> {
 
 /// The simulator takes an optional GenesisConfig, which sets up the fees and other settings.
 /// It returns the `master_account` which can then create accounts and deploy contracts.
+///
+/// Every call creates an isolated runtime, so it can be used by tests running in parallel.
+/// Contracts deployed on any of the runtimes are only compiled once per process.
 pub fn init_simulator(genesis_config: Option<GenesisConfig>) -> UserAccount {
     let (runtime, signer, root_account_id) = init_runtime(genesis_config);
     UserAccount::new(&Rc::new(RefCell::new(runtime)), root_account_id, signer)
@@ -553,3 +661,86 @@ macro_rules! view {
         (&$contract).user_account.view_method_call((&$contract).contract.$method($($arg),*))
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::ExecutionStatus;
+
+    fn setup() -> (UserAccount, UserAccount, UserAccount) {
+        let root = init_simulator(None);
+        let status = root.deploy(
+            include_bytes!("../../examples/status-message/res/status_message.wasm"),
+            "status".parse().unwrap(),
+            to_yocto("35"),
+        );
+        let alice = root.create_user("alice".parse().unwrap(), to_yocto("100"));
+        (root, status, alice)
+    }
+
+    #[test]
+    fn function_call_key() {
+        let (_, status, alice) = setup();
+        let alice_status =
+            alice.add_function_call_key(status.account_id(), &["set_status"], Some(to_yocto("1")));
+        assert_eq!(alice_status.account_id(), alice.account_id());
+        assert_eq!(alice_status.allowance(), Some(to_yocto("1")));
+
+        alice_status
+            .call(status.account_id(), "set_status", br#"{"message": "hello"}"#, DEFAULT_GAS, 0)
+            .assert_success();
+        let allowance = alice_status.allowance().unwrap();
+        assert!(allowance < to_yocto("1"));
+        assert_eq!(alice_status.access_key().unwrap().nonce, 1);
+        assert_eq!(alice.allowance(), None);
+        assert_eq!(
+            status
+                .view(status.account_id(), "get_status", br#"{"account_id": "alice"}"#)
+                .unwrap_json::<String>(),
+            "hello"
+        );
+
+        // Method restrictions.
+        let res = alice_status
+            .create_transaction(status.account_id())
+            .function_call("get_status".to_string(), b"{}".to_vec(), DEFAULT_GAS, 0)
+            .try_submit();
+        assert!(matches!(res, Err(RuntimeError::InvalidTxError(_))));
+        let res = alice_status
+            .create_transaction(status.account_id())
+            .function_call("set_status".to_string(), br#"{"message": "hi"}"#.to_vec(), 0, 1)
+            .try_submit();
+        assert!(matches!(res, Err(RuntimeError::InvalidTxError(_))));
+        assert_eq!(alice_status.allowance(), Some(allowance));
+    }
+
+    #[test]
+    fn function_call_key_allowance() {
+        let (_, status, alice) = setup();
+        let alice_status = alice.add_function_call_key(status.account_id(), &[], Some(1));
+        let res = alice_status
+            .create_transaction(status.account_id())
+            .function_call(
+                "set_status".to_string(),
+                br#"{"message": "hi"}"#.to_vec(),
+                DEFAULT_GAS,
+                0,
+            )
+            .try_submit();
+        assert!(matches!(res, Err(RuntimeError::InvalidTxError(_))));
+
+        let alice_status = alice.add_function_call_key(status.account_id(), &[], None);
+        let res = alice_status
+            .create_transaction(status.account_id())
+            .function_call(
+                "set_status".to_string(),
+                br#"{"message": "hi"}"#.to_vec(),
+                DEFAULT_GAS,
+                0,
+            )
+            .try_submit()
+            .unwrap();
+        assert!(matches!(res.status(), ExecutionStatus::SuccessValue(_)));
+        assert_eq!(alice_status.allowance(), None);
+    }
+}
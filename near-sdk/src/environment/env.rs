@@ -234,6 +234,42 @@ pub fn keccak512(value: &[u8]) -> Vec<u8> {
     expect_register(read_register(ATOMIC_OP_REGISTER))
 }
 
+/// Recovers an ECDSA signer public key from a 32 byte message `hash` and a corresponding
+/// 64 byte `signature` along with the `v` recovery byte.
+///
+/// If `malleability_flag` is set, signatures with the `s` value in the upper half of the curve
+/// order are rejected.
+///
+/// Returns the 64 byte uncompressed public key if the recovery was successful.
+///
+/// Note: unit tests recover the key natively, but the runtime of `near-sdk-sim` (`near-vm-logic`
+/// `4.0.0-pre.1`) doesn't provide the `ecrecover` host function yet, so contracts calling it
+/// can't run in simulation tests.
+pub fn ecrecover(
+    hash: &[u8],
+    signature: &[u8],
+    v: u8,
+    malleability_flag: bool,
+) -> Option<[u8; 64]> {
+    let return_code = unsafe {
+        sys::ecrecover(
+            hash.len() as _,
+            hash.as_ptr() as _,
+            signature.len() as _,
+            signature.as_ptr() as _,
+            v as u64,
+            malleability_flag as u64,
+            ATOMIC_OP_REGISTER,
+        )
+    };
+    if return_code == 0 {
+        return None;
+    }
+    let mut public_key = [0u8; 64];
+    public_key.copy_from_slice(&expect_register(read_register(ATOMIC_OP_REGISTER)));
+    Some(public_key)
+}
+
 // ################
 // # Promises API #
 // ################
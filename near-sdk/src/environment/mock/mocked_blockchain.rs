@@ -100,6 +100,27 @@ impl MockedBlockchain {
     }
 }
 
+/// Native implementation of the `ecrecover` host function.
+fn ecrecover(hash: &[u8], signature: &[u8], v: u64, malleability_flag: bool) -> Option<[u8; 64]> {
+    use libsecp256k1::{recover, Message, RecoveryId, Signature};
+
+    if hash.len() != 32 || signature.len() != 64 || v > 3 {
+        return None;
+    }
+    let signature = Signature::parse_standard_slice(signature).ok()?;
+    if malleability_flag && signature.s.is_high() {
+        return None;
+    }
+    let recovery_id = RecoveryId::parse(v as u8).ok()?;
+    let message = Message::parse_slice(hash).ok()?;
+    let public_key = recover(&message, &signature, &recovery_id).ok()?;
+
+    // Skip the uncompressed public key prefix.
+    let mut res = [0u8; 64];
+    res.copy_from_slice(&public_key.serialize()[1..]);
+    Some(res)
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 mod mock_chain {
     use near_vm_logic::{VMLogic, VMLogicError};
@@ -192,6 +213,34 @@ mod mock_chain {
         with_mock_interface(|b| b.keccak512(value_len, value_ptr, register_id))
     }
     #[no_mangle]
+    extern "C" fn ecrecover(
+        hash_len: u64,
+        hash_ptr: u64,
+        sig_len: u64,
+        sig_ptr: u64,
+        v: u64,
+        malleability_flag: u64,
+        register_id: u64,
+    ) -> u64 {
+        // `VMLogic` doesn't implement `ecrecover` yet, so the recovery is done here and only the
+        // result is written to the register through the logic.
+        let (hash, signature) = unsafe {
+            (
+                std::slice::from_raw_parts(hash_ptr as *const u8, hash_len as usize),
+                std::slice::from_raw_parts(sig_ptr as *const u8, sig_len as usize),
+            )
+        };
+        match super::ecrecover(hash, signature, v, malleability_flag != 0) {
+            Some(public_key) => {
+                with_mock_interface(|b| {
+                    b.write_register(register_id, public_key.len() as _, public_key.as_ptr() as _)
+                });
+                1
+            }
+            None => 0,
+        }
+    }
+    #[no_mangle]
     extern "C" fn value_return(value_len: u64, value_ptr: u64) {
         with_mock_interface(|b| b.value_return(value_len, value_ptr))
     }
@@ -1,7 +1,9 @@
 use borsh::{maybestd::io, BorshDeserialize, BorshSchema, BorshSerialize};
 use serde::{de, Deserialize, Serialize};
+use std::borrow::Borrow;
 use std::convert::TryFrom;
 use std::fmt;
+use std::ops::Deref;
 
 use crate::env::is_valid_account_id;
 
@@ -38,6 +40,94 @@ use crate::env::is_valid_account_id;
 )]
 pub struct AccountId(String);
 
+/// Borrowed form of [`AccountId`], the same way `str` is the borrowed form of `String`.
+///
+/// An [`AccountId`] dereferences to an `AccountIdRef`, so all the helpers here are available on
+/// both. Comparing an [`AccountId`] with an `AccountIdRef` doesn't allocate.
+///
+/// # Examples
+/// ```
+/// use near_sdk::{AccountId, AccountIdRef};
+///
+/// let owner = AccountIdRef::new("owner.near").unwrap();
+/// let predecessor: AccountId = "owner.near".parse().unwrap();
+/// assert_eq!(predecessor, owner);
+///
+/// assert!(owner.is_sub_account_of(AccountIdRef::new("near").unwrap()));
+/// assert_eq!(owner.parent().unwrap().as_str(), "near");
+/// assert_eq!(owner.sub_account("app").unwrap().as_str(), "app.owner.near");
+/// ```
+#[derive(Debug, PartialEq, PartialOrd, Ord, Eq, Serialize, Hash)]
+#[repr(transparent)]
+pub struct AccountIdRef(str);
+
+/// The system account which can't be a top-level account.
+const SYSTEM_ACCOUNT_ID: &str = "system";
+
+/// The length of an implicit account ID, which is the hex encoded ED25519 public key.
+const IMPLICIT_ACCOUNT_ID_LEN: usize = 64;
+
+impl AccountIdRef {
+    /// Validates and converts the string to an account ID reference without allocating.
+    pub fn new(id: &str) -> Result<&Self, ParseAccountIdError> {
+        validate_account_id(id)?;
+        Ok(Self::new_unchecked(id))
+    }
+
+    /// Caller must ensure that the account id is valid.
+    ///
+    /// For more information, read: <https://docs.near.org/docs/concepts/account#account-id-rules>
+    pub fn new_unchecked(id: &str) -> &Self {
+        debug_assert!(is_valid_account_id(id.as_bytes()));
+        // Safety: `AccountIdRef` is a `#[repr(transparent)]` wrapper around `str`.
+        unsafe { &*(id as *const str as *const Self) }
+    }
+
+    /// Returns reference to the account ID bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+
+    /// Returns reference to the account ID string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns `true` if the account ID doesn't have a parent, e.g. `near` or `alice`.
+    /// The `system` account is not considered top-level.
+    pub fn is_top_level(&self) -> bool {
+        !self.0.contains('.') && &self.0 != SYSTEM_ACCOUNT_ID
+    }
+
+    /// Returns `true` if the account ID is an implicit account, which is 64 lowercase hex
+    /// characters of an ED25519 public key.
+    pub fn is_implicit(&self) -> bool {
+        self.0.len() == IMPLICIT_ACCOUNT_ID_LEN
+            && self.0.bytes().all(|b| matches!(b, b'a'..=b'f' | b'0'..=b'9'))
+    }
+
+    /// Returns `true` if this account ID is a direct sub-account of `parent`,
+    /// e.g. `alice.near` is a sub-account of `near` but `app.alice.near` is not.
+    pub fn is_sub_account_of(&self, parent: &AccountIdRef) -> bool {
+        self.parent() == Some(parent)
+    }
+
+    /// Returns the account ID this account is a direct sub-account of, or `None` if this is a
+    /// top-level account ID.
+    pub fn parent(&self) -> Option<&AccountIdRef> {
+        self.0.find('.').map(|i| Self::new_unchecked(&self.0[i + 1..]))
+    }
+
+    /// Returns the sub-account ID `{prefix}.{self}`.
+    /// Fails if `prefix` contains a `.` or the result is not a valid account ID.
+    pub fn sub_account(&self, prefix: &str) -> Result<AccountId, ParseAccountIdError> {
+        if prefix.contains('.') {
+            return Err(ParseAccountIdError {});
+        }
+        AccountId::try_from(format!("{}.{}", prefix, &self.0))
+    }
+}
+
 impl AccountId {
     /// Returns reference to the account ID bytes.
     pub fn as_bytes(&self) -> &[u8] {
@@ -62,6 +152,84 @@ impl fmt::Display for AccountId {
     }
 }
 
+impl fmt::Display for AccountIdRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl Deref for AccountId {
+    type Target = AccountIdRef;
+
+    fn deref(&self) -> &AccountIdRef {
+        AccountIdRef::new_unchecked(&self.0)
+    }
+}
+
+impl Borrow<AccountIdRef> for AccountId {
+    fn borrow(&self) -> &AccountIdRef {
+        self
+    }
+}
+
+impl ToOwned for AccountIdRef {
+    type Owned = AccountId;
+
+    fn to_owned(&self) -> AccountId {
+        AccountId(self.0.to_string())
+    }
+}
+
+impl From<&AccountIdRef> for AccountId {
+    fn from(id: &AccountIdRef) -> Self {
+        id.to_owned()
+    }
+}
+
+impl AsRef<str> for AccountIdRef {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<AccountIdRef> for AccountId {
+    fn eq(&self, other: &AccountIdRef) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl PartialEq<AccountId> for AccountIdRef {
+    fn eq(&self, other: &AccountId) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl PartialEq<&AccountIdRef> for AccountId {
+    fn eq(&self, other: &&AccountIdRef) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl PartialEq<AccountId> for &AccountIdRef {
+    fn eq(&self, other: &AccountId) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl BorshSerialize for AccountIdRef {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        BorshSerialize::serialize(&self.0, writer)
+    }
+}
+
+impl<'a> TryFrom<&'a str> for &'a AccountIdRef {
+    type Error = ParseAccountIdError;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        AccountIdRef::new(value)
+    }
+}
+
 impl From<AccountId> for String {
     fn from(id: AccountId) -> Self {
         id.0
@@ -156,6 +324,74 @@ mod tests {
         assert_eq!(key.as_ref(), &"alice.near".to_string());
     }
 
+    #[test]
+    fn test_account_id_ref() {
+        let id: AccountId = "alice.near".parse().unwrap();
+        let id_ref = AccountIdRef::new("alice.near").unwrap();
+        assert_eq!(id, id_ref);
+        assert_eq!(&*id, id_ref);
+        assert_eq!(id_ref.to_owned(), id);
+        assert!(AccountIdRef::new("Alice.near").is_err());
+
+        let mut map = std::collections::HashMap::new();
+        map.insert(id.clone(), 1);
+        assert_eq!(map.get(id_ref), Some(&1));
+
+        assert_eq!(serde_json::to_string(id_ref).unwrap(), serde_json::to_string(&id).unwrap());
+        assert_eq!(id_ref.try_to_vec().unwrap(), id.try_to_vec().unwrap());
+    }
+
+    #[test]
+    fn test_top_level() {
+        for id in &[
+            "near",
+            "alice",
+            "aa",
+            "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+        ] {
+            assert!(AccountIdRef::new_unchecked(id).is_top_level(), "{}", id);
+        }
+        for id in &["alice.near", "app.alice.near", "system"] {
+            assert!(!AccountIdRef::new_unchecked(id).is_top_level(), "{}", id);
+        }
+    }
+
+    #[test]
+    fn test_implicit() {
+        let implicit = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+        assert!(AccountIdRef::new(implicit).unwrap().is_implicit());
+        for id in &[
+            "alice.near",
+            "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcde",
+            "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdeg",
+            "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcd.e",
+        ] {
+            assert!(!AccountIdRef::new(id).unwrap().is_implicit(), "{}", id);
+        }
+    }
+
+    #[test]
+    fn test_sub_accounts() {
+        let near = AccountIdRef::new("near").unwrap();
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let app: AccountId = "app.alice.near".parse().unwrap();
+
+        assert!(alice.is_sub_account_of(near));
+        assert!(app.is_sub_account_of(&alice));
+        assert!(!app.is_sub_account_of(near));
+        assert!(!near.is_sub_account_of(&alice));
+        assert!(!AccountIdRef::new("fakenear").unwrap().is_sub_account_of(near));
+
+        assert_eq!(near.parent(), None);
+        assert_eq!(alice.parent(), Some(near));
+        assert_eq!(app.parent(), Some(&*alice));
+
+        assert_eq!(alice.sub_account("app"), Ok(app));
+        assert!(alice.sub_account("a.b").is_err());
+        assert!(alice.sub_account("").is_err());
+        assert!(alice.sub_account("App").is_err());
+    }
+
     #[test]
     fn borsh_serialize_impl() {
         let id = "test.near";
@@ -4,15 +4,21 @@ pub use self::vm_types::*;
 mod public_key;
 pub use self::public_key::{CurveType, PublicKey};
 
+mod signature;
+pub use self::signature::{ParseSignatureError, Signature};
+
 mod primitives;
 pub use self::primitives::*;
 
 mod account_id;
-pub use self::account_id::{AccountId, ParseAccountIdError};
+pub use self::account_id::{AccountId, AccountIdRef, ParseAccountIdError};
 
 mod gas;
 pub use self::gas::Gas;
 
+mod near_token;
+pub use self::near_token::{NearToken, ParseNearTokenError};
+
 /// Raw type for duration in nanoseconds
 pub type Duration = u64;
 
@@ -0,0 +1,308 @@
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+use crate::Balance;
+
+const ONE_NEAR: Balance = 10u128.pow(24);
+const ONE_MILLINEAR: Balance = 10u128.pow(21);
+const NEAR_DECIMALS: usize = 24;
+
+/// An amount of NEAR tokens, stored as yoctoNEAR (10^-24 NEAR).
+///
+/// Borsh serializes this the same as [`Balance`] and JSON serializes it as a base-10 string of
+/// yoctoNEAR, the same as [`U128`](crate::json_types::U128), so it can replace either without
+/// changing the contract interface. [`Display`] and [`FromStr`] use the human readable NEAR
+/// representation.
+///
+/// # Examples
+/// ```
+/// use near_sdk::NearToken;
+///
+/// let amount: NearToken = "1.5 NEAR".parse().unwrap();
+/// assert_eq!(amount, NearToken::from_millinear(1500));
+/// assert_eq!(amount.to_string(), "1.5 NEAR");
+///
+/// let total = amount.checked_add(NearToken::from_near(1)).unwrap();
+/// assert_eq!(total.as_yoctonear(), 2_500_000_000_000_000_000_000_000);
+/// assert_eq!(NearToken::from_yocto(1).checked_sub(total), None);
+/// ```
+///
+/// Converting to and from [`Balance`] is used to interact with the environment and promises:
+/// ```no_run
+/// use near_sdk::{env, NearToken, Promise};
+///
+/// let deposit = NearToken::from(env::attached_deposit());
+/// Promise::new(env::predecessor_account_id()).transfer(deposit.into());
+/// ```
+///
+/// [`Display`]: std::fmt::Display
+/// [`FromStr`]: std::str::FromStr
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    PartialOrd,
+    Ord,
+    Eq,
+    BorshSerialize,
+    BorshDeserialize,
+    Hash,
+    BorshSchema,
+)]
+#[repr(transparent)]
+pub struct NearToken(Balance);
+
+impl NearToken {
+    /// Creates an amount from yoctoNEAR.
+    pub const fn from_yocto(amount: Balance) -> Self {
+        Self(amount)
+    }
+
+    /// Creates an amount from milliNEAR (10^-3 NEAR).
+    pub const fn from_millinear(amount: u128) -> Self {
+        Self(amount * ONE_MILLINEAR)
+    }
+
+    /// Creates an amount from whole NEAR.
+    pub const fn from_near(amount: u128) -> Self {
+        Self(amount * ONE_NEAR)
+    }
+
+    /// Returns the amount in yoctoNEAR.
+    pub const fn as_yoctonear(&self) -> Balance {
+        self.0
+    }
+
+    /// Returns the amount in milliNEAR, rounded down.
+    pub const fn as_millinear(&self) -> u128 {
+        self.0 / ONE_MILLINEAR
+    }
+
+    /// Returns the amount in whole NEAR, rounded down.
+    pub const fn as_near(&self) -> u128 {
+        self.0 / ONE_NEAR
+    }
+
+    /// Returns `true` if the amount is zero.
+    pub const fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Checked addition. Returns `None` if overflow occurred.
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.0.checked_add(rhs.0).map(Self)
+    }
+
+    /// Checked subtraction. Returns `None` if the result would be negative.
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.0.checked_sub(rhs.0).map(Self)
+    }
+
+    /// Checked multiplication. Returns `None` if overflow occurred.
+    pub fn checked_mul(self, rhs: u128) -> Option<Self> {
+        self.0.checked_mul(rhs).map(Self)
+    }
+
+    /// Checked division. Returns `None` if `rhs == 0`.
+    pub fn checked_div(self, rhs: u128) -> Option<Self> {
+        self.0.checked_div(rhs).map(Self)
+    }
+
+    /// Saturating addition. Returns the maximum amount if overflow occurred.
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        Self(self.0.saturating_add(rhs.0))
+    }
+
+    /// Saturating subtraction. Returns zero if the result would be negative.
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+
+    /// Saturating multiplication. Returns the maximum amount if overflow occurred.
+    pub fn saturating_mul(self, rhs: u128) -> Self {
+        Self(self.0.saturating_mul(rhs))
+    }
+
+    /// Saturating division. Returns zero if `rhs == 0`.
+    pub fn saturating_div(self, rhs: u128) -> Self {
+        Self(self.0.checked_div(rhs).unwrap_or(0))
+    }
+}
+
+impl From<Balance> for NearToken {
+    fn from(amount: Balance) -> Self {
+        Self(amount)
+    }
+}
+
+impl From<NearToken> for Balance {
+    fn from(amount: NearToken) -> Self {
+        amount.0
+    }
+}
+
+impl fmt::Display for NearToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let near = self.0 / ONE_NEAR;
+        let fraction = self.0 % ONE_NEAR;
+        if fraction == 0 {
+            write!(f, "{} NEAR", near)
+        } else {
+            let fraction = format!("{:0width$}", fraction, width = NEAR_DECIMALS);
+            write!(f, "{}.{} NEAR", near, fraction.trim_end_matches('0'))
+        }
+    }
+}
+
+/// Parses a decimal amount of NEAR with an optional `NEAR` suffix, e.g. `"1.5"` or `"1.5 NEAR"`.
+impl std::str::FromStr for NearToken {
+    type Err = ParseNearTokenError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let value = value.strip_suffix("NEAR").map(str::trim_end).unwrap_or(value);
+
+        let mut parts = value.splitn(2, '.');
+        let whole = parts.next().unwrap_or_default();
+        let fraction = parts.next().unwrap_or_default();
+        let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if whole.is_empty() || !is_digits(whole) || !is_digits(fraction) {
+            return Err(ParseNearTokenError { kind: ParseNearTokenErrorKind::InvalidFormat });
+        }
+        if fraction.len() > NEAR_DECIMALS {
+            return Err(ParseNearTokenError { kind: ParseNearTokenErrorKind::TooManyDecimals });
+        }
+
+        let overflow = || ParseNearTokenError { kind: ParseNearTokenErrorKind::Overflow };
+        let whole: u128 = whole.parse().map_err(|_| overflow())?;
+        let fraction: u128 = if fraction.is_empty() {
+            0
+        } else {
+            // Can't overflow, at most 24 digits.
+            fraction.parse::<u128>().map_err(|_| overflow())?
+                * 10u128.pow((NEAR_DECIMALS - fraction.len()) as u32)
+        };
+        whole
+            .checked_mul(ONE_NEAR)
+            .and_then(|whole| whole.checked_add(fraction))
+            .map(Self)
+            .ok_or_else(overflow)
+    }
+}
+
+impl Serialize for NearToken {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.0.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for NearToken {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s: String = Deserialize::deserialize(deserializer)?;
+        s.parse::<Balance>().map(Self).map_err(|err| de::Error::custom(err.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseNearTokenError {
+    kind: ParseNearTokenErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ParseNearTokenErrorKind {
+    InvalidFormat,
+    TooManyDecimals,
+    Overflow,
+}
+
+impl fmt::Display for ParseNearTokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ParseNearTokenErrorKind::InvalidFormat => write!(f, "invalid NEAR amount format"),
+            ParseNearTokenErrorKind::TooManyDecimals => {
+                write!(f, "NEAR amount has more than {} decimals", NEAR_DECIMALS)
+            }
+            ParseNearTokenErrorKind::Overflow => write!(f, "NEAR amount is too large"),
+        }
+    }
+}
+
+impl std::error::Error for ParseNearTokenError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constructors() {
+        assert_eq!(NearToken::from_near(1).as_yoctonear(), ONE_NEAR);
+        assert_eq!(NearToken::from_millinear(1).as_yoctonear(), ONE_MILLINEAR);
+        assert_eq!(NearToken::from_millinear(2500).as_near(), 2);
+        assert_eq!(NearToken::from_near(3).as_millinear(), 3000);
+        assert!(NearToken::default().is_zero());
+    }
+
+    #[test]
+    fn math() {
+        let one = NearToken::from_near(1);
+        assert_eq!(one.checked_add(one), Some(NearToken::from_near(2)));
+        assert_eq!(NearToken::from_yocto(u128::MAX).checked_add(NearToken::from_yocto(1)), None);
+        assert_eq!(one.checked_sub(NearToken::from_near(2)), None);
+        assert_eq!(one.checked_mul(3), Some(NearToken::from_near(3)));
+        assert_eq!(one.checked_div(0), None);
+        assert_eq!(one.saturating_sub(NearToken::from_near(2)), NearToken::default());
+        assert_eq!(one.saturating_mul(u128::MAX), NearToken::from_yocto(u128::MAX));
+        assert_eq!(one.saturating_div(0), NearToken::default());
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(NearToken::from_near(10).to_string(), "10 NEAR");
+        assert_eq!(NearToken::from_millinear(1500).to_string(), "1.5 NEAR");
+        assert_eq!(NearToken::from_yocto(1).to_string(), "0.000000000000000000000001 NEAR");
+        assert_eq!(NearToken::default().to_string(), "0 NEAR");
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!("1.5".parse(), Ok(NearToken::from_millinear(1500)));
+        assert_eq!("1.5 NEAR".parse(), Ok(NearToken::from_millinear(1500)));
+        assert_eq!("35".parse(), Ok(NearToken::from_near(35)));
+        assert_eq!("0.000000000000000000000001".parse(), Ok(NearToken::from_yocto(1)));
+        assert!("".parse::<NearToken>().is_err());
+        assert!(".5".parse::<NearToken>().is_err());
+        assert!("-1".parse::<NearToken>().is_err());
+        assert!("1.5.2".parse::<NearToken>().is_err());
+        assert!("0.0000000000000000000000001".parse::<NearToken>().is_err());
+        assert!("340282366920938463463374607431768211455".parse::<NearToken>().is_err());
+
+        for amount in &[0, 1, 10u128.pow(24), 123_456_789, u128::MAX] {
+            let token = NearToken::from_yocto(*amount);
+            assert_eq!(token.to_string().parse(), Ok(token));
+        }
+    }
+
+    #[test]
+    fn json_ser() {
+        let token = NearToken::from_near(2);
+        let ser = serde_json::to_string(&token).unwrap();
+        assert_eq!(ser, "\"2000000000000000000000000\"");
+        let de: NearToken = serde_json::from_str(&ser).unwrap();
+        assert_eq!(de, token);
+    }
+
+    #[test]
+    fn borsh_ser() {
+        let token = NearToken::from_millinear(7);
+        assert_eq!(token.try_to_vec().unwrap(), token.as_yoctonear().try_to_vec().unwrap());
+    }
+}
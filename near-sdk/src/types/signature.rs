@@ -0,0 +1,357 @@
+use borsh::{maybestd::io, BorshDeserialize, BorshSerialize};
+use bs58::decode::Error as B58Error;
+use std::convert::TryFrom;
+
+use crate::{CurveType, PublicKey};
+
+/// Get the length of the signature bytes for the curve.
+const fn signature_len(curve: CurveType) -> usize {
+    match curve {
+        CurveType::ED25519 => 64,
+        CurveType::SECP256K1 => 65,
+    }
+}
+
+fn curve_from_u8(val: u8) -> Result<CurveType, ParseSignatureError> {
+    match val {
+        0 => Ok(CurveType::ED25519),
+        1 => Ok(CurveType::SECP256K1),
+        _ => Err(ParseSignatureError { kind: ParseSignatureErrorKind::UnknownCurve }),
+    }
+}
+
+/// Signature in a binary format with base58 string serialization with human-readable curve.
+/// The signature types currently supported are `secp256k1` and `ed25519`.
+///
+/// Ed25519 signatures are 64 bytes and secp256k1 signatures are 65 bytes, the 64 byte `r` and
+/// `s` values followed by the recovery byte `v`.
+///
+/// The Borsh serialization is the curve byte followed by the signature bytes, the same as
+/// `near_crypto::Signature`.
+///
+/// Verifying ed25519 signatures with [`Signature::verify`] requires the `ed25519` feature, which
+/// is enabled by default.
+///
+/// # Example
+/// ```
+/// use near_sdk::Signature;
+///
+/// let signature: Signature = "ed25519:3s1dvZdQtcAjBksMHFrysqvF63wnyMHPA4owNQmCJZ2EBakZEKdtMsLqrHdKWQjJbSRN6kRknN2WdwSBLWGCokXj"
+///             .parse()
+///             .unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Signature {
+    data: Vec<u8>,
+}
+
+impl Signature {
+    fn split_key_type_data(value: &str) -> Result<(CurveType, &str), ParseSignatureError> {
+        if let Some(idx) = value.find(':') {
+            let (prefix, data) = value.split_at(idx);
+            let curve = prefix
+                .parse::<CurveType>()
+                .map_err(|_| ParseSignatureError { kind: ParseSignatureErrorKind::UnknownCurve })?;
+            Ok((curve, &data[1..]))
+        } else {
+            // If there is no Default is ED25519.
+            Ok((CurveType::ED25519, value))
+        }
+    }
+
+    /// Creates a signature from the curve and the raw signature bytes.
+    pub fn from_parts(curve: CurveType, data: &[u8]) -> Result<Self, ParseSignatureError> {
+        let expected_length = signature_len(curve);
+        if data.len() != expected_length {
+            return Err(ParseSignatureError {
+                kind: ParseSignatureErrorKind::InvalidLength(data.len()),
+            });
+        }
+        let mut bytes = Vec::with_capacity(1 + expected_length);
+        bytes.push(curve as u8);
+        bytes.extend(data);
+
+        Ok(Self { data: bytes })
+    }
+
+    /// Returns a byte slice of this `Signature`'s contents, prefixed with the curve byte.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Converts a `Signature` into a byte vector, prefixed with the curve byte.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// Get info about the CurveType for this signature
+    pub fn curve_type(&self) -> CurveType {
+        curve_from_u8(self.data[0]).unwrap_or_else(|_| crate::env::abort())
+    }
+
+    /// Returns `true` if this is a valid signature of `message` by `public_key`.
+    ///
+    /// Ed25519 signatures are verified inside the contract, which makes it a relatively expensive
+    /// operation in terms of gas. It requires the `ed25519` feature, which is enabled by default.
+    /// Without it, verifying an ed25519 signature panics. Secp256k1 signatures are verified through the `ecrecover` host
+    /// function and `message` has to be the 32 byte hash which was signed. Malleable secp256k1
+    /// signatures are rejected. See [`env::ecrecover`](crate::env::ecrecover) for where the
+    /// host function is available.
+    ///
+    /// Returns `false` if the curves of the signature and the public key don't match.
+    pub fn verify(&self, message: &[u8], public_key: &PublicKey) -> bool {
+        if self.curve_type() != public_key.curve_type() {
+            return false;
+        }
+        let signature = &self.data[1..];
+        let public_key = &public_key.as_bytes()[1..];
+        match self.curve_type() {
+            #[cfg(feature = "ed25519")]
+            CurveType::ED25519 => {
+                use ed25519_dalek::Verifier;
+
+                let public_key = match ed25519_dalek::PublicKey::from_bytes(public_key) {
+                    Ok(public_key) => public_key,
+                    Err(_) => return false,
+                };
+                let signature = match ed25519_dalek::Signature::try_from(signature) {
+                    Ok(signature) => signature,
+                    Err(_) => return false,
+                };
+                public_key.verify(message, &signature).is_ok()
+            }
+            #[cfg(not(feature = "ed25519"))]
+            CurveType::ED25519 => {
+                crate::env::panic_str("Verifying ed25519 signatures requires the `ed25519` feature")
+            }
+            CurveType::SECP256K1 => {
+                if message.len() != 32 {
+                    return false;
+                }
+                let (signature, v) = signature.split_at(64);
+                match crate::env::ecrecover(message, signature, v[0], true) {
+                    Some(recovered) => recovered[..] == *public_key,
+                    None => false,
+                }
+            }
+        }
+    }
+}
+
+impl From<Signature> for Vec<u8> {
+    fn from(v: Signature) -> Vec<u8> {
+        v.data
+    }
+}
+
+impl TryFrom<Vec<u8>> for Signature {
+    type Error = ParseSignatureError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        if data.is_empty() {
+            return Err(ParseSignatureError {
+                kind: ParseSignatureErrorKind::InvalidLength(data.len()),
+            });
+        }
+
+        let curve = curve_from_u8(data[0])?;
+        if data.len() != signature_len(curve) + 1 {
+            return Err(ParseSignatureError {
+                kind: ParseSignatureErrorKind::InvalidLength(data.len()),
+            });
+        }
+        Ok(Self { data })
+    }
+}
+
+impl BorshSerialize for Signature {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        // Fixed size depending on the curve, so the length is not serialized.
+        writer.write_all(&self.data)
+    }
+}
+
+impl BorshDeserialize for Signature {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        let curve = <u8 as BorshDeserialize>::deserialize(buf).and_then(|c| {
+            curve_from_u8(c).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        })?;
+        let len = signature_len(curve);
+        if buf.len() < len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "unexpected end of signature",
+            ));
+        }
+        let (data, rest) = buf.split_at(len);
+        *buf = rest;
+        Self::from_parts(curve, data).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl serde::Serialize for Signature {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&String::from(self))
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Signature {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s: String = serde::Deserialize::deserialize(deserializer)?;
+        s.parse::<Signature>().map_err(serde::de::Error::custom)
+    }
+}
+
+impl From<&Signature> for String {
+    fn from(signature: &Signature) -> Self {
+        match signature.curve_type() {
+            CurveType::ED25519 => {
+                ["ed25519:", &bs58::encode(&signature.data[1..]).into_string()].concat()
+            }
+            CurveType::SECP256K1 => {
+                ["secp256k1:", &bs58::encode(&signature.data[1..]).into_string()].concat()
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for Signature {
+    type Err = ParseSignatureError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (curve, data) = Signature::split_key_type_data(value)?;
+        let data = bs58::decode(data).into_vec()?;
+        Self::from_parts(curve, &data)
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseSignatureError {
+    kind: ParseSignatureErrorKind,
+}
+
+#[derive(Debug)]
+enum ParseSignatureErrorKind {
+    InvalidLength(usize),
+    Base58(B58Error),
+    UnknownCurve,
+}
+
+impl std::fmt::Display for ParseSignatureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            ParseSignatureErrorKind::InvalidLength(l) => {
+                write!(f, "invalid length of the signature, got {}", l)
+            }
+            ParseSignatureErrorKind::Base58(e) => write!(f, "base58 decoding error: {}", e),
+            ParseSignatureErrorKind::UnknownCurve => write!(f, "unknown curve kind"),
+        }
+    }
+}
+
+impl From<B58Error> for ParseSignatureError {
+    fn from(e: B58Error) -> Self {
+        Self { kind: ParseSignatureErrorKind::Base58(e) }
+    }
+}
+
+impl std::error::Error for ParseSignatureError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::Signer;
+
+    fn ed25519_keypair() -> (ed25519_dalek::Keypair, PublicKey) {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[7u8; 32]).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        let public_key = [&[CurveType::ED25519 as u8], public.as_bytes().as_ref()].concat();
+        (ed25519_dalek::Keypair { secret, public }, PublicKey::try_from(public_key).unwrap())
+    }
+
+    fn secp256k1_sign(hash: &[u8; 32]) -> (Signature, PublicKey) {
+        let secret = libsecp256k1::SecretKey::parse(&[7u8; 32]).unwrap();
+        let public = libsecp256k1::PublicKey::from_secret_key(&secret);
+        let (signature, recovery_id) =
+            libsecp256k1::sign(&libsecp256k1::Message::parse(hash), &secret);
+        let mut data = signature.serialize().to_vec();
+        data.push(recovery_id.serialize());
+        let public_key = [&[CurveType::SECP256K1 as u8], &public.serialize()[1..]].concat();
+        (
+            Signature::from_parts(CurveType::SECP256K1, &data).unwrap(),
+            PublicKey::try_from(public_key).unwrap(),
+        )
+    }
+
+    #[test]
+    #[cfg(feature = "ed25519")]
+    fn verify_ed25519() {
+        let (keypair, public_key) = ed25519_keypair();
+        let signature =
+            Signature::from_parts(CurveType::ED25519, &keypair.sign(b"message").to_bytes())
+                .unwrap();
+        assert!(signature.verify(b"message", &public_key));
+        assert!(!signature.verify(b"other message", &public_key));
+
+        let (_, other_key) = secp256k1_sign(&[1; 32]);
+        assert!(!signature.verify(b"message", &other_key));
+    }
+
+    #[test]
+    fn verify_secp256k1() {
+        let hash = [3u8; 32];
+        let (signature, public_key) = secp256k1_sign(&hash);
+        assert!(signature.verify(&hash, &public_key));
+        assert!(!signature.verify(&[4u8; 32], &public_key));
+        assert!(!signature.verify(b"not a hash", &public_key));
+
+        let (_, other_key) = ed25519_keypair();
+        assert!(!signature.verify(&hash, &other_key));
+    }
+
+    #[test]
+    fn serde_roundtrip() {
+        let (keypair, _) = ed25519_keypair();
+        let signature =
+            Signature::from_parts(CurveType::ED25519, &keypair.sign(b"message").to_bytes())
+                .unwrap();
+        let ser = serde_json::to_string(&signature).unwrap();
+        assert!(ser.starts_with("\"ed25519:"));
+        let de: Signature = serde_json::from_str(&ser).unwrap();
+        assert_eq!(de, signature);
+
+        let (signature, _) = secp256k1_sign(&[3u8; 32]);
+        let ser = serde_json::to_string(&signature).unwrap();
+        assert!(ser.starts_with("\"secp256k1:"));
+        let de: Signature = serde_json::from_str(&ser).unwrap();
+        assert_eq!(de, signature);
+    }
+
+    #[test]
+    fn borsh_format() {
+        let (signature, _) = secp256k1_sign(&[3u8; 32]);
+        let encoded = signature.try_to_vec().unwrap();
+        assert_eq!(encoded.len(), 66);
+        assert_eq!(encoded, signature.as_bytes());
+        assert_eq!(Signature::try_from_slice(&encoded).unwrap(), signature);
+
+        assert!(Signature::try_from_slice(&encoded[..65]).is_err());
+        assert!(Signature::try_from_slice(&[2u8; 66]).is_err());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!("ed25519:abc".parse::<Signature>().is_err());
+        assert!("rsa:3s1dvZdQtcAjBksMHFrysqvF63wnyMHPA4owNQmCJZ2EBakZEKdtMsLqrHdKWQjJbSRN6kRknN2WdwSBLWGCokXj"
+            .parse::<Signature>()
+            .is_err());
+        assert!(Signature::try_from(vec![0u8; 64]).is_err());
+        assert!(Signature::try_from(vec![0u8; 65]).is_ok());
+    }
+}